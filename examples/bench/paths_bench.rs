@@ -1,14 +1,14 @@
 use gpui::{
-    Application, Background, Bounds, ColorSpace, Context, Path, PathBuilder, Pixels, Render,
-    TitlebarOptions, Window, WindowBounds, WindowOptions, canvas, div, linear_color_stop,
-    linear_gradient, point, prelude::*, px, rgb, size,
+    Application, Background, Bounds, CachedPath, ColorSpace, Context, PathBuilder, Pixels, Render,
+    TitlebarOptions, Transform, Window, WindowBounds, WindowOptions, canvas, div,
+    linear_color_stop, linear_gradient, point, prelude::*, px, rgb, size,
 };
 
 const DEFAULT_WINDOW_WIDTH: Pixels = px(1024.0);
 const DEFAULT_WINDOW_HEIGHT: Pixels = px(768.0);
 
 struct PaintingViewer {
-    default_lines: Vec<(Path<Pixels>, Background)>,
+    default_lines: Vec<(CachedPath, Background)>,
     _painting: bool,
 }
 
@@ -31,7 +31,8 @@ impl PaintingViewer {
             builder.line_to(point(px(270.), px(160.)));
             builder.line_to(point(px(330.), px(160.)));
             builder.line_to(point(px(350.), px(100.)));
-            let path = builder.build().unwrap();
+            // The stars are identical, so they share a tessellation, which is made once.
+            let path = builder.build_cached();
             lines.push((
                 path,
                 linear_gradient(
@@ -59,7 +60,9 @@ impl Render for PaintingViewer {
                 move |_, _, _| {},
                 move |_, _, window, _| {
                     for (path, color) in lines {
-                        window.paint_path(path, color);
+                        window
+                            .paint_cached_path(&path, Transform::identity(), color)
+                            .unwrap();
                     }
                 },
             )
@@ -45,20 +45,24 @@ use crate::http_stubs::{self as http_client, HttpClient, Url};
 use smallvec::SmallVec;
 #[cfg(any(test, feature = "test-support"))]
 pub use test_context::*;
-use crate::util::{ResultExt, debug_panic};
+use crate::util::{ResultExt, debug_panic, post_inc};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::HttpCache;
 #[cfg(any(feature = "inspector", debug_assertions))]
 use crate::InspectorElementRegistry;
 use crate::{
     Action, ActionBuildError, ActionRegistry, Any, AnyView, AnyWindowHandle, AppContext, Asset,
-    AssetSource, BackgroundExecutor, Bounds, ClipboardItem, CursorStyle, DispatchPhase, DisplayId,
-    EventEmitter, FocusHandle, FocusMap, ForegroundExecutor, Global, KeyBinding, KeyContext,
-    Keymap, Keystroke, LayoutId, Menu, MenuItem, OwnedMenu, PathPromptOptions, Pixels, Platform,
-    PlatformDisplay, PlatformKeyboardLayout, PlatformKeyboardMapper, Point, Priority,
+    AssetSource, BackgroundExecutor, Bounds, ClipboardItem, CursorStyle, DevicePixels,
+    DispatchPhase, DisplayId, EventEmitter, FocusHandle, FocusMap, ForegroundExecutor, Global,
+    GlobalHotkeyError, GlobalHotkeyId, Image, ImageSource, KeyBinding, KeyBindingMetaIndex,
+    KeyContext, Keymap, KeymapLoadError, Keystroke, LayoutId, Menu, MenuItem, Notification,
+    NotificationAction, NotificationHandle, NotificationId, OwnedMenu, PathPromptOptions, Pixels,
+    Platform, PlatformDisplay, PlatformKeyboardLayout, PlatformKeyboardMapper, Point, Priority,
     PromptBuilder, PromptButton, PromptHandle, PromptLevel, Render, RenderImage,
-    RenderablePromptHandle, Reservation, ScreenCaptureSource, SharedString, SubscriberSet,
-    Subscription, SvgRenderer, Task, TextSystem, Window, WindowAppearance, WindowHandle, WindowId,
-    WindowInvalidator, current_platform,
+    RenderablePromptHandle, Reservation, Resource, ScreenCaptureSource, SharedString, Size,
+    StatusItem, StatusItemId, SubscriberSet, Subscription, SvgRenderer, Task, TextSystem, Window,
+    WindowAppearance, WindowHandle, WindowId, WindowInvalidator, current_platform,
     default_colors::{Colors, GlobalColors},
     hash, init_app_menus,
 };
@@ -72,6 +76,16 @@ mod test_context;
 /// The duration for which futures returned from [Context::on_app_quit] can run before the application fully quits.
 pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(100);
 
+/// How long a window waits for the next keystroke of a multi-stroke binding by default, see
+/// [`App::set_multi_stroke_timeout`].
+pub const DEFAULT_MULTI_STROKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often [`App::watch_keymap_file`] checks whether the keymap file has changed.
+const KEYMAP_FILE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often [`App::watch_asset_changes`] checks the asset source for changed assets.
+const ASSET_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Temporary(?) wrapper around [`RefCell<App>`] to help us debug any double borrows.
 /// Strongly consider removing after stabilization.
 #[doc(hidden)]
@@ -186,6 +200,15 @@ impl Application {
         self
     }
 
+    /// Sets the cache of HTTP responses on disk for the application.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_http_cache(self, http_cache: HttpCache) -> Self {
+        let mut context_lock = self.0.borrow_mut();
+        context_lock.http_cache = Some(Arc::new(http_cache));
+        drop(context_lock);
+        self
+    }
+
     /// Configures when the application should automatically quit.
     /// By default, [`QuitMode::Default`] is used.
     pub fn with_quit_mode(self, mode: QuitMode) -> Self {
@@ -620,14 +643,23 @@ pub struct App {
     text_system: Arc<TextSystem>,
     flushing_effects: bool,
     pending_updates: usize,
+    next_global_hotkey_id: usize,
     pub(crate) actions: Rc<ActionRegistry>,
     pub(crate) active_drag: Option<AnyDrag>,
     pub(crate) background_executor: BackgroundExecutor,
     pub(crate) foreground_executor: ForegroundExecutor,
     pub(crate) loading_assets: FxHashMap<(TypeId, u64), Box<dyn Any>>,
     asset_source: Arc<dyn AssetSource>,
+    /// How many times each asset has been reloaded, which is part of the key that SVGs rendered
+    /// from it are cached with.
+    pub(crate) asset_versions: FxHashMap<SharedString, usize>,
+    /// The sizes that each image resource has been decoded at by the asset system, so that
+    /// [`crate::ImageSource::remove_asset`] can remove those decodes too.
+    pub(crate) sized_image_decodes: FxHashMap<Resource, FxHashSet<Size<DevicePixels>>>,
     pub(crate) svg_renderer: SvgRenderer,
     http_client: Arc<dyn HttpClient>,
+    #[cfg(not(target_arch = "wasm32"))]
+    http_cache: Option<Arc<HttpCache>>,
     pub(crate) globals_by_type: FxHashMap<TypeId, Box<dyn Any>>,
     pub(crate) entities: EntityMap,
     pub(crate) window_update_stack: Vec<WindowId>,
@@ -638,6 +670,7 @@ pub struct App {
     pub(crate) keymap: Rc<RefCell<Keymap>>,
     pub(crate) keyboard_layout: Box<dyn PlatformKeyboardLayout>,
     pub(crate) keyboard_mapper: Rc<dyn PlatformKeyboardMapper>,
+    pub(crate) multi_stroke_timeout: Duration,
     pub(crate) global_action_listeners:
         FxHashMap<TypeId, Vec<Rc<dyn Fn(&dyn Any, DispatchPhase, &mut Self)>>>,
     pending_effects: VecDeque<Effect>,
@@ -699,13 +732,18 @@ impl App {
                 actions: Rc::new(ActionRegistry::default()),
                 flushing_effects: false,
                 pending_updates: 0,
+                next_global_hotkey_id: 0,
                 active_drag: None,
                 background_executor: executor,
                 foreground_executor,
                 svg_renderer: SvgRenderer::new(asset_source.clone()),
                 loading_assets: Default::default(),
                 asset_source,
+                asset_versions: FxHashMap::default(),
+                sized_image_decodes: FxHashMap::default(),
                 http_client,
+                #[cfg(not(target_arch = "wasm32"))]
+                http_cache: None,
                 globals_by_type: FxHashMap::default(),
                 entities,
                 new_entity_observers: SubscriberSet::new(),
@@ -716,6 +754,7 @@ impl App {
                 keymap: Rc::new(RefCell::new(Keymap::default())),
                 keyboard_layout,
                 keyboard_mapper,
+                multi_stroke_timeout: DEFAULT_MULTI_STROKE_TIMEOUT,
                 global_action_listeners: FxHashMap::default(),
                 pending_effects: VecDeque::new(),
                 pending_notifications: FxHashSet::default(),
@@ -1254,6 +1293,18 @@ impl App {
         self.http_client = new_client;
     }
 
+    /// Returns the cache of HTTP responses on disk, if the application has one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn http_cache(&self) -> Option<Arc<HttpCache>> {
+        self.http_cache.clone()
+    }
+
+    /// Sets the cache of HTTP responses on disk, which images from URIs are loaded through.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_http_cache(&mut self, http_cache: Option<Arc<HttpCache>>) {
+        self.http_cache = http_cache;
+    }
+
     /// Configures when the application should automatically quit.
     /// By default, [`QuitMode::Default`] is used.
     pub fn set_quit_mode(&mut self, mode: QuitMode) {
@@ -1566,6 +1617,41 @@ impl App {
         &self.asset_source
     }
 
+    /// Load the SVGs and images that use the given asset paths from the asset source again, and
+    /// redraw all windows. Images held by an [`crate::ImageCache`] are loaded again once the cache
+    /// drops them.
+    pub fn reload_assets(&mut self, paths: &[SharedString]) {
+        for path in paths {
+            *self.asset_versions.entry(path.clone()).or_default() += 1;
+
+            ImageSource::Resource(Resource::Embedded(path.clone())).remove_asset(self);
+        }
+        self.refresh_windows();
+    }
+
+    /// Check the asset source for changed assets, as reported by
+    /// [`AssetSource::take_changes`], and reload them with [`Self::reload_assets`]. This is meant
+    /// for development, with a source such as [`crate::DirectoryAssets::watch_changes`].
+    ///
+    /// The asset source is checked until the returned task is dropped.
+    pub fn watch_asset_changes(&mut self) -> Task<()> {
+        let asset_source = self.asset_source.clone();
+        self.spawn(async move |cx| {
+            loop {
+                let changes = cx
+                    .background_spawn({
+                        let asset_source = asset_source.clone();
+                        async move { asset_source.take_changes() }
+                    })
+                    .await;
+                if !changes.is_empty() && cx.update(|cx| cx.reload_assets(&changes)).is_err() {
+                    break;
+                }
+                cx.background_executor().timer(ASSET_POLL_INTERVAL).await;
+            }
+        })
+    }
+
     /// Accessor for the text system.
     pub fn text_system(&self) -> &Arc<TextSystem> {
         &self.text_system
@@ -1814,6 +1900,82 @@ impl App {
         self.keymap.clone()
     }
 
+    /// Set how long windows wait for the next keystroke of a multi-stroke binding before
+    /// dispatching the keystrokes typed so far on their own. This applies to keystrokes typed
+    /// after it's set.
+    pub fn set_multi_stroke_timeout(&mut self, timeout: Duration) {
+        self.multi_stroke_timeout = timeout;
+    }
+
+    /// How long windows wait for the next keystroke of a multi-stroke binding, see
+    /// [`Self::set_multi_stroke_timeout`].
+    pub fn multi_stroke_timeout(&self) -> Duration {
+        self.multi_stroke_timeout
+    }
+
+    /// Load the key bindings of a keymap file, as described in [`Keymap::load_from_json`], and
+    /// reload them whenever the file changes. The bindings are given the `meta` index, and replace
+    /// those with the same index each time they're loaded, in the same position in the keymap.
+    /// `on_load` is called after each load with the errors in the file, if any.
+    ///
+    /// The file is watched until the returned task is dropped.
+    pub fn watch_keymap_file(
+        &mut self,
+        path: impl Into<PathBuf>,
+        meta: KeyBindingMetaIndex,
+        mut on_load: impl FnMut(Vec<KeymapLoadError>, &mut App) + 'static,
+    ) -> Task<()> {
+        let path = path.into();
+        self.spawn(async move |cx| {
+            let mut last_modified = None;
+            loop {
+                let read = cx.background_spawn({
+                    let path = path.clone();
+                    async move { anyhow::Ok(std::fs::metadata(&path)?.modified()?) }
+                });
+                // A missing file is loaded once it's created.
+                if let Ok(modified) = read.await
+                    && last_modified != Some(modified)
+                {
+                    last_modified = Some(modified);
+                    let source = cx
+                        .background_spawn({
+                            let path = path.clone();
+                            async move { std::fs::read_to_string(path) }
+                        })
+                        .await;
+                    match source {
+                        Ok(source) => {
+                            let updated = cx.update(|cx| {
+                                let mut loaded = Keymap::load_from_json(&source, cx);
+                                for binding in &mut loaded.bindings {
+                                    binding.set_meta(meta);
+                                }
+                                let mut keymap = cx.keymap.borrow_mut();
+                                keymap.replace_bindings(
+                                    |binding| binding.meta() == Some(meta),
+                                    loaded.bindings,
+                                );
+                                drop(keymap);
+                                cx.pending_effects.push_back(Effect::RefreshWindows);
+                                on_load(loaded.errors, cx);
+                            });
+                            if updated.is_err() {
+                                break;
+                            }
+                        }
+                        Err(error) => {
+                            log::error!("failed to read keymap file {path:?}: {error}");
+                        }
+                    }
+                }
+                cx.background_executor()
+                    .timer(KEYMAP_FILE_POLL_INTERVAL)
+                    .await;
+            }
+        })
+    }
+
     /// Register a global handler for actions invoked via the keyboard. These handlers are run at
     /// the end of the bubble phase for actions, and so will only be invoked if there are no other
     /// handlers or if they called `cx.propagate()`.
@@ -1936,8 +2098,8 @@ impl App {
     pub(crate) fn clear_pending_keystrokes(&mut self) {
         for window in self.windows() {
             window
-                .update(self, |_, window, _| {
-                    window.clear_pending_keystrokes();
+                .update(self, |_, window, cx| {
+                    window.cancel_pending_input(cx);
                 })
                 .ok();
         }
@@ -1980,6 +2142,119 @@ impl App {
         self.platform.perform_dock_menu_action(action);
     }
 
+    /// Adds an icon to the system tray, with the given tooltip and a menu whose items dispatch
+    /// their actions when selected. Only supported on Linux for now, where it requires a desktop
+    /// with a StatusNotifierItem host.
+    pub fn add_status_item(
+        &self,
+        icon: Image,
+        tooltip: impl Into<SharedString>,
+        menu: Menu,
+    ) -> Result<StatusItemId> {
+        let item = StatusItem {
+            icon,
+            tooltip: tooltip.into(),
+            menu,
+        };
+        let cx = self.to_async();
+        self.platform.add_status_item(
+            item,
+            Box::new(move |action| {
+                cx.update(|cx| cx.dispatch_action(action)).log_err();
+            }),
+        )
+    }
+
+    /// Replaces the icon, tooltip and menu of the given status item.
+    pub fn update_status_item(
+        &self,
+        id: StatusItemId,
+        icon: Image,
+        tooltip: impl Into<SharedString>,
+        menu: Menu,
+    ) {
+        let item = StatusItem {
+            icon,
+            tooltip: tooltip.into(),
+            menu,
+        };
+        self.platform.update_status_item(id, item);
+    }
+
+    /// Removes the given status item from the system tray.
+    pub fn remove_status_item(&self, id: StatusItemId) {
+        self.platform.remove_status_item(id);
+    }
+
+    /// Shows a desktop notification with the given buttons. The returned task resolves once the
+    /// desktop has shown it, with a handle that emits a [`crate::NotificationEvent`] when the
+    /// notification or one of its buttons is clicked.
+    pub fn show_notification(
+        &mut self,
+        title: impl Into<SharedString>,
+        body: impl Into<SharedString>,
+        actions: Vec<NotificationAction>,
+    ) -> Task<Result<Entity<NotificationHandle>>> {
+        let notification = Notification {
+            title: title.into(),
+            body: body.into(),
+            actions,
+        };
+        let mut shown = None;
+        let handle = self.new(|cx| {
+            let id = NotificationId(cx.entity_id());
+            let this = cx.weak_entity();
+            let mut async_cx = cx.to_async();
+            shown = Some(cx.platform.show_notification(
+                id,
+                notification,
+                Box::new(move |event| {
+                    this.update(&mut async_cx, |_, cx| cx.emit(event)).ok();
+                }),
+            ));
+            NotificationHandle { id }
+        });
+        let shown = shown.expect("the notification handle was just created");
+        self.foreground_executor.spawn(async move {
+            shown
+                .await
+                .map_err(|_| anyhow!("showing the notification was cancelled"))??;
+            Ok(handle)
+        })
+    }
+
+    /// Registers a system-wide hotkey that dispatches the given action whenever it's pressed,
+    /// even when none of the application's windows are focused. The returned task resolves once
+    /// the desktop has accepted the hotkey, or with [`GlobalHotkeyError::Conflict`] if it's
+    /// already in use. On Wayland, the user may be asked to confirm the binding.
+    pub fn register_global_hotkey(
+        &mut self,
+        keystroke: Keystroke,
+        action: Box<dyn Action>,
+    ) -> Task<Result<GlobalHotkeyId, GlobalHotkeyError>> {
+        let id = GlobalHotkeyId(post_inc(&mut self.next_global_hotkey_id));
+        let cx = self.to_async();
+        let registered = self.platform.register_global_hotkey(
+            id,
+            &keystroke,
+            Box::new(move || {
+                cx.update(|cx| cx.dispatch_action(action.as_ref()))
+                    .log_err();
+            }),
+        );
+        self.foreground_executor.spawn(async move {
+            registered
+                .await
+                .map_err(|_| anyhow!("global hotkey registration was cancelled"))??;
+            Ok(id)
+        })
+    }
+
+    /// Releases a hotkey registered with [`App::register_global_hotkey`].
+    pub fn unregister_global_hotkey(&mut self, id: GlobalHotkeyId) {
+        self.platform.unregister_global_hotkey(id);
+    }
+
     /// Adds given path to the bottom of the list of recent paths for the application.
     /// The list is usually shown on the application icon's context menu in the dock,
     /// and allows to open the recent files via that context menu.
@@ -2549,7 +2824,12 @@ impl<'a, T> Drop for GpuiBorrow<'a, T> {
 mod test {
     use std::{cell::RefCell, rc::Rc};
 
-    use crate::{AppContext, TestAppContext};
+    use crate::{
+        self as gpui, AppContext, GlobalHotkeyError, Keystroke, NotificationAction,
+        NotificationEvent, TestAppContext, actions,
+    };
+
+    actions!(test_only, [QuickCapture]);
 
     #[test]
     fn test_gpui_borrow() {
@@ -2581,4 +2861,75 @@ mod test {
 
         assert_eq!(*observation_count.borrow(), 2);
     }
+
+    #[gpui::test]
+    async fn test_notification_events(cx: &mut TestAppContext) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+
+        let handle = cx
+            .update(|cx| {
+                cx.show_notification(
+                    "Sync finished",
+                    "3 files were updated",
+                    vec![NotificationAction::new("open", "Open Folder")],
+                )
+            })
+            .await
+            .unwrap();
+        cx.update(|cx| {
+            cx.subscribe(&handle, {
+                let events = events.clone();
+                move |_, event: &NotificationEvent, _| events.borrow_mut().push(event.clone())
+            })
+            .detach();
+        });
+
+        let id = cx.read(|cx| handle.read(cx).id());
+        let notifications = cx.notifications();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].0, id);
+        assert_eq!(notifications[0].1.title, "Sync finished");
+        assert_eq!(notifications[0].1.actions[0].label, "Open Folder");
+
+        cx.simulate_notification_event(id, NotificationEvent::ActionInvoked("open".into()));
+        cx.simulate_notification_event(id, NotificationEvent::Activated);
+        assert_eq!(
+            *events.borrow(),
+            [
+                NotificationEvent::ActionInvoked("open".into()),
+                NotificationEvent::Activated
+            ]
+        );
+
+        cx.update(|cx| handle.update(cx, |handle, cx| handle.dismiss(cx)));
+        assert!(cx.notifications().is_empty());
+    }
+
+    #[gpui::test]
+    async fn test_global_hotkeys(cx: &mut TestAppContext) {
+        let captures = Rc::new(RefCell::new(0));
+        cx.update(|cx| {
+            let captures = captures.clone();
+            cx.on_action(move |_: &QuickCapture, _| *captures.borrow_mut() += 1);
+        });
+
+        let keystroke = Keystroke::parse("ctrl-alt-space").unwrap();
+        let id = cx
+            .update(|cx| cx.register_global_hotkey(keystroke.clone(), Box::new(QuickCapture)))
+            .await
+            .unwrap();
+        cx.simulate_global_hotkey("ctrl-alt-space");
+        cx.simulate_global_hotkey("ctrl-alt-space");
+        assert_eq!(*captures.borrow(), 2);
+
+        let conflict = cx
+            .update(|cx| cx.register_global_hotkey(keystroke.clone(), Box::new(QuickCapture)))
+            .await;
+        assert!(matches!(conflict, Err(GlobalHotkeyError::Conflict(k)) if k == keystroke));
+
+        cx.update(|cx| cx.unregister_global_hotkey(id));
+        cx.update(|cx| cx.register_global_hotkey(keystroke, Box::new(QuickCapture)))
+            .await
+            .unwrap();
+    }
 }
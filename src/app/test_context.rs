@@ -2,10 +2,10 @@ use crate::{
     Action, AnyView, AnyWindowHandle, App, AppCell, AppContext, AsyncApp, AvailableSpace,
     BackgroundExecutor, BorrowAppContext, Bounds, Capslock, ClipboardItem, DrawPhase, Drawable,
     Element, Empty, EventEmitter, ForegroundExecutor, Global, InputEvent, Keystroke, Modifiers,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels,
-    Platform, Point, Render, Result, Size, Task, TestDispatcher, TestPlatform,
-    TestScreenCaptureSource, TestWindow, TextSystem, VisualContext, Window, WindowBounds,
-    WindowHandle, WindowOptions, app::GpuiMode,
+    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Notification,
+    NotificationEvent, NotificationId, Pixels, Platform, Point, Render, Result, Size, Task,
+    TestDispatcher, TestPlatform, TestScreenCaptureSource, TestWindow, TextSystem, VisualContext,
+    Window, WindowBounds, WindowHandle, WindowOptions, app::GpuiMode,
 };
 use anyhow::{anyhow, bail};
 use futures::{Stream, StreamExt, channel::oneshot};
@@ -331,6 +331,25 @@ impl TestAppContext {
         self.test_platform.pending_prompt()
     }
 
+    /// All the notifications that are currently shown, in the order they were shown.
+    pub fn notifications(&self) -> Vec<(NotificationId, Notification)> {
+        self.test_platform.notifications()
+    }
+
+    /// Simulates the user clicking the given notification or one of its actions.
+    #[track_caller]
+    pub fn simulate_notification_event(&self, id: NotificationId, event: NotificationEvent) {
+        self.test_platform.simulate_notification_event(id, event);
+    }
+
+    /// Simulates the user pressing a hotkey registered with [`App::register_global_hotkey`],
+    /// regardless of which window is focused.
+    #[track_caller]
+    pub fn simulate_global_hotkey(&self, keystroke: &str) {
+        self.test_platform
+            .simulate_global_hotkey(&Keystroke::parse(keystroke).unwrap());
+    }
+
     /// All the urls that have been opened with cx.open_url() during this test.
     pub fn opened_url(&self) -> Option<String> {
         self.test_platform.opened_url.borrow().clone()
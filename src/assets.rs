@@ -1,12 +1,18 @@
 use crate::{DevicePixels, Pixels, Result, SharedString, Size, size};
+use anyhow::{Context as _, anyhow};
+use collections::{FxHashMap, FxHashSet};
+use parking_lot::Mutex;
 use smallvec::SmallVec;
 
 use image::{Delay, Frame};
 use std::{
     borrow::Cow,
-    fmt,
+    fmt, fs,
     hash::Hash,
+    io,
+    path::{Component, Path, PathBuf},
     sync::atomic::{AtomicUsize, Ordering::SeqCst},
+    time::SystemTime,
 };
 
 /// A source of assets for this app to use.
@@ -16,6 +22,12 @@ pub trait AssetSource: 'static + Send + Sync {
 
     /// List the assets at the given path.
     fn list(&self, path: &str) -> Result<Vec<SharedString>>;
+
+    /// Returns the paths of the assets that have changed since this was last called, for sources
+    /// that watch their assets for changes. See [`crate::App::watch_asset_changes`].
+    fn take_changes(&self) -> Vec<SharedString> {
+        Vec::new()
+    }
 }
 
 impl AssetSource for () {
@@ -28,6 +40,229 @@ impl AssetSource for () {
     }
 }
 
+/// An asset source that loads assets from a directory in the file system, where the path of each
+/// asset is relative to the directory and separated by `/`.
+pub struct DirectoryAssets {
+    root: PathBuf,
+    modified_times: Option<Mutex<FxHashMap<SharedString, Option<SystemTime>>>>,
+}
+
+impl DirectoryAssets {
+    /// Create a source that loads assets from the given directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            modified_times: None,
+        }
+    }
+
+    /// Track when the assets that are loaded are modified, so that they're reported by
+    /// [`AssetSource::take_changes`]. Use this during development, to reload assets as they're
+    /// edited.
+    pub fn watch_changes(mut self) -> Self {
+        self.modified_times = Some(Mutex::default());
+        self
+    }
+
+    /// The directory that assets are loaded from.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let relative = Path::new(path);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(anyhow!(
+                "asset path {path:?} is outside of the asset directory"
+            ));
+        }
+        Ok(self.root.join(relative))
+    }
+
+    fn list_into(
+        &self,
+        directory: &Path,
+        prefix: &str,
+        paths: &mut Vec<SharedString>,
+    ) -> Result<()> {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to list {directory:?}"));
+            }
+        };
+        for entry in entries {
+            let entry = entry?;
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            let path = format!("{prefix}{name}");
+            if entry.file_type()?.is_dir() {
+                self.list_into(&entry.path(), &format!("{path}/"), paths)?;
+            } else {
+                paths.push(path.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AssetSource for DirectoryAssets {
+    fn load(&self, path: &str) -> Result<Option<Cow<'static, [u8]>>> {
+        let full_path = self.resolve(path)?;
+        if let Some(modified_times) = &self.modified_times {
+            let modified = fs::metadata(&full_path).and_then(|m| m.modified()).ok();
+            modified_times
+                .lock()
+                .insert(SharedString::from(path.to_string()), modified);
+        }
+        match fs::read(&full_path) {
+            Ok(bytes) => Ok(Some(Cow::Owned(bytes))),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error).with_context(|| format!("failed to load asset {path:?}")),
+        }
+    }
+
+    fn list(&self, path: &str) -> Result<Vec<SharedString>> {
+        let directory = self.resolve(path)?;
+        let prefix = match path.trim_end_matches('/') {
+            "" => String::new(),
+            path => format!("{path}/"),
+        };
+        let mut paths = Vec::new();
+        self.list_into(&directory, &prefix, &mut paths)?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn take_changes(&self) -> Vec<SharedString> {
+        let Some(modified_times) = &self.modified_times else {
+            return Vec::new();
+        };
+        let mut changes = Vec::new();
+        for (path, last_modified) in modified_times.lock().iter_mut() {
+            let Ok(full_path) = self.resolve(path) else {
+                continue;
+            };
+            let modified = fs::metadata(full_path).and_then(|m| m.modified()).ok();
+            if modified != *last_modified {
+                *last_modified = modified;
+                changes.push(path.clone());
+            }
+        }
+        changes
+    }
+}
+
+/// An asset source for assets that are compiled into the app, as a table of paths and their
+/// contents, which is usually built with [`include_bytes!`]:
+///
+/// ```ignore
+/// static ASSETS: EmbeddedAssets = EmbeddedAssets::new(&[
+///     ("icons/close.svg", include_bytes!("../assets/icons/close.svg")),
+///     ("icons/open.svg", include_bytes!("../assets/icons/open.svg")),
+/// ]);
+/// ```
+///
+/// Each file is listed in the table, since directories aren't read at compile time. To embed a
+/// whole directory, implement [`AssetSource`] for a type from a crate that does, such as
+/// `rust-embed`.
+#[derive(Clone, Copy)]
+pub struct EmbeddedAssets {
+    files: &'static [(&'static str, &'static [u8])],
+}
+
+impl EmbeddedAssets {
+    /// Create a source from a table of asset paths and their contents.
+    pub const fn new(files: &'static [(&'static str, &'static [u8])]) -> Self {
+        Self { files }
+    }
+}
+
+impl AssetSource for EmbeddedAssets {
+    fn load(&self, path: &str) -> Result<Option<Cow<'static, [u8]>>> {
+        Ok(self
+            .files
+            .iter()
+            .find(|(file_path, _)| *file_path == path)
+            .map(|(_, bytes)| Cow::Borrowed(*bytes)))
+    }
+
+    fn list(&self, path: &str) -> Result<Vec<SharedString>> {
+        // As with `DirectoryAssets`, the path is that of a directory.
+        let directory = path.trim_end_matches('/');
+        Ok(self
+            .files
+            .iter()
+            .filter(|(file_path, _)| {
+                directory.is_empty()
+                    || file_path
+                        .strip_prefix(directory)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(|(file_path, _)| SharedString::new_static(file_path))
+            .collect())
+    }
+}
+
+/// An asset source made of other sources, which are checked in order for each asset, so that
+/// assets in the earlier sources take precedence. Add sources of user overrides before the
+/// bundled assets that they override.
+#[derive(Default)]
+pub struct LayeredAssets {
+    layers: Vec<Box<dyn AssetSource>>,
+}
+
+impl LayeredAssets {
+    /// Create a source without any layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source to check after the existing ones.
+    pub fn with_layer(mut self, source: impl AssetSource) -> Self {
+        self.layers.push(Box::new(source));
+        self
+    }
+}
+
+impl AssetSource for LayeredAssets {
+    fn load(&self, path: &str) -> Result<Option<Cow<'static, [u8]>>> {
+        for layer in &self.layers {
+            if let Some(bytes) = layer.load(path)? {
+                return Ok(Some(bytes));
+            }
+        }
+        Ok(None)
+    }
+
+    fn list(&self, path: &str) -> Result<Vec<SharedString>> {
+        let mut seen = FxHashSet::default();
+        let mut paths = Vec::new();
+        for layer in &self.layers {
+            for path in layer.list(path)? {
+                if seen.insert(path.clone()) {
+                    paths.push(path);
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    fn take_changes(&self) -> Vec<SharedString> {
+        let mut seen = FxHashSet::default();
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.take_changes())
+            .filter(|path| seen.insert(path.clone()))
+            .collect()
+    }
+}
+
 /// A unique identifier for the image cache
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ImageId(pub usize);
@@ -44,6 +279,9 @@ pub struct RenderImage {
     pub id: ImageId,
     /// The scale factor of this image on render.
     pub(crate) scale_factor: f32,
+    /// How much smaller the frames were decoded than the source image, so that the image is laid
+    /// out at its original size.
+    pub(crate) decode_scale: f32,
     data: SmallVec<[Frame; 1]>,
 }
 
@@ -63,6 +301,7 @@ impl RenderImage {
         Self {
             id: ImageId(NEXT_ID.fetch_add(1, SeqCst)),
             scale_factor: 1.0,
+            decode_scale: 1.0,
             data: data.into(),
         }
     }
@@ -80,10 +319,16 @@ impl RenderImage {
         size(width.into(), height.into())
     }
 
+    /// Get the size of the source image, in pixels, before it was downscaled when decoding.
+    pub(crate) fn original_size(&self, frame_index: usize) -> Size<DevicePixels> {
+        self.size(frame_index)
+            .map(|v| DevicePixels((v.0 as f32 / self.decode_scale).round() as i32))
+    }
+
     /// Get the size of this image, in pixels for display, adjusted for the scale factor.
     pub(crate) fn render_size(&self, frame_index: usize) -> Size<Pixels> {
         self.size(frame_index)
-            .map(|v| (v.0 as f32 / self.scale_factor).into())
+            .map(|v| (v.0 as f32 / (self.scale_factor * self.decode_scale)).into())
     }
 
     /// Get the delay of this frame from the previous
@@ -95,6 +340,14 @@ impl RenderImage {
     pub fn frame_count(&self) -> usize {
         self.data.len()
     }
+
+    /// Get the number of bytes that the decoded frames of this image take up in memory.
+    pub fn byte_len(&self) -> usize {
+        self.data
+            .iter()
+            .map(|frame| frame.buffer().as_raw().len())
+            .sum()
+    }
 }
 
 impl fmt::Debug for RenderImage {
@@ -105,3 +358,60 @@ impl fmt::Debug for RenderImage {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static BUNDLED: EmbeddedAssets = EmbeddedAssets::new(&[
+        ("icons/close.svg", b"bundled close"),
+        ("icons/open.svg", b"bundled open"),
+        ("icons2/open.svg", b"other open"),
+        ("fonts/mono.ttf", b"font"),
+    ]);
+
+    #[test]
+    fn test_embedded_assets_list() {
+        let list = |path| BUNDLED.list(path).unwrap();
+        let icons = ["icons/close.svg", "icons/open.svg"].map(SharedString::from);
+        assert_eq!(list("icons"), icons);
+        assert_eq!(list("icons/"), icons);
+        assert_eq!(list("icons/open.svg"), [] as [SharedString; 0]);
+        assert_eq!(list("fonts"), [SharedString::from("fonts/mono.ttf")]);
+        assert_eq!(list("").len(), 4);
+    }
+
+    #[test]
+    fn test_layered_assets() {
+        let root = std::env::temp_dir().join(format!("gpui-assets-{}", std::process::id()));
+        fs::create_dir_all(root.join("icons")).unwrap();
+        fs::write(root.join("icons/close.svg"), b"override close").unwrap();
+        fs::write(root.join("icons/extra.svg"), b"extra").unwrap();
+
+        let assets = LayeredAssets::new()
+            .with_layer(DirectoryAssets::new(&root).watch_changes())
+            .with_layer(BUNDLED);
+        let load = |path| assets.load(path).unwrap().map(|bytes| bytes.into_owned());
+        assert_eq!(load("icons/close.svg"), Some(b"override close".to_vec()));
+        assert_eq!(load("icons/open.svg"), Some(b"bundled open".to_vec()));
+        assert_eq!(load("icons/missing.svg"), None);
+        assert!(assets.load("../secrets").is_err());
+        assert_eq!(
+            assets.list("icons").unwrap(),
+            ["icons/close.svg", "icons/extra.svg", "icons/open.svg"].map(SharedString::from)
+        );
+        assert!(assets.take_changes().is_empty());
+
+        // Only the assets that have been loaded are watched for changes.
+        fs::remove_file(root.join("icons/close.svg")).unwrap();
+        fs::remove_file(root.join("icons/extra.svg")).unwrap();
+        assert_eq!(
+            assets.take_changes(),
+            [SharedString::from("icons/close.svg")]
+        );
+        assert!(assets.take_changes().is_empty());
+        assert_eq!(load("icons/close.svg"), Some(b"bundled close".to_vec()));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,348 @@
+use crate::RenderImage;
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Frames with a delay this short or shorter are shown for [`DEFAULT_FRAME_DELAY`] instead, as
+/// browsers do, so that images whose frames have no delay don't play as fast as they can be drawn.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(10);
+
+/// How long frames with too short a delay are shown for.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// The slowest speed that an image can play at, see [`AnimatedImageHandle::set_speed`].
+pub const MIN_ANIMATED_IMAGE_SPEED: f32 = 1. / 64.;
+
+/// The fastest speed that an image can play at, see [`AnimatedImageHandle::set_speed`].
+pub const MAX_ANIMATED_IMAGE_SPEED: f32 = 64.;
+
+/// A handle to the playback of an animated image, such as a GIF, used to play, pause or seek it.
+/// Pass it to [`crate::Img::track_animation`].
+///
+/// The handle doesn't redraw the image when it's changed, so notify the view that shows it, as
+/// with [`crate::ScrollHandle`].
+#[derive(Clone, Debug, Default)]
+pub struct AnimatedImageHandle(Rc<RefCell<AnimatedImageState>>);
+
+#[derive(Debug)]
+struct AnimatedImageState {
+    playing: bool,
+    frame_index: usize,
+    frame_count: usize,
+    speed: f32,
+    loop_count: Option<u32>,
+    loops_completed: u32,
+    last_frame_time: Option<Instant>,
+    reported_frame_index: Option<usize>,
+}
+
+impl Default for AnimatedImageState {
+    fn default() -> Self {
+        Self {
+            playing: true,
+            frame_index: 0,
+            frame_count: 0,
+            speed: 1.,
+            loop_count: None,
+            loops_completed: 0,
+            last_frame_time: None,
+            reported_frame_index: None,
+        }
+    }
+}
+
+impl AnimatedImageState {
+    fn finished_looping(&self) -> bool {
+        self.loop_count
+            .is_some_and(|loop_count| self.loops_completed >= loop_count)
+    }
+}
+
+/// An event for an animated image showing a different frame, see [`crate::Img::on_frame_change`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameChangeEvent {
+    /// The index of the frame that's shown
+    pub frame_index: usize,
+    /// The number of frames in the image
+    pub frame_count: usize,
+}
+
+impl AnimatedImageHandle {
+    /// Construct a new handle, which plays the image from its first frame, looping forever.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Play the image from the frame it's on. If it stopped because it looped as many times as
+    /// it should, it's played again from its first frame.
+    pub fn play(&self) {
+        let mut state = self.0.borrow_mut();
+        if state.playing {
+            return;
+        }
+        if state.finished_looping() {
+            state.loops_completed = 0;
+            state.frame_index = 0;
+        }
+        state.playing = true;
+    }
+
+    /// Pause the image on the frame it's on.
+    pub fn pause(&self) {
+        let mut state = self.0.borrow_mut();
+        state.playing = false;
+        state.last_frame_time = None;
+    }
+
+    /// Whether the image is playing.
+    pub fn is_playing(&self) -> bool {
+        self.0.borrow().playing
+    }
+
+    /// Show the frame at the given index, or the last frame if there aren't that many, for the
+    /// whole of its delay.
+    pub fn seek(&self, frame_index: usize) {
+        let mut state = self.0.borrow_mut();
+        state.frame_index = frame_index;
+        state.last_frame_time = None;
+    }
+
+    /// The index of the frame that's shown.
+    pub fn frame_index(&self) -> usize {
+        self.0.borrow().frame_index
+    }
+
+    /// The number of frames in the image, which is zero until it has loaded.
+    pub fn frame_count(&self) -> usize {
+        self.0.borrow().frame_count
+    }
+
+    /// Set how fast the image plays, where 2.0 is twice as fast as the delays of its frames and
+    /// 0.5 is half as fast. Use [`Self::pause`] to stop it.
+    ///
+    /// The speed is clamped to between [`MIN_ANIMATED_IMAGE_SPEED`] and
+    /// [`MAX_ANIMATED_IMAGE_SPEED`], and a NaN speed plays the image at its normal speed.
+    pub fn set_speed(&self, speed: f32) {
+        self.0.borrow_mut().speed = if speed.is_nan() {
+            1.
+        } else {
+            speed.clamp(MIN_ANIMATED_IMAGE_SPEED, MAX_ANIMATED_IMAGE_SPEED)
+        };
+    }
+
+    /// How fast the image plays, see [`Self::set_speed`].
+    pub fn speed(&self) -> f32 {
+        self.0.borrow().speed
+    }
+
+    /// Set how many times the image plays through before it stops on its last frame, or `None`
+    /// to loop forever.
+    pub fn set_loop_count(&self, loop_count: Option<u32>) {
+        let mut state = self.0.borrow_mut();
+        state.loop_count = loop_count;
+        state.loops_completed = 0;
+    }
+
+    /// How many times the image plays through, see [`Self::set_loop_count`].
+    pub fn loop_count(&self) -> Option<u32> {
+        self.0.borrow().loop_count
+    }
+
+    /// Advances playback of the given image to the current time, returning the index of the frame
+    /// to show and whether it's still playing.
+    pub(crate) fn advance(&self, image: &RenderImage) -> (usize, bool) {
+        self.advance_to(image, Instant::now())
+    }
+
+    fn advance_to(&self, image: &RenderImage, now: Instant) -> (usize, bool) {
+        let mut state = self.0.borrow_mut();
+        let frame_count = image.frame_count();
+        state.frame_count = frame_count;
+        state.frame_index = state.frame_index.min(frame_count.saturating_sub(1));
+        if !state.playing || frame_count <= 1 {
+            state.last_frame_time = None;
+            return (state.frame_index, false);
+        }
+
+        let Some(last_frame_time) = state.last_frame_time else {
+            state.last_frame_time = Some(now);
+            return (state.frame_index, true);
+        };
+        let mut elapsed = now
+            .saturating_duration_since(last_frame_time)
+            .mul_f32(state.speed);
+
+        // Skip whole loops at once, so that catching up after a long time away doesn't step
+        // through every frame that would have been shown.
+        let loop_duration = (0..frame_count)
+            .map(|frame_index| frame_delay(image, frame_index))
+            .sum::<Duration>();
+        let skipped_loops = elapsed.as_nanos() / loop_duration.as_nanos();
+        if skipped_loops > 0 {
+            elapsed = Duration::from_nanos((elapsed.as_nanos() % loop_duration.as_nanos()) as u64);
+            state.loops_completed = state
+                .loops_completed
+                .saturating_add(u32::try_from(skipped_loops).unwrap_or(u32::MAX));
+            if state.finished_looping() {
+                state.frame_index = frame_count - 1;
+                state.playing = false;
+                state.last_frame_time = None;
+                return (state.frame_index, false);
+            }
+        }
+
+        loop {
+            let frame_delay = frame_delay(image, state.frame_index);
+            if elapsed < frame_delay {
+                break;
+            }
+            elapsed -= frame_delay;
+
+            if state.frame_index + 1 < frame_count {
+                state.frame_index += 1;
+                continue;
+            }
+            state.loops_completed += 1;
+            if state.finished_looping() {
+                state.playing = false;
+                state.last_frame_time = None;
+                return (state.frame_index, false);
+            }
+            state.frame_index = 0;
+        }
+        state.last_frame_time = Some(now - elapsed.div_f32(state.speed));
+        (state.frame_index, true)
+    }
+
+    /// Returns an event if the frame that's shown has changed since this was last called. The
+    /// first frame that's shown isn't a change.
+    pub(crate) fn take_frame_change(&self) -> Option<FrameChangeEvent> {
+        let mut state = self.0.borrow_mut();
+        let frame_index = state.frame_index;
+        let previous = state.reported_frame_index.replace(frame_index);
+        (previous? != frame_index).then(|| FrameChangeEvent {
+            frame_index,
+            frame_count: state.frame_count,
+        })
+    }
+}
+
+fn frame_delay(image: &RenderImage, frame_index: usize) -> Duration {
+    let delay = Duration::from(image.delay(frame_index));
+    if delay <= MIN_FRAME_DELAY {
+        DEFAULT_FRAME_DELAY
+    } else {
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Delay, Frame, RgbaImage};
+    use smallvec::SmallVec;
+
+    fn animated_image(frame_count: usize) -> RenderImage {
+        animated_image_with_delay(frame_count, Duration::from_millis(100))
+    }
+
+    fn animated_image_with_delay(frame_count: usize, delay: Duration) -> RenderImage {
+        let frames = (0..frame_count)
+            .map(|_| {
+                let delay = Delay::from_saturating_duration(delay);
+                Frame::from_parts(RgbaImage::new(1, 1), 0, 0, delay)
+            })
+            .collect::<SmallVec<_>>();
+        RenderImage::new(frames)
+    }
+
+    #[test]
+    fn test_animated_image_playback() {
+        let image = animated_image(3);
+        let handle = AnimatedImageHandle::new();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        assert_eq!(handle.advance_to(&image, at(0)), (0, true));
+        assert_eq!(handle.take_frame_change(), None);
+        assert_eq!(handle.advance_to(&image, at(250)), (2, true));
+        assert_eq!(
+            handle.take_frame_change(),
+            Some(FrameChangeEvent {
+                frame_index: 2,
+                frame_count: 3,
+            })
+        );
+        // Playback loops, keeping the time left over from the previous frame.
+        assert_eq!(handle.advance_to(&image, at(310)), (0, true));
+
+        handle.set_speed(2.);
+        assert_eq!(handle.advance_to(&image, at(360)), (1, true));
+
+        handle.pause();
+        assert_eq!(handle.advance_to(&image, at(1000)), (1, false));
+        handle.seek(10);
+        assert_eq!(handle.advance_to(&image, at(1000)), (2, false));
+
+        // Images stop on their last frame once they've looped as many times as they should.
+        handle.set_speed(1.);
+        handle.set_loop_count(Some(1));
+        handle.seek(0);
+        handle.play();
+        assert_eq!(handle.advance_to(&image, at(2000)), (0, true));
+        assert_eq!(handle.advance_to(&image, at(2500)), (2, false));
+        assert!(!handle.is_playing());
+
+        handle.play();
+        assert_eq!(handle.advance_to(&image, at(3000)), (0, true));
+    }
+
+    #[test]
+    fn test_short_frame_delays() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // Frames with delays of 10ms or less are shown for 100ms, as they are in browsers.
+        for delay in [0, 10] {
+            let image = animated_image_with_delay(3, Duration::from_millis(delay));
+            let handle = AnimatedImageHandle::new();
+            assert_eq!(handle.advance_to(&image, at(0)), (0, true));
+            assert_eq!(handle.advance_to(&image, at(99)), (0, true));
+            assert_eq!(handle.advance_to(&image, at(100)), (1, true));
+        }
+
+        let image = animated_image_with_delay(3, Duration::from_millis(20));
+        let handle = AnimatedImageHandle::new();
+        assert_eq!(handle.advance_to(&image, at(0)), (0, true));
+        assert_eq!(handle.advance_to(&image, at(20)), (1, true));
+    }
+
+    #[test]
+    fn test_animated_image_catch_up() {
+        let image = animated_image(3);
+        let handle = AnimatedImageHandle::new();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // Whole loops are skipped, keeping the time into the current one.
+        assert_eq!(handle.advance_to(&image, at(0)), (0, true));
+        assert_eq!(handle.advance_to(&image, at(50)), (0, true));
+        assert_eq!(handle.advance_to(&image, at(3_000_000_260)), (2, true));
+        assert_eq!(handle.advance_to(&image, at(3_000_000_310)), (0, true));
+
+        // Images still stop once they've looped as many times as they should.
+        handle.set_loop_count(Some(2));
+        assert_eq!(handle.advance_to(&image, at(3_000_000_900)), (2, false));
+        assert!(!handle.is_playing());
+
+        handle.set_speed(f32::INFINITY);
+        assert_eq!(handle.speed(), MAX_ANIMATED_IMAGE_SPEED);
+        handle.set_speed(0.);
+        assert_eq!(handle.speed(), MIN_ANIMATED_IMAGE_SPEED);
+        handle.set_speed(f32::NAN);
+        assert_eq!(handle.speed(), 1.);
+    }
+}
@@ -0,0 +1,260 @@
+use super::menu::{
+    MenuOwner, OpenMenus, choose, find_mnemonic, handle_navigation_key, item_name, label,
+    mnemonics, render_menu, selectable_items,
+};
+use crate::{
+    App, Colors, Component, Context, ElementId, InteractiveElement, IntoElement, Keystroke,
+    MouseButton, OwnedMenu, ParentElement, RenderOnce, StatefulInteractiveElement, Styled,
+    Subscription, Window, anchored, deferred, div, util::FluentBuilder,
+};
+
+/// Creates a menu bar that shows the application menus set with [`App::set_menus`] within the
+/// window, for platforms that don't display them in a global menu bar, such as Linux.
+pub fn app_menu_bar(id: impl Into<ElementId>) -> AppMenuBar {
+    AppMenuBar { id: id.into() }
+}
+
+/// A menu bar rendered within the window, see [`app_menu_bar`].
+///
+/// Menus are opened by clicking their title, by pressing Alt together with the underlined letter
+/// of their name, or with F10, and are navigated with the arrow keys. Items show the keystrokes
+/// bound to their action, are disabled while their action isn't available, and dispatch their
+/// action to the focused element when chosen.
+pub struct AppMenuBar {
+    id: ElementId,
+}
+
+impl IntoElement for AppMenuBar {
+    type Element = Component<Self>;
+
+    fn into_element(self) -> Self::Element {
+        Component::new(self)
+    }
+}
+
+impl RenderOnce for AppMenuBar {
+    fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let state = window.use_keyed_state(self.id.clone(), cx, AppMenuBarState::new);
+        if !window.is_window_active() && state.read(cx).open.is_open() {
+            state.update(cx, |state, cx| state.close(cx));
+        }
+
+        let menus = cx.get_menus().unwrap_or_default();
+        let colors = Colors::for_appearance(window);
+        let open = state.read(cx).open.clone();
+        let show_mnemonics = open.is_open() || window.modifiers().alt;
+        let mnemonics = mnemonics(menus.iter().map(|menu| menu.name.as_ref()));
+
+        let mut titles = Vec::with_capacity(menus.len());
+        for (ix, menu) in menus.iter().enumerate() {
+            let is_open = open.open_path.first() == Some(&ix);
+            let panel =
+                is_open.then(|| render_menu(&state, menu, 0, &open, true, &colors, window, cx));
+            titles.push(
+                div()
+                    .id(ix)
+                    .relative()
+                    .px_2()
+                    .py_0p5()
+                    .rounded_sm()
+                    .map(|this| {
+                        if is_open {
+                            this.bg(colors.selected).text_color(colors.selected_text)
+                        } else {
+                            this.hover(|style| style.bg(colors.surface_hover))
+                        }
+                    })
+                    .child(label(
+                        menu.name.clone(),
+                        mnemonics[ix]
+                            .as_ref()
+                            .filter(|_| show_mnemonics)
+                            .map(|mnemonic| mnemonic.range.clone()),
+                    ))
+                    .on_mouse_down(MouseButton::Left, {
+                        let state = state.clone();
+                        move |_, window, cx| {
+                            state.update(cx, |state, cx| {
+                                if state.open.open_path.first() == Some(&ix) {
+                                    state.close(cx);
+                                } else {
+                                    state.open(ix, false, window, cx);
+                                }
+                            })
+                        }
+                    })
+                    .on_hover({
+                        let state = state.clone();
+                        move |hovered, window, cx| {
+                            state.update(cx, |state, cx| {
+                                if *hovered
+                                    && state.open.is_open()
+                                    && state.open.open_path.first() != Some(&ix)
+                                {
+                                    state.open(ix, false, window, cx);
+                                }
+                            })
+                        }
+                    })
+                    .children(panel.map(|panel| {
+                        div()
+                            .absolute()
+                            .top_full()
+                            .left_0()
+                            .child(deferred(anchored().child(panel)).with_priority(1))
+                    })),
+            );
+        }
+
+        div()
+            .id(self.id)
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_0p5()
+            .px_1()
+            .text_sm()
+            .text_color(colors.text)
+            .bg(colors.surface)
+            .on_hover({
+                let state = state.clone();
+                move |hovered, _, cx| {
+                    state.update(cx, |state, _| state.bar_hovered = *hovered);
+                }
+            })
+            .children(titles)
+    }
+}
+
+/// The state of an [`AppMenuBar`], kept across frames.
+struct AppMenuBarState {
+    open: OpenMenus,
+    bar_hovered: bool,
+    _keystroke_interceptor: Subscription,
+}
+
+impl AppMenuBarState {
+    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let this = cx.weak_entity();
+        let window_handle = window.window_handle();
+        // Keystrokes are intercepted rather than handled by a focused element, so that menus can
+        // be opened and navigated without taking focus away from the element that their actions
+        // are dispatched to.
+        let keystroke_interceptor = cx.intercept_keystrokes(move |event, window, cx| {
+            if window.window_handle() != window_handle {
+                return;
+            }
+            let handled = this
+                .update(cx, |state, cx| {
+                    state.handle_keystroke(&event.keystroke, window, cx)
+                })
+                .unwrap_or(false);
+            if handled {
+                cx.stop_propagation();
+            }
+        });
+
+        Self {
+            open: OpenMenus::default(),
+            bar_hovered: false,
+            _keystroke_interceptor: keystroke_interceptor,
+        }
+    }
+
+    fn open(
+        &mut self,
+        ix: usize,
+        highlight_first: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.open.is_open() {
+            // Matches what native menus do when they're about to open.
+            window.cancel_pending_input(cx);
+        }
+        self.open.open_path = vec![ix];
+        self.open.highlighted = None;
+        if highlight_first {
+            let menus = cx.get_menus().unwrap_or_default();
+            if let Some(menu) = menus.get(ix) {
+                self.open.highlighted = selectable_items(menu, window, cx).first().copied();
+            }
+        }
+        cx.notify();
+    }
+
+    /// Handles a keystroke in this menu bar's window, returning whether it was consumed.
+    fn handle_keystroke(
+        &mut self,
+        keystroke: &Keystroke,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let menus = cx.get_menus().unwrap_or_default();
+        if menus.is_empty() {
+            return false;
+        }
+
+        let modifiers = &keystroke.modifiers;
+        if !self.open.is_open() {
+            if keystroke.key == "f10" && !modifiers.modified() {
+                self.open(0, true, window, cx);
+                return true;
+            }
+            if modifiers.alt && !modifiers.control && !modifiers.platform {
+                let mnemonics = mnemonics(menus.iter().map(|menu| menu.name.as_ref()));
+                if let Some(ix) = find_mnemonic(&mnemonics, &keystroke.key) {
+                    self.open(ix, true, window, cx);
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        if handle_navigation_key(self, &keystroke.key, window, cx) {
+            return true;
+        }
+        let Some(menu) = self.open.innermost_menu(&menus) else {
+            self.close(cx);
+            return false;
+        };
+        let top_ix = self.open.open_path[0];
+        match keystroke.key.as_str() {
+            "left" => self.open((top_ix + menus.len() - 1) % menus.len(), true, window, cx),
+            "right" => self.open((top_ix + 1) % menus.len(), true, window, cx),
+            key => {
+                if !modifiers.control && !modifiers.platform {
+                    let mnemonics = mnemonics(menu.items.iter().map(item_name));
+                    if let Some(ix) = find_mnemonic(&mnemonics, key) {
+                        choose(self, ix, window, cx);
+                    }
+                }
+            }
+        }
+        // While a menu is open, it takes all keystrokes, like a native menu would.
+        true
+    }
+}
+
+impl MenuOwner for AppMenuBarState {
+    fn open_menus(&self) -> &OpenMenus {
+        &self.open
+    }
+
+    fn open_menus_mut(&mut self) -> &mut OpenMenus {
+        &mut self.open
+    }
+
+    fn menus(&self, cx: &App) -> Vec<OwnedMenu> {
+        cx.get_menus().unwrap_or_default()
+    }
+
+    fn is_owner_hovered(&self) -> bool {
+        self.bar_hovered
+    }
+
+    fn close(&mut self, cx: &mut Context<Self>) {
+        self.open.clear();
+        cx.notify();
+    }
+}
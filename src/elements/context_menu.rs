@@ -0,0 +1,313 @@
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use super::menu::{
+    MenuOwner, OpenMenus, handle_navigation_key, item_name, render_menu, selectable_items,
+};
+use crate::{
+    AnyElement, App, Colors, Component, Context, Div, ElementId, InteractiveElement, IntoElement,
+    Keystroke, MenuItem, MouseButton, OwnedMenu, ParentElement, Pixels, Point, RenderOnce,
+    StatefulInteractiveElement, StyleRefinement, Styled, Subscription, Window, anchored, deferred,
+    div, point, px, util::FluentBuilder,
+};
+
+/// How long after the last typed character the next one starts a new search.
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Creates an element that shows a menu with the given items where it's right-clicked. The
+/// items are built each time the menu is opened, from the same [`MenuItem`]s as the
+/// application menus.
+pub fn context_menu(
+    id: impl Into<ElementId>,
+    items: impl Fn(&mut Window, &mut App) -> Vec<MenuItem> + 'static,
+) -> ContextMenu {
+    ContextMenu {
+        id: id.into(),
+        items: Rc::new(items),
+        base: div(),
+    }
+}
+
+/// An element that shows a menu where it's right-clicked, see [`context_menu`].
+///
+/// The menu is navigated with the arrow keys, or by typing the start of an item's name. Items
+/// show the keystrokes bound to their action, are disabled while their action isn't available,
+/// and dispatch their action to the focused element when chosen.
+pub struct ContextMenu {
+    id: ElementId,
+    items: Rc<dyn Fn(&mut Window, &mut App) -> Vec<MenuItem>>,
+    base: Div,
+}
+
+impl ParentElement for ContextMenu {
+    fn extend(&mut self, elements: impl IntoIterator<Item = AnyElement>) {
+        self.base.extend(elements)
+    }
+}
+
+impl Styled for ContextMenu {
+    fn style(&mut self) -> &mut StyleRefinement {
+        self.base.style()
+    }
+}
+
+impl IntoElement for ContextMenu {
+    type Element = Component<Self>;
+
+    fn into_element(self) -> Self::Element {
+        Component::new(self)
+    }
+}
+
+impl RenderOnce for ContextMenu {
+    fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let state = window.use_keyed_state(self.id.clone(), cx, ContextMenuState::new);
+        if !window.is_window_active() && state.read(cx).open.is_open() {
+            state.update(cx, |state, cx| state.close(cx));
+        }
+
+        let colors = Colors::for_appearance(window);
+        let (open, menu, position) = {
+            let state = state.read(cx);
+            (state.open.clone(), state.menu.clone(), state.position)
+        };
+        let panel = menu
+            .filter(|_| open.is_open())
+            .map(|menu| render_menu(&state, &menu, 0, &open, false, &colors, window, cx));
+
+        self.base
+            .id(self.id)
+            .on_hover({
+                let state = state.clone();
+                move |hovered, _, cx| {
+                    state.update(cx, |state, _| state.owner_hovered = *hovered);
+                }
+            })
+            .on_mouse_down(MouseButton::Right, {
+                let state = state.clone();
+                let items = self.items.clone();
+                move |event, window, cx| {
+                    let items = items(window, cx);
+                    state.update(cx, |state, cx| {
+                        state.open_at(event.position, items, window, cx)
+                    });
+                    // Only the innermost context menu opens.
+                    cx.stop_propagation();
+                }
+            })
+            .on_mouse_down(MouseButton::Left, {
+                let state = state.clone();
+                move |_, _, cx| {
+                    state.update(cx, |state, cx| {
+                        if state.open.is_open() {
+                            state.close(cx);
+                        }
+                    })
+                }
+            })
+            .when_some(panel, |this, panel| {
+                this.child(
+                    deferred(
+                        anchored()
+                            .position(position)
+                            .snap_to_window_with_margin(px(8.))
+                            .child(div().text_sm().child(panel)),
+                    )
+                    .with_priority(1),
+                )
+            })
+    }
+}
+
+/// The state of a [`ContextMenu`], kept across frames.
+struct ContextMenuState {
+    open: OpenMenus,
+    /// The menu that's open, built from the items when it was opened.
+    menu: Option<OwnedMenu>,
+    position: Point<Pixels>,
+    owner_hovered: bool,
+    typed: String,
+    last_typed_at: Option<Instant>,
+    _keystroke_interceptor: Subscription,
+}
+
+impl ContextMenuState {
+    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let this = cx.weak_entity();
+        let window_handle = window.window_handle();
+        // Like the app menu bar, this leaves focus on the element that actions are dispatched to.
+        let keystroke_interceptor = cx.intercept_keystrokes(move |event, window, cx| {
+            if window.window_handle() != window_handle {
+                return;
+            }
+            let handled = this
+                .update(cx, |state, cx| {
+                    state.handle_keystroke(&event.keystroke, window, cx)
+                })
+                .unwrap_or(false);
+            if handled {
+                cx.stop_propagation();
+            }
+        });
+
+        Self {
+            open: OpenMenus::default(),
+            menu: None,
+            position: point(px(0.), px(0.)),
+            owner_hovered: false,
+            typed: String::new(),
+            last_typed_at: None,
+            _keystroke_interceptor: keystroke_interceptor,
+        }
+    }
+
+    fn open_at(
+        &mut self,
+        position: Point<Pixels>,
+        items: Vec<MenuItem>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if items.is_empty() {
+            self.close(cx);
+            return;
+        }
+        window.cancel_pending_input(cx);
+        self.menu = Some(OwnedMenu {
+            name: "".into(),
+            items: items.into_iter().map(MenuItem::owned).collect(),
+        });
+        self.position = position;
+        self.open.clear();
+        self.open.open_path.push(0);
+        self.typed.clear();
+        cx.notify();
+    }
+
+    /// Handles a keystroke in this menu's window, returning whether it was consumed.
+    fn handle_keystroke(
+        &mut self,
+        keystroke: &Keystroke,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if !self.open.is_open() {
+            return false;
+        }
+        if handle_navigation_key(self, &keystroke.key, window, cx) {
+            self.typed.clear();
+            return true;
+        }
+
+        let typed_char = keystroke
+            .key_char
+            .as_deref()
+            .filter(|_| !keystroke.modifiers.control && !keystroke.modifiers.platform)
+            .filter(|key_char| key_char.chars().all(|c| !c.is_control()));
+        if let Some(key_char) = typed_char {
+            let now = Instant::now();
+            if self
+                .last_typed_at
+                .is_none_or(|last_typed_at| now - last_typed_at > TYPE_AHEAD_TIMEOUT)
+            {
+                self.typed.clear();
+            }
+            self.typed.push_str(key_char);
+            self.last_typed_at = Some(now);
+
+            let menus = self.menus(cx);
+            if let Some(menu) = self.open.innermost_menu(&menus) {
+                let items = selectable_items(menu, window, cx)
+                    .into_iter()
+                    .map(|ix| (ix, item_name(&menu.items[ix])))
+                    .collect::<Vec<_>>();
+                if let Some(ix) = type_ahead_target(&items, &self.typed, self.open.highlighted) {
+                    self.open.highlighted = Some(ix);
+                    cx.notify();
+                }
+            }
+        }
+        // While the menu is open, it takes all keystrokes, like a native menu would.
+        true
+    }
+}
+
+impl MenuOwner for ContextMenuState {
+    fn open_menus(&self) -> &OpenMenus {
+        &self.open
+    }
+
+    fn open_menus_mut(&mut self) -> &mut OpenMenus {
+        &mut self.open
+    }
+
+    fn menus(&self, _: &App) -> Vec<OwnedMenu> {
+        self.menu.iter().cloned().collect()
+    }
+
+    fn is_owner_hovered(&self) -> bool {
+        self.owner_hovered
+    }
+
+    fn close(&mut self, cx: &mut Context<Self>) {
+        self.open.clear();
+        self.menu = None;
+        self.typed.clear();
+        cx.notify();
+    }
+}
+
+/// Finds the item whose name starts with the typed text. Typing the same character repeatedly
+/// cycles through the items that start with it, beginning after the highlighted one.
+fn type_ahead_target(
+    items: &[(usize, &str)],
+    typed: &str,
+    highlighted: Option<usize>,
+) -> Option<usize> {
+    let typed = typed.to_lowercase();
+    let first_char = typed.chars().next()?;
+    let cycling = typed.chars().all(|c| c == first_char);
+    let query = if cycling {
+        first_char.to_string()
+    } else {
+        typed
+    };
+
+    let highlighted_position =
+        highlighted.and_then(|highlighted| items.iter().position(|(ix, _)| *ix == highlighted));
+    let start = match highlighted_position {
+        Some(position) if cycling => position + 1,
+        Some(position) => position,
+        None => 0,
+    };
+    (0..items.len())
+        .map(|offset| &items[(start + offset) % items.len()])
+        .find(|(_, name)| name.to_lowercase().starts_with(&query))
+        .map(|(ix, _)| *ix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_ahead_target() {
+        let items = [(0, "Cut"), (1, "Copy"), (2, "Paste"), (4, "Comment")];
+
+        assert_eq!(type_ahead_target(&items, "p", None), Some(2));
+        assert_eq!(type_ahead_target(&items, "x", None), None);
+
+        // Repeating a character cycles through the matching items.
+        assert_eq!(type_ahead_target(&items, "c", None), Some(0));
+        assert_eq!(type_ahead_target(&items, "c", Some(0)), Some(1));
+        assert_eq!(type_ahead_target(&items, "cc", Some(1)), Some(4));
+        assert_eq!(type_ahead_target(&items, "ccc", Some(4)), Some(0));
+
+        // Longer prefixes stay on the highlighted item while it matches.
+        assert_eq!(type_ahead_target(&items, "co", Some(1)), Some(1));
+        assert_eq!(type_ahead_target(&items, "com", Some(1)), Some(4));
+        assert_eq!(type_ahead_target(&items, "CoP", None), Some(1));
+    }
+}
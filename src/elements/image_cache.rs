@@ -1,7 +1,8 @@
 use crate::{
-    AnyElement, AnyEntity, App, AppContext, Asset, AssetLogger, Bounds, Element, ElementId, Entity,
-    GlobalElementId, ImageAssetLoader, ImageCacheError, InspectorElementId, IntoElement, LayoutId,
-    ParentElement, Pixels, RenderImage, Resource, Style, StyleRefinement, Styled, Task, Window,
+    AnyElement, AnyEntity, App, AppContext, Asset, AssetLogger, Bounds, DevicePixels, Element,
+    ElementId, Entity, GlobalElementId, ImageAssetLoader, ImageCacheError, InspectorElementId,
+    IntoElement, LayoutId, ParentElement, Pixels, RenderImage, Resource, Size,
+    SizedImageAssetLoader, SizedResource, Style, StyleRefinement, Styled, Task, Window, WindowId,
     hash,
 };
 
@@ -30,6 +31,13 @@ pub struct AnyImageCache {
         window: &mut Window,
         cx: &mut App,
     ) -> Option<Result<Arc<RenderImage>, ImageCacheError>>,
+    load_scaled_fn: fn(
+        image_cache: &AnyEntity,
+        resource: &Resource,
+        decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>>,
 }
 
 impl<I: ImageCache> From<Entity<I>> for AnyImageCache {
@@ -37,6 +45,7 @@ impl<I: ImageCache> From<Entity<I>> for AnyImageCache {
         Self {
             image_cache: image_cache.into_any(),
             load_fn: any_image_cache::load::<I>,
+            load_scaled_fn: any_image_cache::load_scaled::<I>,
         }
     }
 }
@@ -52,6 +61,18 @@ impl AnyImageCache {
     ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
         (self.load_fn)(&self.image_cache, resource, window, cx)
     }
+
+    /// Load an image given a resource, decoded at the given size
+    /// returns the result of loading the image if it has finished loading, or None if it is still loading
+    pub fn load_scaled(
+        &self,
+        resource: &Resource,
+        decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        (self.load_scaled_fn)(&self.image_cache, resource, decode_size, window, cx)
+    }
 }
 
 mod any_image_cache {
@@ -66,6 +87,19 @@ mod any_image_cache {
         let image_cache = image_cache.clone().downcast::<I>().unwrap();
         image_cache.update(cx, |image_cache, cx| image_cache.load(resource, window, cx))
     }
+
+    pub(crate) fn load_scaled<I: 'static + ImageCache>(
+        image_cache: &AnyEntity,
+        resource: &Resource,
+        decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        let image_cache = image_cache.clone().downcast::<I>().unwrap();
+        image_cache.update(cx, |image_cache, cx| {
+            image_cache.load_scaled(resource, decode_size, window, cx)
+        })
+    }
 }
 
 /// An image cache element.
@@ -209,6 +243,47 @@ pub trait ImageCache: 'static {
         window: &mut Window,
         cx: &mut App,
     ) -> Option<Result<Arc<RenderImage>, ImageCacheError>>;
+
+    /// Load an image given a resource, decoded at the given size, which is a bucket from
+    /// [`crate::decode_size_bucket`] of the size that the image is displayed at
+    /// returns the result of loading the image if it has finished loading, or None if it is still loading
+    ///
+    /// By default, this loads the image at its full size with [`Self::load`].
+    fn load_scaled(
+        &mut self,
+        resource: &Resource,
+        _decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        self.load(resource, window, cx)
+    }
+}
+
+/// The key of an image in a cache: the hash of its resource, and the size it's decoded at, if any.
+type ImageCacheKey = (u64, Option<Size<DevicePixels>>);
+
+/// Starts loading an image on the background executor, decoded at the given size if any.
+fn load_image_task(
+    resource: &Resource,
+    decode_size: Option<Size<DevicePixels>>,
+    cx: &mut App,
+) -> ImageLoadingTask {
+    let task = match decode_size {
+        Some(decode_size) => {
+            let source = SizedResource {
+                resource: resource.clone(),
+                decode_size,
+            };
+            let fut = AssetLogger::<SizedImageAssetLoader>::load(source, cx);
+            cx.background_executor().spawn(fut)
+        }
+        None => {
+            let fut = AssetLogger::<ImageAssetLoader>::load(resource.clone(), cx);
+            cx.background_executor().spawn(fut)
+        }
+    };
+    task.shared()
 }
 
 /// An object that can create an ImageCache during the render phase.
@@ -224,8 +299,8 @@ impl<T: ImageCache> ImageCacheProvider for Entity<T> {
     }
 }
 
-/// An implementation of ImageCache, that uses an LRU caching strategy to unload images when the cache is full
-pub struct RetainAllImageCache(HashMap<u64, ImageCacheItem>);
+/// An implementation of ImageCache, that retains all the images it loads until it's cleared or dropped
+pub struct RetainAllImageCache(HashMap<ImageCacheKey, ImageCacheItem>);
 
 impl fmt::Debug for RetainAllImageCache {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -260,15 +335,37 @@ impl RetainAllImageCache {
         window: &mut Window,
         cx: &mut App,
     ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
-        let hash = hash(source);
+        self.load_at_size(source, None, window, cx)
+    }
 
-        if let Some(item) = self.0.get_mut(&hash) {
+    /// Load an image from the given source, decoded at the given size.
+    ///
+    /// Returns `None` if the image is loading.
+    pub fn load_scaled(
+        &mut self,
+        source: &Resource,
+        decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        self.load_at_size(source, Some(decode_size), window, cx)
+    }
+
+    fn load_at_size(
+        &mut self,
+        source: &Resource,
+        decode_size: Option<Size<DevicePixels>>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        let key = (hash(source), decode_size);
+
+        if let Some(item) = self.0.get_mut(&key) {
             return item.get();
         }
 
-        let fut = AssetLogger::<ImageAssetLoader>::load(source.clone(), cx);
-        let task = cx.background_executor().spawn(fut).shared();
-        self.0.insert(hash, ImageCacheItem::Loading(task.clone()));
+        let task = load_image_task(source, decode_size, cx);
+        self.0.insert(key, ImageCacheItem::Loading(task.clone()));
 
         let entity = window.current_view();
         window
@@ -294,13 +391,21 @@ impl RetainAllImageCache {
         }
     }
 
-    /// Remove the image from the cache by the given source.
+    /// Remove the image from the cache by the given source, at all the sizes it was decoded at.
     pub fn remove(&mut self, source: &Resource, window: &mut Window, cx: &mut App) {
         let hash = hash(source);
-        if let Some(mut item) = self.0.remove(&hash)
-            && let Some(Ok(image)) = item.get()
-        {
-            cx.drop_image(image, Some(window));
+        let keys = self
+            .0
+            .keys()
+            .filter(|(key_hash, _)| *key_hash == hash)
+            .copied()
+            .collect::<Vec<_>>();
+        for key in keys {
+            if let Some(mut item) = self.0.remove(&key)
+                && let Some(Ok(image)) = item.get()
+            {
+                cx.drop_image(image, Some(window));
+            }
         }
     }
 
@@ -324,6 +429,16 @@ impl ImageCache for RetainAllImageCache {
     ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
         RetainAllImageCache::load(self, resource, window, cx)
     }
+
+    fn load_scaled(
+        &mut self,
+        resource: &Resource,
+        decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        RetainAllImageCache::load_scaled(self, resource, decode_size, window, cx)
+    }
 }
 
 /// Constructs a retain-all image cache that uses the element state associated with the given ID.
@@ -351,3 +466,422 @@ impl ImageCacheProvider for RetainAllImageCacheProvider {
             .into()
     }
 }
+
+/// Statistics about how an [`LruImageCache`] has been used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImageCacheStats {
+    /// The number of loads of images that were already in the cache, whether or not they had
+    /// finished loading
+    pub hits: usize,
+    /// The number of loads of images that weren't in the cache, and so started loading them
+    pub misses: usize,
+    /// The number of images that were evicted to stay within the cache's budget
+    pub evictions: usize,
+}
+
+/// An implementation of ImageCache that holds the decoded images it loads until they take up more
+/// memory than its budget, then evicts those that were least recently used, dropping them from the
+/// sprite atlas of each window.
+///
+/// Images that are visible aren't evicted, even when the cache is over its budget, since they
+/// would just be loaded again. An image is visible if it was loaded while drawing the current or
+/// previous frame of the window it was loaded in.
+pub struct LruImageCache {
+    entries: HashMap<ImageCacheKey, LruImageCacheEntry>,
+    max_bytes: usize,
+    used_bytes: usize,
+    /// Incremented on each load, to order entries by when they were last used.
+    clock: u64,
+    stats: ImageCacheStats,
+}
+
+struct LruImageCacheEntry {
+    item: ImageCacheItem,
+    /// The size of the decoded image, once it has loaded.
+    bytes: Option<usize>,
+    last_used: u64,
+    last_used_frame: (WindowId, u64),
+}
+
+impl fmt::Debug for LruImageCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LruImageCache")
+            .field("num_images", &self.entries.len())
+            .field("used_bytes", &self.used_bytes)
+            .field("max_bytes", &self.max_bytes)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl LruImageCache {
+    /// Create a new image cache, which holds up to `max_bytes` of decoded images.
+    pub fn new(max_bytes: usize, cx: &mut App) -> Entity<Self> {
+        let e = cx.new(|_cx| LruImageCache {
+            entries: HashMap::new(),
+            max_bytes,
+            used_bytes: 0,
+            clock: 0,
+            stats: ImageCacheStats::default(),
+        });
+        cx.observe_release(&e, |image_cache, cx| {
+            for (_, mut entry) in std::mem::take(&mut image_cache.entries) {
+                if let Some(Ok(image)) = entry.item.get() {
+                    cx.drop_image(image, None);
+                }
+            }
+        })
+        .detach();
+        e
+    }
+
+    /// Load an image from the given source, evicting the least recently used images that aren't
+    /// visible if the cache goes over its budget.
+    ///
+    /// Returns `None` if the image is loading.
+    pub fn load(
+        &mut self,
+        source: &Resource,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        self.load_at_size(source, None, window, cx)
+    }
+
+    /// Load an image from the given source, decoded at the given size, evicting the least
+    /// recently used images that aren't visible if the cache goes over its budget.
+    ///
+    /// Returns `None` if the image is loading.
+    pub fn load_scaled(
+        &mut self,
+        source: &Resource,
+        decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        self.load_at_size(source, Some(decode_size), window, cx)
+    }
+
+    fn load_at_size(
+        &mut self,
+        source: &Resource,
+        decode_size: Option<Size<DevicePixels>>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        let key = (hash(source), decode_size);
+        self.clock += 1;
+        let frame = (window.window_handle().window_id(), window.frame_number());
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            self.stats.hits += 1;
+            entry.last_used = self.clock;
+            entry.last_used_frame = frame;
+            let result = entry.item.get()?;
+            if entry.bytes.is_none() {
+                let bytes = result.as_ref().map_or(0, |image| image.byte_len());
+                entry.bytes = Some(bytes);
+                self.used_bytes += bytes;
+                self.evict(window, cx);
+            }
+            return Some(result);
+        }
+
+        self.stats.misses += 1;
+        let task = load_image_task(source, decode_size, cx);
+        self.entries.insert(
+            key,
+            LruImageCacheEntry {
+                item: ImageCacheItem::Loading(task.clone()),
+                bytes: None,
+                last_used: self.clock,
+                last_used_frame: frame,
+            },
+        );
+
+        let entity = window.current_view();
+        window
+            .spawn(cx, {
+                async move |cx| {
+                    _ = task.await;
+                    cx.on_next_frame(move |_, cx| {
+                        cx.notify(entity);
+                    });
+                }
+            })
+            .detach();
+
+        None
+    }
+
+    /// Evicts the least recently used images that have loaded and aren't visible, until the cache
+    /// is within its budget or there are none left to evict.
+    fn evict(&mut self, window: &mut Window, cx: &mut App) {
+        while self.used_bytes > self.max_bytes {
+            let Some(key) = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.bytes.is_some() && !is_visible(entry, window, cx))
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            let mut entry = self.entries.remove(&key).unwrap();
+            self.used_bytes -= entry.bytes.unwrap_or(0);
+            self.stats.evictions += 1;
+            if let Some(Ok(image)) = entry.item.get() {
+                cx.drop_image(image, Some(window));
+            }
+        }
+    }
+
+    /// Set the number of bytes of decoded images that the cache holds, evicting images that
+    /// aren't visible if it's now over its budget.
+    pub fn set_max_bytes(&mut self, max_bytes: usize, window: &mut Window, cx: &mut App) {
+        self.max_bytes = max_bytes;
+        self.evict(window, cx);
+    }
+
+    /// Returns the number of bytes of decoded images that the cache holds at most, unless more
+    /// are visible.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Returns the number of bytes of decoded images in the cache.
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    /// Returns statistics about how the cache has been used.
+    pub fn stats(&self) -> ImageCacheStats {
+        self.stats
+    }
+
+    /// Clear the image cache.
+    pub fn clear(&mut self, window: &mut Window, cx: &mut App) {
+        for (_, mut entry) in std::mem::take(&mut self.entries) {
+            if let Some(Ok(image)) = entry.item.get() {
+                cx.drop_image(image, Some(window));
+            }
+        }
+        self.used_bytes = 0;
+    }
+
+    /// Remove the image from the cache by the given source, at all the sizes it was decoded at.
+    pub fn remove(&mut self, source: &Resource, window: &mut Window, cx: &mut App) {
+        let hash = hash(source);
+        let keys = self
+            .entries
+            .keys()
+            .filter(|(key_hash, _)| *key_hash == hash)
+            .copied()
+            .collect::<Vec<_>>();
+        for key in keys {
+            let Some(mut entry) = self.entries.remove(&key) else {
+                continue;
+            };
+            self.used_bytes -= entry.bytes.unwrap_or(0);
+            if let Some(Ok(image)) = entry.item.get() {
+                cx.drop_image(image, Some(window));
+            }
+        }
+    }
+
+    /// Returns the number of images in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Whether the image was loaded while drawing the current or previous frame of its window, which
+/// is looked up in the app unless it's the window being updated. Images of closed windows aren't
+/// visible.
+fn is_visible(entry: &LruImageCacheEntry, window: &Window, cx: &App) -> bool {
+    let (window_id, frame_number) = entry.last_used_frame;
+    let current_frame_number = if window_id == window.window_handle().window_id() {
+        Some(window.frame_number())
+    } else {
+        cx.windows
+            .get(window_id)
+            .and_then(|window| window.as_ref())
+            .map(|window| window.frame_number())
+    };
+    current_frame_number.is_some_and(|current| frame_number + 1 >= current)
+}
+
+impl ImageCache for LruImageCache {
+    fn load(
+        &mut self,
+        resource: &Resource,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        LruImageCache::load(self, resource, window, cx)
+    }
+
+    fn load_scaled(
+        &mut self,
+        resource: &Resource,
+        decode_size: Size<DevicePixels>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
+        LruImageCache::load_scaled(self, resource, decode_size, window, cx)
+    }
+}
+
+/// Constructs an LRU image cache holding up to `max_bytes` of decoded images, that uses the
+/// element state associated with the given ID.
+pub fn lru(id: impl Into<ElementId>, max_bytes: usize) -> LruImageCacheProvider {
+    LruImageCacheProvider {
+        id: id.into(),
+        max_bytes,
+    }
+}
+
+/// A provider struct for creating an LRU image cache inline
+pub struct LruImageCacheProvider {
+    id: ElementId,
+    max_bytes: usize,
+}
+
+impl ImageCacheProvider for LruImageCacheProvider {
+    fn provide(&mut self, window: &mut Window, cx: &mut App) -> AnyImageCache {
+        window
+            .with_global_id(self.id.clone(), |global_id, window| {
+                window.with_element_state::<Entity<LruImageCache>, _>(
+                    global_id,
+                    |cache, _window| {
+                        let cache = cache.unwrap_or_else(|| LruImageCache::new(self.max_bytes, cx));
+                        (cache.clone(), cache)
+                    },
+                )
+            })
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{self as gpui, Context, Render, TestAppContext, div};
+    use image::{Frame, RgbaImage};
+
+    struct Empty;
+
+    impl Render for Empty {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div()
+        }
+    }
+
+    /// Adds an image to the cache as if it had finished loading, with 4 bytes per pixel.
+    fn insert_loaded(cache: &mut LruImageCache, source: &Resource, width: u32, window: &Window) {
+        let image = RenderImage::new(SmallVec::from_elem(Frame::new(RgbaImage::new(width, 1)), 1));
+        cache.entries.insert(
+            (hash(source), None),
+            LruImageCacheEntry {
+                item: ImageCacheItem::Loaded(Ok(Arc::new(image))),
+                bytes: None,
+                last_used: 0,
+                last_used_frame: (window.window_handle().window_id(), window.frame_number()),
+            },
+        );
+    }
+
+    #[gpui::test]
+    fn test_lru_image_cache(cx: &mut TestAppContext) {
+        let window = cx.update(|cx| {
+            cx.open_window(Default::default(), |_, cx| cx.new(|_| Empty))
+                .unwrap()
+        });
+        let cache = cx.update(|cx| LruImageCache::new(100, cx));
+        let sources = ["a", "b", "c", "d"].map(|name| Resource::Embedded(name.into()));
+
+        // Visible images are kept, even when they're over the budget.
+        window
+            .update(cx, |_, window, cx| {
+                cache.update(cx, |cache, cx| {
+                    for source in &sources[..3] {
+                        insert_loaded(cache, source, 10, window);
+                        assert!(cache.load(source, window, cx).unwrap().is_ok());
+                    }
+                    assert_eq!(cache.used_bytes(), 120);
+                    assert_eq!(cache.len(), 3);
+                });
+                window.refresh();
+            })
+            .unwrap();
+        window.update(cx, |_, window, _| window.refresh()).unwrap();
+
+        // Once they're no longer visible, the least recently used images are evicted.
+        window
+            .update(cx, |_, window, cx| {
+                cache.update(cx, |cache, cx| {
+                    cache.load(&sources[2], window, cx).unwrap().unwrap();
+                    insert_loaded(cache, &sources[3], 10, window);
+                    cache.load(&sources[3], window, cx).unwrap().unwrap();
+                    assert_eq!(cache.used_bytes(), 80);
+                    assert_eq!(cache.len(), 2);
+
+                    assert!(cache.load(&sources[0], window, cx).is_none());
+                    assert_eq!(
+                        cache.stats(),
+                        ImageCacheStats {
+                            hits: 5,
+                            misses: 1,
+                            evictions: 2,
+                        }
+                    );
+                })
+            })
+            .unwrap();
+    }
+
+    #[gpui::test]
+    fn test_lru_image_cache_windows(cx: &mut TestAppContext) {
+        let [first_window, second_window] = [(); 2].map(|_| {
+            cx.update(|cx| {
+                cx.open_window(Default::default(), |_, cx| cx.new(|_| Empty))
+                    .unwrap()
+            })
+        });
+        let cache = cx.update(|cx| LruImageCache::new(100, cx));
+        let sources = ["a", "b"].map(|name| Resource::Embedded(name.into()));
+
+        first_window
+            .update(cx, |_, window, cx| {
+                cache.update(cx, |cache, cx| {
+                    insert_loaded(cache, &sources[0], 20, window);
+                    cache.load(&sources[0], window, cx).unwrap().unwrap();
+                })
+            })
+            .unwrap();
+
+        // Images loaded in another window are evicted once that window draws frames without
+        // them, even though it no longer loads any images from the cache.
+        for _ in 0..2 {
+            first_window
+                .update(cx, |_, window, _| window.refresh())
+                .unwrap();
+        }
+        second_window
+            .update(cx, |_, window, cx| {
+                cache.update(cx, |cache, cx| {
+                    insert_loaded(cache, &sources[1], 10, window);
+                    cache.load(&sources[1], window, cx).unwrap().unwrap();
+                    assert_eq!(cache.len(), 1);
+                    assert_eq!(cache.used_bytes(), 40);
+                    assert_eq!(cache.stats().evictions, 1);
+                })
+            })
+            .unwrap();
+    }
+}
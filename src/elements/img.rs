@@ -1,8 +1,9 @@
 use crate::{
-    AnyElement, AnyImageCache, App, Asset, AssetLogger, Bounds, DefiniteLength, Element, ElementId,
-    Entity, GlobalElementId, Hitbox, Image, ImageCache, InspectorElementId, InteractiveElement,
-    Interactivity, IntoElement, LayoutId, Length, ObjectFit, Pixels, RenderImage, Resource,
-    SharedString, SharedUri, StyleRefinement, Styled, Task, Window, px,
+    AnimatedImageHandle, AnyElement, AnyImageCache, App, Asset, AssetLogger, Bounds,
+    DefiniteLength, DevicePixels, Element, ElementId, Entity, FrameChangeEvent, GlobalElementId,
+    Hitbox, Image, ImageCache, InspectorElementId, InteractiveElement, Interactivity, IntoElement,
+    LayoutId, Length, ObjectFit, Pixels, RenderImage, Resource, SharedString, SharedUri, Size,
+    Style, StyleRefinement, Styled, Task, Thumbhash, Window, px, size,
 };
 use anyhow::Result;
 #[cfg(not(target_arch = "wasm32"))]
@@ -11,8 +12,9 @@ use futures::Future;
 #[cfg(not(target_arch = "wasm32"))]
 use futures::AsyncReadExt;
 use image::{
-    AnimationDecoder, DynamicImage, Frame, ImageError, ImageFormat, Rgba,
-    codecs::{gif::GifDecoder, webp::WebPDecoder},
+    AnimationDecoder, DynamicImage, Frame, Frames, ImageError, ImageFormat, Rgba,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    imageops,
 };
 use smallvec::SmallVec;
 use std::{
@@ -20,6 +22,7 @@ use std::{
     io::{self, Cursor},
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
+    rc::Rc,
     sync::Arc,
     time::Duration,
 };
@@ -51,6 +54,10 @@ pub const LOADING_DELAY: Duration = Duration::from_millis(200);
 /// Custom loaders, or external images will not use this asset loader
 pub type ImgResourceLoader = AssetLogger<ImageAssetLoader>;
 
+/// A type alias to the resource loader that the `img()` element uses to decode images at the size
+/// they're displayed at.
+pub type SizedImgResourceLoader = AssetLogger<SizedImageAssetLoader>;
+
 /// A source of image content.
 #[derive(Clone)]
 pub enum ImageSource {
@@ -152,6 +159,8 @@ pub struct ImageStyle {
     object_fit: ObjectFit,
     loading: Option<Box<dyn Fn() -> AnyElement>>,
     fallback: Option<Box<dyn Fn() -> AnyElement>>,
+    placeholder: Option<Thumbhash>,
+    fade_in: Option<Duration>,
 }
 
 impl Default for ImageStyle {
@@ -161,6 +170,8 @@ impl Default for ImageStyle {
             object_fit: ObjectFit::Contain,
             loading: None,
             fallback: None,
+            placeholder: None,
+            fade_in: None,
         }
     }
 }
@@ -195,6 +206,22 @@ pub trait StyledImage: Sized {
         self.image_style().loading = Some(Box::new(loading));
         self
     }
+
+    /// Set a placeholder to show while the image is being loaded, which is also laid out at the
+    /// aspect ratio of the image.
+    fn placeholder(mut self, placeholder: Thumbhash) -> Self {
+        self.image_style().placeholder = Some(placeholder);
+        self
+    }
+
+    /// Fade the image in over the given duration once it's loaded, over its placeholder if it
+    /// has one. Images that were already loaded when they're first shown appear right away.
+    ///
+    /// The element needs an ID to fade in, so that it can tell when the image was loaded.
+    fn fade_in(mut self, duration: Duration) -> Self {
+        self.image_style().fade_in = Some(duration);
+        self
+    }
 }
 
 impl StyledImage for Img {
@@ -210,13 +237,22 @@ impl StyledImage for Stateful<Img> {
 }
 
 /// An image element.
+///
+/// Images loaded from a [`Resource`] are decoded at about the size they're displayed at, see
+/// [`decode_size_bucket`]. Elements with an ID remember the size they were displayed at between
+/// frames, and decode the image again as it changes. Those without decode it at the size in their
+/// style, or at the size of the viewport.
 pub struct Img {
     interactivity: Interactivity,
     source: ImageSource,
     style: ImageStyle,
     image_cache: Option<AnyImageCache>,
+    animation: Option<AnimatedImageHandle>,
+    frame_change_listener: Option<FrameChangeListener>,
 }
 
+type FrameChangeListener = Rc<dyn Fn(&FrameChangeEvent, &mut Window, &mut App)>;
+
 /// Create a new image element.
 #[track_caller]
 pub fn img(source: impl Into<ImageSource>) -> Img {
@@ -225,6 +261,8 @@ pub fn img(source: impl Into<ImageSource>) -> Img {
         source: source.into(),
         style: ImageStyle::default(),
         image_cache: None,
+        animation: None,
+        frame_change_listener: None,
     }
 }
 
@@ -254,6 +292,25 @@ impl Img {
             ..self
         }
     }
+
+    /// Track the playback of an animated image with the given handle, so that it can be paused,
+    /// seeked, or played at a different speed. Animated images play without a handle when the
+    /// element has an ID.
+    pub fn track_animation(mut self, handle: &AnimatedImageHandle) -> Self {
+        self.animation = Some(handle.clone());
+        self
+    }
+
+    /// Register a listener to be called when an animated image shows a different frame, whether
+    /// because it played or because it was seeked. The element needs an ID or a handle from
+    /// [`Self::track_animation`] to play.
+    pub fn on_frame_change(
+        mut self,
+        listener: impl Fn(&FrameChangeEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.frame_change_listener = Some(Rc::new(listener));
+        self
+    }
 }
 
 impl Deref for Stateful<Img> {
@@ -271,15 +328,25 @@ impl DerefMut for Stateful<Img> {
 }
 
 /// The image state between frames
+#[derive(Default)]
 struct ImgState {
-    frame_index: usize,
-    last_frame_time: Option<Instant>,
+    /// The playback of the image, when it isn't tracked by a handle from the element.
+    animation: AnimatedImageHandle,
     started_loading: Option<(Instant, Task<()>)>,
+    /// The size to decode the image at, from the bounds it was last painted in.
+    decode_size: Option<Size<DevicePixels>>,
+    /// The size the image was decoded at before, to show until it's decoded at its new size.
+    previous_decode_size: Option<Size<DevicePixels>>,
+    /// When the image finished loading, while it's fading in.
+    loaded_at: Option<Instant>,
 }
 
 /// The image layout state between frames
 pub struct ImgLayoutState {
     frame_index: usize,
+    decode_size: Size<DevicePixels>,
+    loading: bool,
+    opacity: f32,
     replacement: Option<AnyElement>,
 }
 
@@ -304,19 +371,20 @@ impl Element for Img {
     ) -> (LayoutId, Self::RequestLayoutState) {
         let mut layout_state = ImgLayoutState {
             frame_index: 0,
+            decode_size: Size::default(),
+            loading: false,
+            opacity: 1.,
             replacement: None,
         };
 
         window.with_optional_element_state(global_id, |state, window| {
-            let mut state = state.map(|state| {
-                state.unwrap_or(ImgState {
-                    frame_index: 0,
-                    last_frame_time: None,
-                    started_loading: None,
-                })
-            });
+            let mut state = state.map(|state| state.unwrap_or_default());
 
-            let frame_index = state.as_ref().map(|state| state.frame_index).unwrap_or(0);
+            let animation = self
+                .animation
+                .clone()
+                .or_else(|| state.as_ref().map(|state| state.animation.clone()));
+            let mut frame_index = 0;
 
             let layout_id = self.interactivity.request_layout(
                 global_id,
@@ -326,31 +394,66 @@ impl Element for Img {
                 |mut style, window, cx| {
                     let mut replacement_id = None;
 
-                    match self.source.use_data(
-                        self.image_cache
-                            .clone()
-                            .or_else(|| window.image_cache_stack.last().cloned()),
-                        window,
-                        cx,
-                    ) {
+                    let image_cache = self
+                        .image_cache
+                        .clone()
+                        .or_else(|| window.image_cache_stack.last().cloned());
+                    let mut decode_size = state
+                        .as_ref()
+                        .and_then(|state| state.decode_size)
+                        .unwrap_or_else(|| initial_decode_size(&style, window));
+                    let mut data =
+                        self.source
+                            .use_data(image_cache.clone(), Some(decode_size), window, cx);
+                    if data.is_none()
+                        && let Some(previous_decode_size) =
+                            state.as_ref().and_then(|state| state.previous_decode_size)
+                        && let Some(Ok(previous_data)) = self.source.get_data(
+                            image_cache,
+                            Some(previous_decode_size),
+                            window,
+                            cx,
+                        )
+                    {
+                        data = Some(Ok(previous_data));
+                        decode_size = previous_decode_size;
+                    }
+                    layout_state.decode_size = decode_size;
+
+                    match data {
                         Some(Ok(data)) => {
+                            if let Some(animation) = &animation {
+                                let (index, playing) = animation.advance(&data);
+                                frame_index = index;
+                                if playing {
+                                    window.request_animation_frame();
+                                }
+                                if let Some(event) = animation.take_frame_change()
+                                    && let Some(listener) = self.frame_change_listener.clone()
+                                {
+                                    window
+                                        .defer(cx, move |window, cx| listener(&event, window, cx));
+                                }
+                            }
+
                             if let Some(state) = &mut state {
-                                let frame_count = data.frame_count();
-                                if frame_count > 1 {
-                                    let current_time = Instant::now();
-                                    if let Some(last_frame_time) = state.last_frame_time {
-                                        let elapsed = current_time - last_frame_time;
-                                        let frame_duration =
-                                            Duration::from(data.delay(state.frame_index));
-
-                                        if elapsed >= frame_duration {
-                                            state.frame_index =
-                                                (state.frame_index + 1) % frame_count;
-                                            state.last_frame_time =
-                                                Some(current_time - (elapsed - frame_duration));
+                                // Images fade in once they load, but not when they were already
+                                // loaded.
+                                if let Some(fade_in) = self.style.fade_in {
+                                    let now = cx.background_executor().now();
+                                    if state.started_loading.is_some() {
+                                        state.loaded_at = Some(now);
+                                    }
+                                    if let Some(loaded_at) = state.loaded_at {
+                                        let progress =
+                                            now.saturating_duration_since(loaded_at).as_secs_f32()
+                                                / fade_in.as_secs_f32();
+                                        if progress < 1. {
+                                            layout_state.opacity = progress;
+                                            window.request_animation_frame();
+                                        } else {
+                                            state.loaded_at = None;
                                         }
-                                    } else {
-                                        state.last_frame_time = Some(current_time);
                                     }
                                 }
                                 state.started_loading = None;
@@ -386,10 +489,6 @@ impl Element for Img {
                                     _ => Length::Definite(image_size.height.into()),
                                 };
                             }
-
-                            if global_id.is_some() && data.frame_count() > 1 {
-                                window.request_animation_frame();
-                            }
                         }
                         Some(_err) => {
                             if let Some(fallback) = self.style.fallback.as_ref() {
@@ -402,6 +501,10 @@ impl Element for Img {
                             }
                         }
                         None => {
+                            layout_state.loading = true;
+                            if let Some(placeholder) = &self.style.placeholder {
+                                style.aspect_ratio = Some(placeholder.aspect_ratio());
+                            }
                             if let Some(state) = &mut state {
                                 if let Some((started_loading, _)) = state.started_loading {
                                     if started_loading.elapsed() > LOADING_DELAY
@@ -481,17 +584,50 @@ impl Element for Img {
             window,
             cx,
             |style, window, cx| {
-                if let Some(Ok(data)) = source.use_data(
-                    self.image_cache
-                        .clone()
-                        .or_else(|| window.image_cache_stack.last().cloned()),
-                    window,
-                    cx,
-                ) {
-                    let new_bounds = self
-                        .style
-                        .object_fit
-                        .get_bounds(bounds, data.size(layout_state.frame_index));
+                let image_cache = self
+                    .image_cache
+                    .clone()
+                    .or_else(|| window.image_cache_stack.last().cloned());
+                let mut decode_size = layout_state.decode_size;
+                let displayed_decode_size =
+                    decode_size_bucket(bounds.size.to_device_pixels(window.scale_factor()));
+                if let Some(global_id) = global_id
+                    && matches!(source, ImageSource::Resource(_))
+                    && displayed_decode_size != decode_size
+                {
+                    window.with_element_state::<ImgState, _>(global_id, |state, _| {
+                        let mut state = state.unwrap_or_default();
+                        state.previous_decode_size = Some(decode_size);
+                        state.decode_size = Some(displayed_decode_size);
+                        ((), state)
+                    });
+                    // Start decoding the image at the size it's displayed at, drawing it at the
+                    // size it was decoded at before until it's ready.
+                    if let Some(Ok(_)) = source.use_data(
+                        image_cache.clone(),
+                        Some(displayed_decode_size),
+                        window,
+                        cx,
+                    ) {
+                        decode_size = displayed_decode_size;
+                    }
+                }
+
+                let data = source.use_data(image_cache, Some(decode_size), window, cx);
+                let fading_in = matches!(data, Some(Ok(_))) && layout_state.opacity < 1.;
+                if let Some(placeholder) = &self.style.placeholder
+                    && (layout_state.loading || fading_in)
+                {
+                    // Placeholders are scaled to fill the space that the image will, since they're
+                    // much smaller than it.
+                    let object_fit = match self.style.object_fit {
+                        ObjectFit::Fill | ObjectFit::Cover => self.style.object_fit,
+                        ObjectFit::Contain | ObjectFit::ScaleDown | ObjectFit::None => {
+                            ObjectFit::Contain
+                        }
+                    };
+                    let placeholder = placeholder.image();
+                    let new_bounds = object_fit.get_bounds(bounds, placeholder.size(0));
                     let corner_radii = style
                         .corner_radii
                         .to_pixels(window.rem_size())
@@ -500,11 +636,34 @@ impl Element for Img {
                         .paint_image(
                             new_bounds,
                             corner_radii,
-                            data,
-                            layout_state.frame_index,
+                            placeholder,
+                            0,
                             self.style.grayscale,
                         )
                         .log_err();
+                }
+
+                if let Some(Ok(data)) = data {
+                    let new_bounds = self
+                        .style
+                        .object_fit
+                        .get_bounds(bounds, data.original_size(layout_state.frame_index));
+                    let corner_radii = style
+                        .corner_radii
+                        .to_pixels(window.rem_size())
+                        .clamp_radii_for_quad_size(new_bounds.size);
+                    let opacity = fading_in.then_some(layout_state.opacity);
+                    window.with_element_opacity(opacity, |window| {
+                        window
+                            .paint_image(
+                                new_bounds,
+                                corner_radii,
+                                data,
+                                layout_state.frame_index,
+                                self.style.grayscale,
+                            )
+                            .log_err();
+                    });
                 } else if let Some(replacement) = &mut layout_state.replacement {
                     replacement.paint(window, cx);
                 }
@@ -539,17 +698,22 @@ impl ImageSource {
     pub(crate) fn use_data(
         &self,
         cache: Option<AnyImageCache>,
+        decode_size: Option<Size<DevicePixels>>,
         window: &mut Window,
         cx: &mut App,
     ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
         match self {
-            ImageSource::Resource(resource) => {
-                if let Some(cache) = cache {
-                    cache.load(resource, window, cx)
-                } else {
-                    window.use_asset::<ImgResourceLoader>(resource, cx)
+            ImageSource::Resource(resource) => match (cache, decode_size) {
+                (Some(cache), Some(decode_size)) => {
+                    cache.load_scaled(resource, decode_size, window, cx)
                 }
-            }
+                (Some(cache), None) => cache.load(resource, window, cx),
+                (None, Some(decode_size)) => {
+                    let source = sized_asset_source(resource, decode_size, cx);
+                    window.use_asset::<SizedImgResourceLoader>(&source, cx)
+                }
+                (None, None) => window.use_asset::<ImgResourceLoader>(resource, cx),
+            },
             ImageSource::Custom(loading_fn) => loading_fn(window, cx),
             ImageSource::Render(data) => Some(Ok(data.to_owned())),
             ImageSource::Image(data) => window.use_asset::<AssetLogger<ImageDecoder>>(data, cx),
@@ -559,28 +723,41 @@ impl ImageSource {
     pub(crate) fn get_data(
         &self,
         cache: Option<AnyImageCache>,
+        decode_size: Option<Size<DevicePixels>>,
         window: &mut Window,
         cx: &mut App,
     ) -> Option<Result<Arc<RenderImage>, ImageCacheError>> {
         match self {
-            ImageSource::Resource(resource) => {
-                if let Some(cache) = cache {
-                    cache.load(resource, window, cx)
-                } else {
-                    window.get_asset::<ImgResourceLoader>(resource, cx)
+            ImageSource::Resource(resource) => match (cache, decode_size) {
+                (Some(cache), Some(decode_size)) => {
+                    cache.load_scaled(resource, decode_size, window, cx)
                 }
-            }
+                (Some(cache), None) => cache.load(resource, window, cx),
+                (None, Some(decode_size)) => {
+                    let source = sized_asset_source(resource, decode_size, cx);
+                    window.get_asset::<SizedImgResourceLoader>(&source, cx)
+                }
+                (None, None) => window.get_asset::<ImgResourceLoader>(resource, cx),
+            },
             ImageSource::Custom(loading_fn) => loading_fn(window, cx),
             ImageSource::Render(data) => Some(Ok(data.to_owned())),
             ImageSource::Image(data) => window.get_asset::<AssetLogger<ImageDecoder>>(data, cx),
         }
     }
 
-    /// Remove this image source from the asset system
+    /// Remove this image source from the asset system, including its decodes at the sizes it was
+    /// displayed at by [`SizedImageAssetLoader`].
     pub fn remove_asset(&self, cx: &mut App) {
         match self {
             ImageSource::Resource(resource) => {
                 cx.remove_asset::<ImgResourceLoader>(resource);
+                let decode_sizes = cx.sized_image_decodes.remove(resource).unwrap_or_default();
+                for decode_size in decode_sizes {
+                    cx.remove_asset::<SizedImgResourceLoader>(&SizedResource {
+                        resource: resource.clone(),
+                        decode_size,
+                    });
+                }
             }
             ImageSource::Custom(_) | ImageSource::Render(_) => {}
             ImageSource::Image(data) => cx.remove_asset::<AssetLogger<ImageDecoder>>(data),
@@ -588,6 +765,23 @@ impl ImageSource {
     }
 }
 
+/// Returns the source that the asset system decodes `resource` at `decode_size` from, recording
+/// the size so that [`ImageSource::remove_asset`] can remove that decode again.
+fn sized_asset_source(
+    resource: &Resource,
+    decode_size: Size<DevicePixels>,
+    cx: &mut App,
+) -> SizedResource {
+    cx.sized_image_decodes
+        .entry(resource.clone())
+        .or_default()
+        .insert(decode_size);
+    SizedResource {
+        resource: resource.clone(),
+        decode_size,
+    }
+}
+
 #[derive(Clone)]
 enum ImageDecoder {}
 
@@ -616,105 +810,139 @@ impl Asset for ImageAssetLoader {
         source: Self::Source,
         cx: &mut App,
     ) -> impl Future<Output = Self::Output> + Send + 'static {
-        let client = cx.http_client();
-        // TODO: Can we make SVGs always rescale?
-        // let scale_factor = cx.scale_factor();
-        let svg_renderer = cx.svg_renderer();
-        let asset_source = cx.asset_source().clone();
-        async move {
-            let bytes = match source.clone() {
-                Resource::Path(uri) => fs::read(uri.as_ref())?,
-                Resource::Uri(uri) => {
-                    // HTTP image loading - only available on native platforms
-                    #[cfg(not(target_arch = "wasm32"))]
-                    {
-                        let mut response = client
-                            .get(uri.as_ref(), ().into(), true)
-                            .await
-                            .with_context(|| format!("loading image asset from {uri:?}"))?;
-                        let mut body = Vec::new();
-                        response.body_mut().read_to_end(&mut body).await?;
-                        if !response.status().is_success() {
-                            let mut body = String::from_utf8_lossy(&body).into_owned();
-                            let first_line = body.lines().next().unwrap_or("").trim_end();
-                            body.truncate(first_line.len());
-                            return Err(ImageCacheError::BadStatus {
-                                uri,
-                                status: response.status(),
-                                body,
-                            });
-                        }
-                        body
-                    }
-                    #[cfg(target_arch = "wasm32")]
-                    {
-                        // TODO: Implement fetch-based HTTP loading for WASM
-                        let _ = client; // suppress unused warning
-                        return Err(ImageCacheError::Asset(
-                            format!("HTTP image loading not yet supported on WASM: {}", uri).into(),
-                        ));
-                    }
-                }
-                Resource::Embedded(path) => {
-                    let data = asset_source.load(&path).ok().flatten();
-                    if let Some(data) = data {
-                        data.to_vec()
-                    } else {
-                        return Err(ImageCacheError::Asset(
-                            format!("Embedded resource not found: {}", path).into(),
-                        ));
-                    }
-                }
-            };
+        load_image(source, None, cx)
+    }
+}
+
+/// A resource to load an image from, along with the size to decode it at. See
+/// [`SizedImageAssetLoader`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SizedResource {
+    /// The resource to load the image from
+    pub resource: Resource,
+    /// The size to decode the image at, which should be a bucket from [`decode_size_bucket`] so
+    /// that images are only decoded again when their size changes enough
+    pub decode_size: Size<DevicePixels>,
+}
+
+/// An image loader for the GPUI asset system that decodes images at a given size, downscaling
+/// those that are larger so that they're as small as they can be while still covering it. The
+/// decoded images are laid out at the size of the source image.
+#[derive(Clone)]
+pub enum SizedImageAssetLoader {}
+
+impl Asset for SizedImageAssetLoader {
+    type Source = SizedResource;
+    type Output = Result<Arc<RenderImage>, ImageCacheError>;
+
+    fn load(
+        source: Self::Source,
+        cx: &mut App,
+    ) -> impl Future<Output = Self::Output> + Send + 'static {
+        load_image(source.resource, Some(source.decode_size), cx)
+    }
+}
+
+/// Rounds up the size that an image is displayed at, in device pixels, to the size that it
+/// should be decoded at. Sizes are rounded up to powers of two, so that images don't need to be
+/// decoded again each time they're resized.
+pub fn decode_size_bucket(size: Size<DevicePixels>) -> Size<DevicePixels> {
+    size.map(|dimension| {
+        let dimension = (dimension.0.max(1) as u32).next_power_of_two();
+        DevicePixels(dimension.min(i32::MAX as u32 / 2 + 1) as i32)
+    })
+}
 
-            if let Ok(format) = image::guess_format(&bytes) {
-                let data = match format {
-                    ImageFormat::Gif => {
-                        let decoder = GifDecoder::new(Cursor::new(&bytes))?;
-                        let mut frames = SmallVec::new();
-
-                        for frame in decoder.into_frames() {
-                            let mut frame = frame?;
-                            // Convert from RGBA to BGRA.
-                            for pixel in frame.buffer_mut().chunks_exact_mut(4) {
-                                pixel.swap(0, 2);
+fn load_image(
+    source: Resource,
+    decode_size: Option<Size<DevicePixels>>,
+    cx: &mut App,
+) -> impl Future<Output = Result<Arc<RenderImage>, ImageCacheError>> + Send + 'static {
+    let client = cx.http_client();
+    // TODO: Can we make SVGs always rescale?
+    // let scale_factor = cx.scale_factor();
+    let svg_renderer = cx.svg_renderer();
+    let asset_source = cx.asset_source().clone();
+    #[cfg(not(target_arch = "wasm32"))]
+    let executor = cx.background_executor().clone();
+    #[cfg(not(target_arch = "wasm32"))]
+    let http_cache = cx.http_cache();
+    async move {
+        let bytes = match source.clone() {
+            Resource::Path(uri) => fs::read(uri.as_ref())?,
+            Resource::Uri(uri) => {
+                // HTTP image loading - only available on native platforms
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let mut attempt = 1;
+                    loop {
+                        match fetch_uri(client.as_ref(), http_cache.as_deref(), &uri).await {
+                            Ok(body) => break body,
+                            Err(error)
+                                if attempt < URI_LOAD_ATTEMPTS && is_transient_error(&error) =>
+                            {
+                                let delay = URI_RETRY_DELAY * 2u32.pow(attempt - 1);
+                                log::debug!(
+                                    "retrying image load from {uri:?} in {delay:?} after error: {error}"
+                                );
+                                executor.timer(delay).await;
+                                attempt += 1;
                             }
-                            frames.push(frame);
+                            Err(error) => return Err(error),
                         }
-
-                        frames
                     }
-                    ImageFormat::WebP => {
-                        let mut decoder = WebPDecoder::new(Cursor::new(&bytes))?;
-
-                        if decoder.has_animation() {
-                            let _ = decoder.set_background_color(Rgba([0, 0, 0, 0]));
-                            let mut frames = SmallVec::new();
-
-                            for frame in decoder.into_frames() {
-                                let mut frame = frame?;
-                                // Convert from RGBA to BGRA.
-                                for pixel in frame.buffer_mut().chunks_exact_mut(4) {
-                                    pixel.swap(0, 2);
-                                }
-                                frames.push(frame);
-                            }
+                }
+                #[cfg(target_arch = "wasm32")]
+                {
+                    // TODO: Implement fetch-based HTTP loading for WASM
+                    let _ = client; // suppress unused warning
+                    return Err(ImageCacheError::Asset(
+                        format!("HTTP image loading not yet supported on WASM: {}", uri).into(),
+                    ));
+                }
+            }
+            Resource::Embedded(path) => {
+                let data = asset_source.load(&path).ok().flatten();
+                if let Some(data) = data {
+                    data.to_vec()
+                } else {
+                    return Err(ImageCacheError::Asset(
+                        format!("Embedded resource not found: {}", path).into(),
+                    ));
+                }
+            }
+        };
 
-                            frames
-                        } else {
-                            let mut data = DynamicImage::from_decoder(decoder)?.into_rgba8();
+        if let Ok(format) = image::guess_format(&bytes) {
+            let mut data = match format {
+                ImageFormat::Gif => {
+                    let decoder = GifDecoder::new(Cursor::new(&bytes))?;
+                    animation_frames(decoder.into_frames())?
+                }
+                ImageFormat::WebP => {
+                    let mut decoder = WebPDecoder::new(Cursor::new(&bytes))?;
 
-                            // Convert from RGBA to BGRA.
-                            for pixel in data.chunks_exact_mut(4) {
-                                pixel.swap(0, 2);
-                            }
+                    if decoder.has_animation() {
+                        let _ = decoder.set_background_color(Rgba([0, 0, 0, 0]));
+                        animation_frames(decoder.into_frames())?
+                    } else {
+                        let mut data = DynamicImage::from_decoder(decoder)?.into_rgba8();
 
-                            SmallVec::from_elem(Frame::new(data), 1)
+                        // Convert from RGBA to BGRA.
+                        for pixel in data.chunks_exact_mut(4) {
+                            pixel.swap(0, 2);
                         }
+
+                        SmallVec::from_elem(Frame::new(data), 1)
                     }
-                    _ => {
-                        let mut data =
-                            image::load_from_memory_with_format(&bytes, format)?.into_rgba8();
+                }
+                ImageFormat::Png => {
+                    let decoder = PngDecoder::new(Cursor::new(&bytes))?;
+
+                    if decoder.is_apng()? {
+                        animation_frames(decoder.apng()?.into_frames())?
+                    } else {
+                        let mut data = DynamicImage::from_decoder(decoder)?.into_rgba8();
 
                         // Convert from RGBA to BGRA.
                         for pixel in data.chunks_exact_mut(4) {
@@ -723,16 +951,149 @@ impl Asset for ImageAssetLoader {
 
                         SmallVec::from_elem(Frame::new(data), 1)
                     }
-                };
+                }
+                _ => {
+                    let mut data =
+                        image::load_from_memory_with_format(&bytes, format)?.into_rgba8();
+
+                    // Convert from RGBA to BGRA.
+                    for pixel in data.chunks_exact_mut(4) {
+                        pixel.swap(0, 2);
+                    }
 
-                Ok(Arc::new(RenderImage::new(data)))
-            } else {
-                svg_renderer
-                    .render_single_frame(&bytes, 1.0, true)
-                    .map_err(Into::into)
-            }
+                    SmallVec::from_elem(Frame::new(data), 1)
+                }
+            };
+
+            let decode_scale = match decode_size {
+                Some(decode_size) => downscale_frames(&mut data, decode_size),
+                None => 1.0,
+            };
+            let mut image = RenderImage::new(data);
+            image.decode_scale = decode_scale;
+            Ok(Arc::new(image))
+        } else {
+            svg_renderer
+                .render_single_frame(&bytes, 1.0, true)
+                .map_err(Into::into)
+        }
+    }
+}
+
+/// Decodes the frames of an animated image, in BGRA format.
+fn animation_frames(frames: Frames<'_>) -> Result<SmallVec<[Frame; 1]>, ImageCacheError> {
+    let mut decoded_frames = SmallVec::new();
+    for frame in frames {
+        let mut frame = frame?;
+        // Convert from RGBA to BGRA.
+        for pixel in frame.buffer_mut().chunks_exact_mut(4) {
+            pixel.swap(0, 2);
         }
+        decoded_frames.push(frame);
     }
+    Ok(decoded_frames)
+}
+
+/// How many times loading an image from a URI is attempted, when it fails with errors that may not
+/// happen again.
+#[cfg(not(target_arch = "wasm32"))]
+const URI_LOAD_ATTEMPTS: u32 = 4;
+
+/// How long to wait before loading an image from a URI again, doubling with each attempt.
+#[cfg(not(target_arch = "wasm32"))]
+const URI_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn fetch_uri(
+    client: &dyn http_client::HttpClient,
+    http_cache: Option<&crate::HttpCache>,
+    uri: &SharedUri,
+) -> Result<Vec<u8>, ImageCacheError> {
+    let (status, body) = if let Some(http_cache) = http_cache {
+        let response = http_cache
+            .fetch(client, uri.as_ref())
+            .await
+            .with_context(|| format!("loading image asset from {uri:?}"))?;
+        (response.status, response.body)
+    } else {
+        let mut response = client
+            .get(uri.as_ref(), ().into(), true)
+            .await
+            .with_context(|| format!("loading image asset from {uri:?}"))?;
+        let mut body = Vec::new();
+        response.body_mut().read_to_end(&mut body).await?;
+        (response.status(), body)
+    };
+    if !status.is_success() {
+        let mut body = String::from_utf8_lossy(&body).into_owned();
+        let first_line = body.lines().next().unwrap_or("").trim_end();
+        body.truncate(first_line.len());
+        return Err(ImageCacheError::BadStatus {
+            uri: uri.clone(),
+            status,
+            body,
+        });
+    }
+    Ok(body)
+}
+
+/// Whether loading an image from a URI failed in a way that may not happen again, such as the
+/// connection failing or the server being unavailable, rather than the image not existing.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_transient_error(error: &ImageCacheError) -> bool {
+    match error {
+        ImageCacheError::Other(_) | ImageCacheError::Io(_) => true,
+        ImageCacheError::BadStatus { status, .. } => {
+            status.is_server_error()
+                || *status == HttpStatusCode::REQUEST_TIMEOUT
+                || *status == HttpStatusCode::TOO_MANY_REQUESTS
+        }
+        ImageCacheError::Asset(_) | ImageCacheError::Image(_) | ImageCacheError::Usvg(_) => false,
+    }
+}
+
+/// The size to decode an image at before it has been painted: the size in its style where that's
+/// absolute, or otherwise the size of the viewport, which it's unlikely to be displayed larger than.
+fn initial_decode_size(style: &Style, window: &Window) -> Size<DevicePixels> {
+    let rem_size = window.rem_size();
+    let viewport_size = window.viewport_size();
+    let absolute = |length: Length| match length {
+        Length::Definite(DefiniteLength::Absolute(length)) => Some(length.to_pixels(rem_size)),
+        _ => None,
+    };
+    let displayed_size = match (absolute(style.size.width), absolute(style.size.height)) {
+        (Some(width), Some(height)) => size(width, height),
+        // Images are decoded to cover their size, so the other dimension follows from this one.
+        (Some(width), None) => size(width, px(1.)),
+        (None, Some(height)) => size(px(1.), height),
+        (None, None) => viewport_size,
+    };
+    decode_size_bucket(displayed_size.to_device_pixels(window.scale_factor()))
+}
+
+/// Downscales the frames of an image so that they're as small as they can be while covering the
+/// given size, returning how much smaller they are. Frames that are smaller than it are kept as
+/// they are.
+fn downscale_frames(frames: &mut SmallVec<[Frame; 1]>, decode_size: Size<DevicePixels>) -> f32 {
+    let Some(first_frame) = frames.first() else {
+        return 1.0;
+    };
+    let (width, height) = first_frame.buffer().dimensions();
+    let scale = (decode_size.width.0 as f32 / width as f32)
+        .max(decode_size.height.0 as f32 / height as f32);
+    if scale >= 1.0 {
+        return 1.0;
+    }
+
+    let new_width = ((width as f32 * scale).ceil() as u32).clamp(1, width);
+    let new_height = ((height as f32 * scale).ceil() as u32).clamp(1, height);
+    for frame in frames.iter_mut() {
+        let buffer = imageops::thumbnail(frame.buffer(), new_width, new_height);
+        let left = (frame.left() as f32 * scale) as u32;
+        let top = (frame.top() as f32 * scale) as u32;
+        *frame = Frame::from_parts(buffer, left, top, frame.delay());
+    }
+    new_width as f32 / width as f32
 }
 
 /// An error that can occur when interacting with the image cache.
@@ -788,3 +1149,230 @@ impl From<image::ImageError> for ImageCacheError {
         Self::Image(Arc::new(value))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Render, TestAppContext, div};
+    use futures::{FutureExt as _, future::BoxFuture};
+    use http_client::{AsyncBody, HttpClient, Request, Response, Url, http::HeaderValue};
+    use image::RgbaImage;
+    use std::{
+        cell::RefCell,
+        sync::atomic::{AtomicUsize, Ordering::SeqCst},
+    };
+
+    /// A server that responds to every request with the same status.
+    struct FailingServer {
+        status: HttpStatusCode,
+        requests: AtomicUsize,
+    }
+
+    impl HttpClient for FailingServer {
+        fn type_name(&self) -> &'static str {
+            "FailingServer"
+        }
+
+        fn send(
+            &self,
+            _request: Request<AsyncBody>,
+        ) -> BoxFuture<'static, anyhow::Result<Response<AsyncBody>>> {
+            self.requests.fetch_add(1, SeqCst);
+            let response = Response::builder()
+                .status(self.status)
+                .body(AsyncBody::from("unavailable".to_string()));
+            async move { Ok(response?) }.boxed()
+        }
+
+        fn user_agent(&self) -> Option<&HeaderValue> {
+            None
+        }
+
+        fn proxy(&self) -> Option<&Url> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        let bad_status = |status| ImageCacheError::BadStatus {
+            uri: "https://example.com/image.png".into(),
+            status,
+            body: String::new(),
+        };
+        assert!(is_transient_error(&bad_status(
+            HttpStatusCode::SERVICE_UNAVAILABLE
+        )));
+        assert!(is_transient_error(&bad_status(
+            HttpStatusCode::REQUEST_TIMEOUT
+        )));
+        assert!(is_transient_error(&bad_status(
+            HttpStatusCode::TOO_MANY_REQUESTS
+        )));
+        assert!(!is_transient_error(&bad_status(HttpStatusCode::NOT_FOUND)));
+        assert!(!is_transient_error(&bad_status(HttpStatusCode::FORBIDDEN)));
+        assert!(is_transient_error(&ImageCacheError::from(io::Error::from(
+            io::ErrorKind::ConnectionReset
+        ))));
+        assert!(is_transient_error(&ImageCacheError::from(anyhow::anyhow!(
+            "connection refused"
+        ))));
+        assert!(!is_transient_error(&ImageCacheError::Asset(
+            "missing".into()
+        )));
+    }
+
+    #[crate::test]
+    fn test_uri_load_retries(cx: &mut TestAppContext) {
+        let load = |status, cx: &mut TestAppContext| {
+            let server = Arc::new(FailingServer {
+                status,
+                requests: AtomicUsize::new(0),
+            });
+            let (task, _) = cx.update(|cx| {
+                cx.set_http_client(server.clone());
+                let uri = SharedUri::from(format!("https://example.com/{}.png", status.as_u16()));
+                cx.fetch_asset::<ImgResourceLoader>(&Resource::Uri(uri))
+            });
+            cx.run_until_parked();
+            (server, task)
+        };
+
+        // Transient errors are retried after delays that double each time, until the image has
+        // been requested `URI_LOAD_ATTEMPTS` times.
+        let (server, task) = load(HttpStatusCode::SERVICE_UNAVAILABLE, cx);
+        let requests_after = |delay, cx: &mut TestAppContext| {
+            cx.executor().advance_clock(delay);
+            cx.run_until_parked();
+            server.requests.load(SeqCst)
+        };
+        assert_eq!(requests_after(Duration::ZERO, cx), 1);
+        assert_eq!(
+            requests_after(URI_RETRY_DELAY - Duration::from_millis(1), cx),
+            1
+        );
+        assert_eq!(requests_after(Duration::from_millis(1), cx), 2);
+        assert_eq!(requests_after(URI_RETRY_DELAY * 2, cx), 3);
+        assert_eq!(requests_after(URI_RETRY_DELAY * 4, cx), 4);
+        assert_eq!(
+            requests_after(URI_RETRY_DELAY * 8, cx),
+            URI_LOAD_ATTEMPTS as usize
+        );
+        assert!(matches!(
+            task.now_or_never(),
+            Some(Err(ImageCacheError::BadStatus { .. }))
+        ));
+
+        // Other errors fail straight away.
+        let (server, task) = load(HttpStatusCode::NOT_FOUND, cx);
+        assert_eq!(server.requests.load(SeqCst), 1);
+        assert!(matches!(
+            task.now_or_never(),
+            Some(Err(ImageCacheError::BadStatus { .. }))
+        ));
+    }
+
+    struct FadingImage {
+        image: Rc<RefCell<Option<Arc<RenderImage>>>>,
+    }
+
+    impl Render for FadingImage {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            let image = self.image.clone();
+            div().child(
+                img(move |_: &mut Window, _: &mut App| image.borrow().clone().map(Ok))
+                    .id("image")
+                    .size(px(10.))
+                    .fade_in(Duration::from_millis(100)),
+            )
+        }
+    }
+
+    #[crate::test]
+    fn test_image_fade_in(cx: &mut TestAppContext) {
+        let image = Rc::new(RefCell::new(None));
+        let (_, cx) = cx.add_window_view(|_, _| FadingImage {
+            image: image.clone(),
+        });
+        let draw = |cx: &mut crate::VisualTestContext| {
+            cx.update(|window, _| window.refresh());
+            cx.update(|window, _| {
+                window
+                    .rendered_frame
+                    .scene
+                    .polychrome_sprites
+                    .iter()
+                    .map(|sprite| sprite.opacity)
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert!(draw(cx).is_empty());
+
+        // Images that finish loading fade in over the given duration.
+        *image.borrow_mut() = Some(Arc::new(RenderImage::new(SmallVec::from_elem(
+            Frame::new(RgbaImage::new(10, 10)),
+            1,
+        ))));
+        assert_eq!(draw(cx), [0.]);
+        cx.executor().advance_clock(Duration::from_millis(50));
+        let opacity = draw(cx);
+        assert_eq!(opacity.len(), 1);
+        assert!((opacity[0] - 0.5).abs() < 0.001);
+        cx.executor().advance_clock(Duration::from_millis(50));
+        assert_eq!(draw(cx), [1.]);
+        cx.executor().advance_clock(Duration::from_millis(50));
+        assert_eq!(draw(cx), [1.]);
+    }
+
+    #[test]
+    fn test_decode_size_bucket() {
+        assert_eq!(
+            decode_size_bucket(size(DevicePixels(300), DevicePixels(64))),
+            size(DevicePixels(512), DevicePixels(64))
+        );
+        assert_eq!(
+            decode_size_bucket(size(DevicePixels(0), DevicePixels(1))),
+            size(DevicePixels(1), DevicePixels(1))
+        );
+    }
+
+    #[crate::test]
+    fn test_remove_sized_decodes(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let resource = Resource::Embedded("image.png".into());
+            for decode_size in [
+                size(DevicePixels(64), DevicePixels(32)),
+                size(DevicePixels(128), DevicePixels(64)),
+            ] {
+                let source = sized_asset_source(&resource, decode_size, cx);
+                let _ = cx.fetch_asset::<SizedImgResourceLoader>(&source);
+            }
+            assert_eq!(cx.loading_assets.len(), 2);
+
+            ImageSource::Resource(resource).remove_asset(cx);
+            assert!(cx.loading_assets.is_empty());
+            assert!(cx.sized_image_decodes.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_downscale_frames() {
+        let mut frames = SmallVec::from_elem(Frame::new(RgbaImage::new(400, 100)), 1);
+
+        // Images are kept as they are when they're smaller than the decode size.
+        let decode_scale = downscale_frames(&mut frames, size(DevicePixels(512), DevicePixels(64)));
+        assert_eq!(decode_scale, 1.0);
+        assert_eq!(frames[0].buffer().dimensions(), (400, 100));
+
+        // Otherwise they're downscaled to cover it.
+        let decode_scale = downscale_frames(&mut frames, size(DevicePixels(64), DevicePixels(64)));
+        assert_eq!(frames[0].buffer().dimensions(), (256, 64));
+
+        let mut image = RenderImage::new(frames);
+        image.decode_scale = decode_scale;
+        assert_eq!(
+            image.original_size(0),
+            size(DevicePixels(400), DevicePixels(100))
+        );
+    }
+}
@@ -0,0 +1,453 @@
+use std::ops::Range;
+
+use itertools::Itertools as _;
+
+use crate::{
+    AnyElement, App, Colors, Context, ElementId, Entity, HighlightStyle, InteractiveElement,
+    IntoElement, OwnedMenu, OwnedMenuItem, ParentElement, SharedString, StatefulInteractiveElement,
+    Styled, StyledText, UnderlineStyle, Window, div, is_menu_action_available, px,
+    util::FluentBuilder,
+};
+
+/// Which menus of an [`crate::AppMenuBar`] or [`crate::ContextMenu`] are open, kept across
+/// frames.
+#[derive(Clone, Default)]
+pub(crate) struct OpenMenus {
+    /// The index of the open menu, followed by the index of each open submenu within its parent.
+    pub open_path: Vec<usize>,
+    /// The highlighted item of the innermost open menu.
+    pub highlighted: Option<usize>,
+    /// The depth of the menu panel under the pointer, if any.
+    pub hovered_panel: Option<usize>,
+}
+
+impl OpenMenus {
+    pub fn is_open(&self) -> bool {
+        !self.open_path.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.open_path.clear();
+        self.highlighted = None;
+        self.hovered_panel = None;
+    }
+
+    pub fn innermost_menu<'a>(&self, menus: &'a [OwnedMenu]) -> Option<&'a OwnedMenu> {
+        let (first, rest) = self.open_path.split_first()?;
+        let mut menu = menus.get(*first)?;
+        for ix in rest {
+            match menu.items.get(*ix)? {
+                OwnedMenuItem::Submenu(submenu) => menu = submenu,
+                _ => return None,
+            }
+        }
+        Some(menu)
+    }
+}
+
+/// The state of an element that shows menus, which are rendered with [`render_menu`].
+pub(crate) trait MenuOwner: Sized + 'static {
+    fn open_menus(&self) -> &OpenMenus;
+
+    fn open_menus_mut(&mut self) -> &mut OpenMenus;
+
+    /// The menus that [`OpenMenus::open_path`] starts from.
+    fn menus(&self, cx: &App) -> Vec<OwnedMenu>;
+
+    /// Whether the pointer is over the element showing the menus, where pressing the mouse
+    /// doesn't dismiss them.
+    fn is_owner_hovered(&self) -> bool;
+
+    fn close(&mut self, cx: &mut Context<Self>);
+}
+
+/// Highlights the item at the given index of the menu at the given depth, opening it if it's
+/// a submenu, and closing any submenus below that depth.
+pub(crate) fn hover_item<S: MenuOwner>(
+    state: &mut S,
+    depth: usize,
+    ix: usize,
+    is_submenu: bool,
+    cx: &mut Context<S>,
+) {
+    let open = state.open_menus_mut();
+    open.open_path.truncate(depth + 1);
+    if is_submenu {
+        open.open_path.push(ix);
+        open.highlighted = None;
+    } else {
+        open.highlighted = Some(ix);
+    }
+    cx.notify();
+}
+
+/// Chooses the item at the given index of the innermost open menu, which either opens a
+/// submenu or dispatches an action and closes the menus.
+pub(crate) fn choose<S: MenuOwner>(
+    state: &mut S,
+    ix: usize,
+    window: &mut Window,
+    cx: &mut Context<S>,
+) {
+    let menus = state.menus(cx);
+    let Some(menu) = state.open_menus().innermost_menu(&menus) else {
+        return;
+    };
+    match menu.items.get(ix) {
+        Some(OwnedMenuItem::Submenu(submenu)) => {
+            let highlighted = selectable_items(submenu, window, cx).first().copied();
+            let open = state.open_menus_mut();
+            open.open_path.push(ix);
+            open.highlighted = highlighted;
+            cx.notify();
+        }
+        Some(OwnedMenuItem::Action { action, .. })
+            if is_menu_action_available(action.as_ref(), window, cx) =>
+        {
+            let action = action.boxed_clone();
+            state.close(cx);
+            window.dispatch_action(action, cx);
+        }
+        _ => {}
+    }
+}
+
+/// Handles the keys that navigate within the open menus, returning whether the key was one of
+/// them. Left and right are only handled when they close or open a submenu.
+pub(crate) fn handle_navigation_key<S: MenuOwner>(
+    state: &mut S,
+    key: &str,
+    window: &mut Window,
+    cx: &mut Context<S>,
+) -> bool {
+    let menus = state.menus(cx);
+    let Some(menu) = state.open_menus().innermost_menu(&menus) else {
+        return false;
+    };
+    let open = state.open_menus_mut();
+    match key {
+        "escape" => {
+            if open.open_path.len() > 1 {
+                open.highlighted = open.open_path.pop();
+                cx.notify();
+            } else {
+                state.close(cx);
+            }
+        }
+        "up" | "down" => {
+            let selectable = selectable_items(menu, window, cx);
+            let open = state.open_menus_mut();
+            let position = open
+                .highlighted
+                .and_then(|ix| selectable.iter().position(|item| *item == ix));
+            let next = match (key, position) {
+                (_, _) if selectable.is_empty() => None,
+                ("up", Some(position)) => {
+                    Some((position + selectable.len() - 1) % selectable.len())
+                }
+                ("up", None) => Some(selectable.len() - 1),
+                (_, Some(position)) => Some((position + 1) % selectable.len()),
+                (_, None) => Some(0),
+            };
+            open.highlighted = next.map(|position| selectable[position]);
+            cx.notify();
+        }
+        "left" if open.open_path.len() > 1 => {
+            open.highlighted = open.open_path.pop();
+            cx.notify();
+        }
+        "right" => {
+            let highlighted_submenu = open
+                .highlighted
+                .filter(|ix| matches!(menu.items.get(*ix), Some(OwnedMenuItem::Submenu(_))));
+            let Some(ix) = highlighted_submenu else {
+                return false;
+            };
+            choose(state, ix, window, cx);
+        }
+        "enter" | "space" => {
+            if let Some(ix) = open.highlighted {
+                choose(state, ix, window, cx);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Renders a panel with the items of the menu at the given depth, along with its open submenu.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_menu<S: MenuOwner>(
+    state: &Entity<S>,
+    menu: &OwnedMenu,
+    depth: usize,
+    open: &OpenMenus,
+    show_mnemonics: bool,
+    colors: &Colors,
+    window: &mut Window,
+    cx: &mut App,
+) -> AnyElement {
+    let open_submenu = open.open_path.get(depth + 1).copied();
+    let highlighted = if open_submenu.is_some() {
+        open_submenu
+    } else {
+        open.highlighted
+    };
+    let mnemonics = mnemonics(menu.items.iter().map(item_name));
+    let keymap = cx.keymap.clone();
+
+    let mut rows = Vec::with_capacity(menu.items.len());
+    for (ix, item) in menu.items.iter().enumerate() {
+        let (name, checked, enabled, keystrokes, submenu) = match item {
+            OwnedMenuItem::Separator => {
+                rows.push(
+                    div()
+                        .h(px(1.))
+                        .my_1()
+                        .bg(colors.separator)
+                        .into_any_element(),
+                );
+                continue;
+            }
+            OwnedMenuItem::SystemMenu(_) => continue,
+            OwnedMenuItem::Submenu(submenu) => {
+                (submenu.name.clone(), false, true, None, Some(submenu))
+            }
+            OwnedMenuItem::Action {
+                name,
+                action,
+                checked,
+                ..
+            } => {
+                let keystrokes = keymap
+                    .borrow()
+                    .bindings_for_action(action.as_ref())
+                    .find_or_first(|binding| binding.predicate().is_none())
+                    .map(|binding| {
+                        binding
+                            .keystrokes()
+                            .iter()
+                            .map(ToString::to_string)
+                            .join(" ")
+                    });
+                (
+                    SharedString::from(name.clone()),
+                    *checked,
+                    is_menu_action_available(action.as_ref(), window, cx),
+                    keystrokes,
+                    None,
+                )
+            }
+        };
+
+        let is_highlighted = enabled && highlighted == Some(ix);
+        let submenu_panel = submenu.filter(|_| open_submenu == Some(ix)).map(|submenu| {
+            div().absolute().top_0().left_full().child(render_menu(
+                state,
+                submenu,
+                depth + 1,
+                open,
+                show_mnemonics,
+                colors,
+                window,
+                cx,
+            ))
+        });
+        let is_submenu = submenu.is_some();
+
+        rows.push(
+            div()
+                .id(ix)
+                .relative()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap_4()
+                .px_3()
+                .py_0p5()
+                .map(|this| {
+                    if !enabled {
+                        this.text_color(colors.disabled)
+                    } else if is_highlighted {
+                        this.bg(colors.selected).text_color(colors.selected_text)
+                    } else {
+                        this
+                    }
+                })
+                .child(div().w_3().child(if checked { "✓" } else { "" }))
+                .child(
+                    div().flex_grow().child(label(
+                        name,
+                        mnemonics[ix]
+                            .as_ref()
+                            .filter(|_| show_mnemonics)
+                            .map(|mnemonic| mnemonic.range.clone()),
+                    )),
+                )
+                .children(keystrokes.map(|keystrokes| {
+                    div()
+                        .when(!is_highlighted, |this| this.text_color(colors.text_muted))
+                        .child(keystrokes)
+                }))
+                .when(is_submenu, |this| this.child("▸"))
+                .when(enabled, |this| {
+                    this.on_hover({
+                        let state = state.clone();
+                        move |hovered, _, cx| {
+                            if *hovered {
+                                state.update(cx, |state, cx| {
+                                    hover_item(state, depth, ix, is_submenu, cx)
+                                });
+                            }
+                        }
+                    })
+                    .on_click({
+                        let state = state.clone();
+                        move |_, window, cx| {
+                            state.update(cx, |state, cx| {
+                                state.open_menus_mut().open_path.truncate(depth + 1);
+                                choose(state, ix, window, cx);
+                            })
+                        }
+                    })
+                })
+                .children(submenu_panel)
+                .into_any_element(),
+        );
+    }
+
+    div()
+        .id(ElementId::named_usize("menu", depth))
+        .flex()
+        .flex_col()
+        .min_w(px(200.))
+        .py_1()
+        .bg(colors.background)
+        .text_color(colors.text)
+        .border_1()
+        .border_color(colors.border)
+        .rounded_md()
+        .shadow_md()
+        .occlude()
+        .on_hover({
+            let state = state.clone();
+            move |hovered, _, cx| {
+                state.update(cx, |state, _| {
+                    let open = state.open_menus_mut();
+                    if *hovered {
+                        open.hovered_panel = Some(depth);
+                    } else if open.hovered_panel == Some(depth) {
+                        open.hovered_panel = None;
+                    }
+                })
+            }
+        })
+        .on_mouse_down_out({
+            let state = state.clone();
+            move |_, _, cx| {
+                state.update(cx, |state, cx| {
+                    if !state.is_owner_hovered() && state.open_menus().hovered_panel.is_none() {
+                        state.close(cx);
+                    }
+                })
+            }
+        })
+        .children(rows)
+        .into_any_element()
+}
+
+/// The items of a menu that can be highlighted with the keyboard.
+pub(crate) fn selectable_items(menu: &OwnedMenu, window: &Window, cx: &mut App) -> Vec<usize> {
+    menu.items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| match item {
+            OwnedMenuItem::Submenu(_) => true,
+            OwnedMenuItem::Action { action, .. } => {
+                is_menu_action_available(action.as_ref(), window, cx)
+            }
+            OwnedMenuItem::Separator | OwnedMenuItem::SystemMenu(_) => false,
+        })
+        .map(|(ix, _)| ix)
+        .collect()
+}
+
+pub(crate) fn item_name(item: &OwnedMenuItem) -> &str {
+    match item {
+        OwnedMenuItem::Submenu(submenu) => submenu.name.as_ref(),
+        OwnedMenuItem::Action { name, .. } => name,
+        OwnedMenuItem::Separator | OwnedMenuItem::SystemMenu(_) => "",
+    }
+}
+
+/// The character of a menu or item name that chooses it from the keyboard, shown underlined.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Mnemonic {
+    pub key: String,
+    pub range: Range<usize>,
+}
+
+/// Assigns each name the first of its letters or digits that isn't already the mnemonic of an
+/// earlier name.
+pub(crate) fn mnemonics<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<Option<Mnemonic>> {
+    let mut taken = Vec::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let (ix, key) = name
+                .char_indices()
+                .filter(|(_, c)| c.is_alphanumeric())
+                .map(|(ix, c)| (ix..ix + c.len_utf8(), c.to_lowercase().to_string()))
+                .find(|(_, key)| !taken.contains(key))?;
+            taken.push(key.clone());
+            Some(Mnemonic { key, range: ix })
+        })
+        .collect()
+}
+
+pub(crate) fn find_mnemonic(mnemonics: &[Option<Mnemonic>], key: &str) -> Option<usize> {
+    let key = key.to_lowercase();
+    mnemonics.iter().position(|mnemonic| {
+        mnemonic
+            .as_ref()
+            .is_some_and(|mnemonic| mnemonic.key == key)
+    })
+}
+
+pub(crate) fn label(name: SharedString, mnemonic: Option<Range<usize>>) -> StyledText {
+    let highlights = mnemonic.map(|range| {
+        (
+            range,
+            HighlightStyle {
+                underline: Some(UnderlineStyle {
+                    thickness: px(1.),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+    });
+    StyledText::new(name).with_highlights(highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mnemonics() {
+        let mnemonics = mnemonics(["File", "Edit", "Find", "", "…", "2 Up"]);
+        let keys = mnemonics
+            .iter()
+            .map(|mnemonic| mnemonic.as_ref().map(|mnemonic| mnemonic.key.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [Some("f"), Some("e"), Some("i"), None, None, Some("2")]
+        );
+        assert_eq!(mnemonics[2].as_ref().unwrap().range, 1..2);
+
+        assert_eq!(find_mnemonic(&mnemonics, "I"), Some(2));
+        assert_eq!(find_mnemonic(&mnemonics, "x"), None);
+    }
+}
@@ -1,19 +1,27 @@
 mod anchored;
+mod animated_image;
 mod animation;
+mod app_menu_bar;
 mod canvas;
+mod context_menu;
 mod deferred;
 mod div;
 mod image_cache;
 mod img;
 mod list;
+mod menu;
 mod surface;
 mod svg;
 mod text;
+mod thumbhash;
 mod uniform_list;
 
 pub use anchored::*;
+pub use animated_image::*;
 pub use animation::*;
+pub use app_menu_bar::*;
 pub use canvas::*;
+pub use context_menu::*;
 pub use deferred::*;
 pub use div::*;
 pub use image_cache::*;
@@ -22,4 +30,5 @@ pub use list::*;
 pub use surface::*;
 pub use svg::*;
 pub use text::*;
+pub use thumbhash::*;
 pub use uniform_list::*;
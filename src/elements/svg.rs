@@ -3,17 +3,19 @@ use std::{fs, path::Path, sync::Arc};
 use crate::{
     App, Asset, Bounds, Element, GlobalElementId, Hitbox, InspectorElementId, InteractiveElement,
     Interactivity, IntoElement, LayoutId, Pixels, Point, Radians, SharedString, Size,
-    StyleRefinement, Styled, TransformationMatrix, Window, geometry::Negate as _, point, px,
-    radians, size,
+    StyleRefinement, Styled, SvgStyle, TransformationMatrix, Window, black, geometry::Negate as _,
+    point, px, radians, size,
 };
 use crate::util::ResultExt;
 
-/// An SVG element.
+/// An SVG element, which is rendered as a mask tinted with the text color unless it's
+/// [`Svg::full_color`].
 pub struct Svg {
     interactivity: Interactivity,
     transformation: Option<Transformation>,
     path: Option<SharedString>,
     external_path: Option<SharedString>,
+    svg_style: SvgStyle,
 }
 
 /// Create a new SVG element.
@@ -24,6 +26,7 @@ pub fn svg() -> Svg {
         transformation: None,
         path: None,
         external_path: None,
+        svg_style: SvgStyle::default(),
     }
 }
 
@@ -40,8 +43,47 @@ impl Svg {
         self
     }
 
+    /// Render the SVG with its own colors, rather than as a mask tinted with the text color. The
+    /// text color is used for `currentColor` instead. Full-color SVGs can't be transformed, see
+    /// [`Svg::with_transformation`].
+    pub fn full_color(mut self) -> Self {
+        self.svg_style.full_color = true;
+        self
+    }
+
+    /// Apply a CSS style sheet to the SVG, after its own styles, such as to recolor the parts of a
+    /// two-tone icon with `.secondary { fill: var(--accent) }`.
+    pub fn style_sheet(mut self, style_sheet: impl Into<SharedString>) -> Self {
+        self.svg_style.style_sheet = Some(style_sheet.into());
+        self
+    }
+
+    /// Set the value of a CSS custom property, such as `--accent`, that the SVG or its style sheet
+    /// use with `var()`. Setting a property again replaces its value.
+    pub fn css_variable(
+        mut self,
+        name: impl Into<SharedString>,
+        value: impl Into<SharedString>,
+    ) -> Self {
+        let name = name.into();
+        let value = value.into();
+        if let Some((_, existing_value)) = self
+            .svg_style
+            .variables
+            .iter_mut()
+            .find(|(existing_name, _)| *existing_name == name)
+        {
+            *existing_value = value;
+        } else {
+            self.svg_style.variables.push((name, value));
+        }
+        self
+    }
+
     /// Transform the SVG element with the given transformation.
     /// Note that this won't effect the hitbox or layout of the element, only the rendering.
+    ///
+    /// Full-color SVGs can't be transformed, and are painted without the transformation.
     pub fn with_transformation(mut self, transformation: Transformation) -> Self {
         self.transformation = Some(transformation);
         self
@@ -117,21 +159,32 @@ impl Element for Svg {
             window,
             cx,
             |style, window, cx| {
-                if let Some((path, color)) = self.path.as_ref().zip(style.text.color) {
-                    let transformation = self
-                        .transformation
-                        .as_ref()
-                        .map(|transformation| {
-                            transformation.into_matrix(bounds.center(), window.scale_factor())
-                        })
-                        .unwrap_or_default();
+                // Monochrome SVGs are only painted when there's a color to tint them with.
+                let Some(color) = style.text.color.or(self.svg_style.full_color.then(black)) else {
+                    return;
+                };
+                self.svg_style.color = color;
+                // An untransformed SVG is given the exact unit matrix, which full-color SVGs need.
+                let transformation = self
+                    .transformation
+                    .filter(|transformation| *transformation != Transformation::default())
+                    .map(|transformation| {
+                        transformation.into_matrix(bounds.center(), window.scale_factor())
+                    })
+                    .unwrap_or_default();
 
+                if let Some(path) = self.path.as_ref() {
                     window
-                        .paint_svg(bounds, path.clone(), None, transformation, color, cx)
+                        .paint_styled_svg(
+                            bounds,
+                            path.clone(),
+                            None,
+                            transformation,
+                            &self.svg_style,
+                            cx,
+                        )
                         .log_err();
-                } else if let Some((path, color)) =
-                    self.external_path.as_ref().zip(style.text.color)
-                {
+                } else if let Some(path) = self.external_path.as_ref() {
                     let Some(bytes) = window
                         .use_asset::<SvgAsset>(path, cx)
                         .and_then(|asset| asset.log_err())
@@ -139,21 +192,13 @@ impl Element for Svg {
                         return;
                     };
 
-                    let transformation = self
-                        .transformation
-                        .as_ref()
-                        .map(|transformation| {
-                            transformation.into_matrix(bounds.center(), window.scale_factor())
-                        })
-                        .unwrap_or_default();
-
                     window
-                        .paint_svg(
+                        .paint_styled_svg(
                             bounds,
                             path.clone(),
                             Some(&bytes),
                             transformation,
-                            color,
+                            &self.svg_style,
                             cx,
                         )
                         .log_err();
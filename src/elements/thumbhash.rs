@@ -0,0 +1,193 @@
+use crate::RenderImage;
+use anyhow::{Result, anyhow};
+use image::{Frame, RgbaImage};
+use smallvec::SmallVec;
+use std::{f32::consts::PI, fmt, sync::Arc};
+
+/// A [ThumbHash](https://evanw.github.io/thumbhash/) of an image: a compact representation of it
+/// that's decoded to a small, blurry version of it, which can be shown while the image loads. See
+/// [`crate::StyledImage::placeholder`].
+///
+/// The hash is decoded when it's created, so create it once and keep it around, rather than
+/// creating it each frame.
+#[derive(Clone)]
+pub struct Thumbhash {
+    hash: Arc<[u8]>,
+    image: Arc<RenderImage>,
+}
+
+impl Thumbhash {
+    /// Decode a ThumbHash from its bytes, failing if they're too short to be one.
+    pub fn new(hash: impl Into<Arc<[u8]>>) -> Result<Self> {
+        let hash = hash.into();
+        let image = decode(&hash)?;
+        Ok(Self {
+            hash,
+            image: Arc::new(RenderImage::new(SmallVec::from_elem(Frame::new(image), 1))),
+        })
+    }
+
+    /// The bytes of the hash.
+    pub fn hash(&self) -> &[u8] {
+        &self.hash
+    }
+
+    /// The aspect ratio of the image that the hash was made from, as its width over its height.
+    /// This is approximate, since the hash only stores the number of frequencies in each direction.
+    pub fn aspect_ratio(&self) -> f32 {
+        let size = self.image.size(0);
+        size.width.0 as f32 / size.height.0 as f32
+    }
+
+    /// The decoded image, which is at most 32 pixels in each direction.
+    pub fn image(&self) -> Arc<RenderImage> {
+        self.image.clone()
+    }
+}
+
+impl fmt::Debug for Thumbhash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Thumbhash")
+            .field("hash", &self.hash)
+            .finish()
+    }
+}
+
+/// Decodes a hash into an image in BGRA format, following the reference implementation.
+fn decode(hash: &[u8]) -> Result<RgbaImage> {
+    let byte = |index: usize| {
+        hash.get(index)
+            .copied()
+            .ok_or_else(|| anyhow!("thumbhash is too short, at {} bytes", hash.len()))
+    };
+
+    let header24 = byte(0)? as u32 | (byte(1)? as u32) << 8 | (byte(2)? as u32) << 16;
+    let header16 = byte(3)? as u32 | (byte(4)? as u32) << 8;
+    let l_dc = (header24 & 63) as f32 / 63.;
+    let p_dc = ((header24 >> 6) & 63) as f32 / 31.5 - 1.;
+    let q_dc = ((header24 >> 12) & 63) as f32 / 31.5 - 1.;
+    let l_scale = ((header24 >> 18) & 31) as f32 / 31.;
+    let has_alpha = header24 >> 23 != 0;
+    let p_scale = ((header16 >> 3) & 63) as f32 / 63.;
+    let q_scale = ((header16 >> 9) & 63) as f32 / 63.;
+    let is_landscape = header16 >> 15 != 0;
+    let (lx, ly) = match (is_landscape, has_alpha) {
+        (true, true) => (5, header16 & 7),
+        (true, false) => (7, header16 & 7),
+        (false, true) => (header16 & 7, 5),
+        (false, false) => (header16 & 7, 7),
+    };
+    let ratio = lx as f32 / ly as f32;
+    let (lx, ly) = (lx.max(3) as usize, ly.max(3) as usize);
+    let (a_dc, a_scale) = if has_alpha {
+        let alpha = byte(5)?;
+        ((alpha & 15) as f32 / 15., (alpha >> 4) as f32 / 15.)
+    } else {
+        (1., 0.)
+    };
+
+    // The AC coefficients are stored as nibbles, with saturation boosted to compensate for
+    // quantization.
+    let mut ac_index = 0;
+    let ac_start = if has_alpha { 6 } else { 5 };
+    let mut decode_channel = |nx: usize, ny: usize, scale: f32| -> Result<Vec<f32>> {
+        let mut ac = Vec::new();
+        for cy in 0..ny {
+            let mut cx = if cy == 0 { 1 } else { 0 };
+            while cx * ny < nx * (ny - cy) {
+                let nibble = (byte(ac_start + (ac_index >> 1))? >> ((ac_index & 1) << 2)) & 15;
+                ac.push((nibble as f32 / 7.5 - 1.) * scale);
+                ac_index += 1;
+                cx += 1;
+            }
+        }
+        Ok(ac)
+    };
+    let l_ac = decode_channel(lx, ly, l_scale)?;
+    let p_ac = decode_channel(3, 3, p_scale * 1.25)?;
+    let q_ac = decode_channel(3, 3, q_scale * 1.25)?;
+    let a_ac = if has_alpha {
+        decode_channel(5, 5, a_scale)?
+    } else {
+        Vec::new()
+    };
+
+    let (width, height) = if ratio > 1. {
+        (32, (32. / ratio).round() as u32)
+    } else {
+        ((32. * ratio).round() as u32, 32)
+    };
+    let (width, height) = (width.max(1), height.max(1));
+    let mut image = RgbaImage::new(width, height);
+    let mut fx = vec![0.; lx.max(if has_alpha { 5 } else { 3 })];
+    let mut fy = vec![0.; ly.max(if has_alpha { 5 } else { 3 })];
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        for (cx, f) in fx.iter_mut().enumerate() {
+            *f = (PI / width as f32 * (x as f32 + 0.5) * cx as f32).cos();
+        }
+        for (cy, f) in fy.iter_mut().enumerate() {
+            *f = (PI / height as f32 * (y as f32 + 0.5) * cy as f32).cos();
+        }
+
+        let (mut l, mut p, mut q, mut a) = (l_dc, p_dc, q_dc, a_dc);
+        let mut j = 0;
+        for cy in 0..ly {
+            let mut cx = if cy == 0 { 1 } else { 0 };
+            while cx * ly < lx * (ly - cy) {
+                l += l_ac[j] * fx[cx] * fy[cy] * 2.;
+                j += 1;
+                cx += 1;
+            }
+        }
+        let mut j = 0;
+        for cy in 0..3 {
+            let skip = if cy == 0 { 1 } else { 0 };
+            for fx in fx.iter().take(3 - cy).skip(skip) {
+                let f = fx * fy[cy] * 2.;
+                p += p_ac[j] * f;
+                q += q_ac[j] * f;
+                j += 1;
+            }
+        }
+        if has_alpha {
+            let mut j = 0;
+            for cy in 0..5 {
+                let skip = if cy == 0 { 1 } else { 0 };
+                for fx in fx.iter().take(5 - cy).skip(skip) {
+                    a += a_ac[j] * fx * fy[cy] * 2.;
+                    j += 1;
+                }
+            }
+        }
+
+        let b = l - 2. / 3. * p;
+        let r = (3. * l - b + q) / 2.;
+        let g = r - q;
+        let channel = |value: f32| (255. * value.clamp(0., 1.)) as u8;
+        *pixel = image::Rgba([channel(b), channel(g), channel(r), channel(a)]);
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbhash() {
+        // A landscape hash of a flat, almost white image.
+        let mut hash = vec![0xBF, 0x07, 0x02, 0x03, 0x80];
+        hash.extend([0; 12]);
+        let thumbhash = Thumbhash::new(hash.clone()).unwrap();
+        let image = thumbhash.image();
+        assert_eq!(image.size(0).width.0, 32);
+        assert_eq!(image.size(0).height.0, 14);
+        assert!((thumbhash.aspect_ratio() - 32. / 14.).abs() < 0.01);
+        for pixel in image.as_bytes(0).unwrap().chunks_exact(4) {
+            assert!(pixel.iter().all(|channel| *channel >= 250), "{pixel:?}");
+        }
+
+        hash.truncate(10);
+        assert!(Thumbhash::new(hash).is_err());
+    }
+}
@@ -785,6 +785,21 @@ impl ForegroundExecutor {
 /// Copy-modified from:
 /// <https://github.com/smol-rs/async-task/blob/ca9dbe1db9c422fd765847fa91306e30a6bb58a9/src/runnable.rs#L405>
 #[track_caller]
+/// Lets other tasks run before continuing, so that a task doing a lot of work in a loop doesn't
+/// hold up the thread it runs on, and can be cancelled partway through.
+pub(crate) async fn yield_now() {
+    let mut yielded = false;
+    futures::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
 fn spawn_local_with_source_location<Fut, S, M>(
     future: Fut,
     schedule: S,
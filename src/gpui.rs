@@ -22,6 +22,8 @@ mod elements;
 mod executor;
 mod geometry;
 mod global;
+#[cfg(not(target_arch = "wasm32"))]
+mod http_cache;
 #[cfg(target_arch = "wasm32")]
 pub mod http_stubs;
 mod input;
@@ -86,6 +88,8 @@ pub use geometry::*;
 pub use global::*;
 pub use gpui_macros::{AppContext, IntoElement, Render, VisualContext, register_action, test};
 #[cfg(not(target_arch = "wasm32"))]
+pub use http_cache::*;
+#[cfg(not(target_arch = "wasm32"))]
 pub use http_client;
 #[cfg(target_arch = "wasm32")]
 pub use http_stubs as http_client;
@@ -0,0 +1,520 @@
+use crate::util::ResultExt;
+use anyhow::{Context as _, Result};
+use collections::FxHashMap;
+use futures::AsyncReadExt;
+use http_client::{
+    AsyncBody, HttpClient, HttpRequestExt, RedirectPolicy, Request, StatusCode,
+    http::{
+        HeaderMap,
+        header::{CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    },
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering::SeqCst},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A cache of HTTP responses on disk, keyed by URI, which is used to load images from
+/// [`crate::Resource::Uri`] once it's set with [`crate::App::set_http_cache`], and can be used by
+/// other [`crate::Asset`]s with [`crate::App::http_cache`].
+///
+/// Responses are fresh for as long as the `max-age` of their `Cache-Control` header, and are
+/// returned without a request while they are. Stale responses with an `ETag` or `Last-Modified`
+/// header are revalidated with a conditional request, so that they're only downloaded again if
+/// they've changed. Responses with `no-store` aren't cached, and the least recently used
+/// responses are removed when the cache is larger than its size limit.
+pub struct HttpCache {
+    directory: PathBuf,
+    max_bytes: u64,
+    entries: Mutex<FxHashMap<u64, HttpCacheEntry>>,
+    clock: AtomicU64,
+}
+
+/// The metadata of a cached response, which is stored next to its body.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct HttpCacheEntry {
+    uri: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// When the response becomes stale, in seconds since the Unix epoch.
+    fresh_until: u64,
+    size: u64,
+    /// When the response was last returned, relative to the others.
+    last_used: u64,
+}
+
+/// A response fetched with [`HttpCache::fetch`].
+#[derive(Clone, Debug)]
+pub struct HttpCacheResponse {
+    /// The status of the response, which is always successful for cached responses
+    pub status: StatusCode,
+    /// The body of the response
+    pub body: Vec<u8>,
+    /// Whether the body came from the cache, either because it was fresh or because the server
+    /// responded that it hadn't changed
+    pub from_cache: bool,
+}
+
+impl HttpCache {
+    /// Open the cache in the given directory, creating it if it doesn't exist, with a limit on
+    /// the total size of the response bodies that it keeps.
+    pub fn new(directory: impl Into<PathBuf>, max_bytes: u64) -> Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("failed to create HTTP cache directory {directory:?}"))?;
+
+        let mut entries = FxHashMap::default();
+        for dir_entry in fs::read_dir(&directory)? {
+            let path = dir_entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let key = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| u64::from_str_radix(stem, 16).ok());
+            let entry = fs::read(&path)
+                .ok()
+                .and_then(|json| serde_json::from_slice::<HttpCacheEntry>(&json).ok());
+            match key.zip(entry) {
+                Some((key, entry)) if key == cache_key(&entry.uri) => {
+                    entries.insert(key, entry);
+                }
+                _ => {
+                    log::warn!("removing invalid HTTP cache entry {path:?}");
+                    fs::remove_file(&path).log_err();
+                }
+            }
+        }
+
+        let clock = entries.values().map(|entry| entry.last_used).max();
+        let cache = Self {
+            directory,
+            max_bytes,
+            entries: Mutex::new(entries),
+            clock: AtomicU64::new(clock.map_or(0, |clock| clock + 1)),
+        };
+        cache.evict(None);
+        Ok(cache)
+    }
+
+    /// The directory that responses are stored in.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The limit on the total size of the cached response bodies, in bytes.
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// The total size of the cached response bodies, in bytes.
+    pub fn used_bytes(&self) -> u64 {
+        self.entries.lock().values().map(|entry| entry.size).sum()
+    }
+
+    /// Fetch the response to a `GET` request for the given URI, following redirects, from the
+    /// cache if it's fresh there or hasn't changed, or from the client otherwise. Successful
+    /// responses are cached, and unsuccessful ones are returned as they are.
+    pub async fn fetch(&self, client: &dyn HttpClient, uri: &str) -> Result<HttpCacheResponse> {
+        let key = cache_key(uri);
+        let now = unix_time(SystemTime::now());
+        let cached = self
+            .entries
+            .lock()
+            .get(&key)
+            .filter(|entry| entry.uri == uri)
+            .cloned();
+
+        if let Some(entry) = &cached
+            && now < entry.fresh_until
+            && let Some(body) = self.read_body(key)
+        {
+            self.touch(key);
+            return Ok(HttpCacheResponse {
+                status: StatusCode::OK,
+                body,
+                from_cache: true,
+            });
+        }
+
+        let (status, headers, body) = send(client, uri, cached.as_ref()).await?;
+        if status == StatusCode::NOT_MODIFIED
+            && let Some(cached) = cached
+        {
+            if let Some(cached_body) = self.read_body(key) {
+                let entry = HttpCacheEntry {
+                    etag: header(&headers, ETAG).or(cached.etag),
+                    last_modified: header(&headers, LAST_MODIFIED).or(cached.last_modified),
+                    fresh_until: now + CacheControl::parse(&headers).fresh_for(),
+                    last_used: self.tick(),
+                    ..cached
+                };
+                self.write_entry(key, &entry).log_err();
+                self.entries.lock().insert(key, entry);
+                return Ok(HttpCacheResponse {
+                    status: StatusCode::OK,
+                    body: cached_body,
+                    from_cache: true,
+                });
+            }
+
+            // The body was removed from the disk, so it has to be downloaded again.
+            self.remove(key);
+            let (status, headers, body) = send(client, uri, None).await?;
+            return Ok(self.store(key, uri, status, &headers, body, now));
+        }
+        Ok(self.store(key, uri, status, &headers, body, now))
+    }
+
+    /// Remove the response for the given URI from the cache.
+    pub fn remove_uri(&self, uri: &str) {
+        self.remove(cache_key(uri));
+    }
+
+    /// Remove all responses from the cache.
+    pub fn clear(&self) {
+        let keys = self.entries.lock().keys().copied().collect::<Vec<_>>();
+        for key in keys {
+            self.remove(key);
+        }
+    }
+
+    fn store(
+        &self,
+        key: u64,
+        uri: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: Vec<u8>,
+        now: u64,
+    ) -> HttpCacheResponse {
+        let cache_control = CacheControl::parse(headers);
+        let etag = header(headers, ETAG);
+        let last_modified = header(headers, LAST_MODIFIED);
+        let fresh_for = cache_control.fresh_for();
+        // Responses can only be reused if they're fresh for a while or can be revalidated.
+        let reusable = fresh_for > 0 || etag.is_some() || last_modified.is_some();
+        if status != StatusCode::OK
+            || cache_control.no_store
+            || !reusable
+            || body.len() as u64 > self.max_bytes
+        {
+            self.remove(key);
+        } else {
+            let entry = HttpCacheEntry {
+                uri: uri.to_string(),
+                etag,
+                last_modified,
+                fresh_until: now + fresh_for,
+                size: body.len() as u64,
+                last_used: self.tick(),
+            };
+            let written = fs::write(self.body_path(key), &body)
+                .map_err(anyhow::Error::from)
+                .and_then(|_| self.write_entry(key, &entry));
+            if written.log_err().is_some() {
+                self.entries.lock().insert(key, entry);
+                self.evict(Some(key));
+            } else {
+                self.remove(key);
+            }
+        }
+
+        HttpCacheResponse {
+            status,
+            body,
+            from_cache: false,
+        }
+    }
+
+    /// Removes the least recently used responses, other than the one with the given key, until
+    /// the cache is within its size limit.
+    fn evict(&self, keep: Option<u64>) {
+        let mut entries = self.entries.lock();
+        let mut used_bytes = entries.values().map(|entry| entry.size).sum::<u64>();
+        if used_bytes <= self.max_bytes {
+            return;
+        }
+        let mut candidates = entries
+            .iter()
+            .filter(|(key, _)| Some(**key) != keep)
+            .map(|(key, entry)| (entry.last_used, *key))
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        for (_, key) in candidates {
+            if used_bytes <= self.max_bytes {
+                break;
+            }
+            if let Some(entry) = entries.remove(&key) {
+                used_bytes -= entry.size;
+                self.remove_files(key);
+            }
+        }
+    }
+
+    fn remove(&self, key: u64) {
+        if self.entries.lock().remove(&key).is_some() {
+            self.remove_files(key);
+        }
+    }
+
+    fn remove_files(&self, key: u64) {
+        for path in [self.entry_path(key), self.body_path(key)] {
+            if let Err(error) = fs::remove_file(&path)
+                && error.kind() != io::ErrorKind::NotFound
+            {
+                log::error!("failed to remove HTTP cache file {path:?}: {error}");
+            }
+        }
+    }
+
+    fn touch(&self, key: u64) {
+        let last_used = self.tick();
+        if let Some(entry) = self.entries.lock().get_mut(&key) {
+            entry.last_used = last_used;
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, SeqCst)
+    }
+
+    fn read_body(&self, key: u64) -> Option<Vec<u8>> {
+        fs::read(self.body_path(key)).ok()
+    }
+
+    fn write_entry(&self, key: u64, entry: &HttpCacheEntry) -> Result<()> {
+        fs::write(self.entry_path(key), serde_json::to_vec(entry)?)?;
+        Ok(())
+    }
+
+    fn entry_path(&self, key: u64) -> PathBuf {
+        self.directory.join(format!("{key:016x}.json"))
+    }
+
+    fn body_path(&self, key: u64) -> PathBuf {
+        self.directory.join(format!("{key:016x}.body"))
+    }
+}
+
+/// The directives of a `Cache-Control` header that the cache follows.
+#[derive(Debug, Default, PartialEq)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn parse(headers: &HeaderMap) -> Self {
+        let mut cache_control = Self::default();
+        for value in headers.get_all(CACHE_CONTROL) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for directive in value.split(',') {
+                let directive = directive.trim().to_ascii_lowercase();
+                match directive.split_once('=') {
+                    Some(("max-age", seconds)) => {
+                        cache_control.max_age = seconds.trim_matches('"').parse().ok();
+                    }
+                    Some(_) => {}
+                    None if directive == "no-store" => cache_control.no_store = true,
+                    None if directive == "no-cache" => cache_control.no_cache = true,
+                    None => {}
+                }
+            }
+        }
+        cache_control
+    }
+
+    /// How many seconds a response is fresh for after it's received.
+    fn fresh_for(&self) -> u64 {
+        if self.no_cache {
+            0
+        } else {
+            self.max_age.unwrap_or(0)
+        }
+    }
+}
+
+async fn send(
+    client: &dyn HttpClient,
+    uri: &str,
+    cached: Option<&HttpCacheEntry>,
+) -> Result<(StatusCode, HeaderMap, Vec<u8>)> {
+    let mut request = Request::get(uri).follow_redirects(RedirectPolicy::FollowAll);
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let mut response = client
+        .send(request.body(AsyncBody::empty())?)
+        .await
+        .with_context(|| format!("fetching {uri:?}"))?;
+    let mut body = Vec::new();
+    response.body_mut().read_to_end(&mut body).await?;
+    Ok((response.status(), response.headers().clone(), body))
+}
+
+fn header(
+    headers: &HeaderMap,
+    name: impl http_client::http::header::AsHeaderName,
+) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// The key for a URI, which is stable across runs so that it can name files.
+fn cache_key(uri: &str) -> u64 {
+    seahash::hash(uri.as_bytes())
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{FutureExt as _, future::BoxFuture};
+    use http_client::{Response, Url, http::HeaderValue};
+    use std::sync::Arc;
+
+    /// A server for a single image, which supports conditional requests with its `ETag`.
+    struct TestServer {
+        requests: Mutex<Vec<Option<String>>>,
+        version: Mutex<u32>,
+        cache_control: &'static str,
+    }
+
+    impl HttpClient for TestServer {
+        fn type_name(&self) -> &'static str {
+            "TestServer"
+        }
+
+        fn send(
+            &self,
+            request: Request<AsyncBody>,
+        ) -> BoxFuture<'static, anyhow::Result<Response<AsyncBody>>> {
+            let if_none_match = header(request.headers(), IF_NONE_MATCH);
+            self.requests.lock().push(if_none_match.clone());
+            let version = *self.version.lock();
+            let etag = format!("\"v{version}\"");
+            let response = Response::builder()
+                .header(ETAG, &etag)
+                .header(CACHE_CONTROL, self.cache_control);
+            let response = if if_none_match == Some(etag) {
+                response
+                    .status(StatusCode::NOT_MODIFIED)
+                    .body(AsyncBody::empty())
+            } else {
+                response
+                    .status(StatusCode::OK)
+                    .body(AsyncBody::from(format!("image v{version}")))
+            };
+            async move { Ok(response?) }.boxed()
+        }
+
+        fn user_agent(&self) -> Option<&HeaderValue> {
+            None
+        }
+
+        fn proxy(&self) -> Option<&Url> {
+            None
+        }
+    }
+
+    fn test_server(cache_control: &'static str) -> Arc<TestServer> {
+        Arc::new(TestServer {
+            requests: Mutex::default(),
+            version: Mutex::new(1),
+            cache_control,
+        })
+    }
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("gpui-http-cache-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&directory).ok();
+        directory
+    }
+
+    #[test]
+    fn test_http_cache_revalidation() {
+        let directory = test_directory("revalidation");
+        let cache = HttpCache::new(&directory, 1024).unwrap();
+        let server = test_server("no-cache");
+        let uri = "https://example.com/image.png";
+        let fetch = || smol::block_on(cache.fetch(server.as_ref(), uri)).unwrap();
+
+        let response = fetch();
+        assert_eq!(response.body, b"image v1");
+        assert!(!response.from_cache);
+
+        // Stale responses are revalidated, and only downloaded again once they've changed.
+        let response = fetch();
+        assert_eq!(response.body, b"image v1");
+        assert!(response.from_cache);
+        *server.version.lock() = 2;
+        let response = fetch();
+        assert_eq!(response.body, b"image v2");
+        assert!(!response.from_cache);
+        assert_eq!(
+            *server.requests.lock(),
+            [None, Some("\"v1\"".into()), Some("\"v1\"".into())]
+        );
+
+        // The cache is kept across runs.
+        drop(cache);
+        let cache = HttpCache::new(&directory, 1024).unwrap();
+        let response = smol::block_on(cache.fetch(server.as_ref(), uri)).unwrap();
+        assert!(response.from_cache);
+        assert_eq!(server.requests.lock().last(), Some(&Some("\"v2\"".into())));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_http_cache_freshness_and_size_limit() {
+        let directory = test_directory("freshness");
+        let cache = HttpCache::new(&directory, 20).unwrap();
+        let server = test_server("max-age=3600");
+        let fetch = |uri| smol::block_on(cache.fetch(server.as_ref(), uri)).unwrap();
+
+        // Fresh responses are returned without a request.
+        fetch("https://example.com/a.png");
+        assert!(fetch("https://example.com/a.png").from_cache);
+        assert_eq!(server.requests.lock().len(), 1);
+        assert_eq!(cache.used_bytes(), 8);
+
+        // The least recently used response is removed to stay within the size limit.
+        fetch("https://example.com/b.png");
+        fetch("https://example.com/c.png");
+        assert_eq!(cache.used_bytes(), 16);
+        assert!(fetch("https://example.com/c.png").from_cache);
+        assert!(!fetch("https://example.com/a.png").from_cache);
+
+        let server = test_server("no-store, max-age=3600");
+        let fetch = |uri| smol::block_on(cache.fetch(server.as_ref(), uri)).unwrap();
+        cache.clear();
+        fetch("https://example.com/d.png");
+        assert!(!fetch("https://example.com/d.png").from_cache);
+        assert_eq!(cache.used_bytes(), 0);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -50,8 +50,8 @@
 //!  KeyBinding::new("cmd-k left", pane::SplitLeft, Some("Pane"))
 
 use crate::{
-    Action, ActionRegistry, App, DispatchPhase, EntityId, FocusId, KeyBinding, KeyContext, Keymap,
-    Keystroke, ModifiersChangedEvent, Window,
+    Action, ActionRegistry, App, DispatchPhase, EntityId, FocusId, KeyBinding, KeyContext,
+    KeyModeStack, Keymap, Keystroke, ModifiersChangedEvent, Window,
 };
 use collections::FxHashMap;
 use smallvec::SmallVec;
@@ -76,6 +76,7 @@ pub(crate) struct DispatchTree {
     focusable_node_ids: FxHashMap<FocusId, DispatchNodeId>,
     view_node_ids: FxHashMap<EntityId, DispatchNodeId>,
     keymap: Rc<RefCell<Keymap>>,
+    key_modes: Rc<RefCell<KeyModeStack>>,
     action_registry: Rc<ActionRegistry>,
 }
 
@@ -137,7 +138,11 @@ pub(crate) struct DispatchActionListener {
 }
 
 impl DispatchTree {
-    pub fn new(keymap: Rc<RefCell<Keymap>>, action_registry: Rc<ActionRegistry>) -> Self {
+    pub fn new(
+        keymap: Rc<RefCell<Keymap>>,
+        key_modes: Rc<RefCell<KeyModeStack>>,
+        action_registry: Rc<ActionRegistry>,
+    ) -> Self {
         Self {
             node_stack: Vec::new(),
             context_stack: Vec::new(),
@@ -146,6 +151,7 @@ impl DispatchTree {
             focusable_node_ids: FxHashMap::default(),
             view_node_ids: FxHashMap::default(),
             keymap,
+            key_modes,
             action_registry,
         }
     }
@@ -450,10 +456,7 @@ impl DispatchTree {
         input: &[Keystroke],
         dispatch_path: &SmallVec<[DispatchNodeId; 32]>,
     ) -> (SmallVec<[KeyBinding; 1]>, bool, Vec<KeyContext>) {
-        let context_stack: Vec<KeyContext> = dispatch_path
-            .iter()
-            .filter_map(|node_id| self.node(*node_id).context.clone())
-            .collect();
+        let context_stack = self.context_stack_for_path(dispatch_path);
 
         let (bindings, partial) = self
             .keymap
@@ -462,6 +465,29 @@ impl DispatchTree {
         (bindings, partial, context_stack)
     }
 
+    /// Returns the contexts of the nodes along a dispatch path, followed by those of the window's
+    /// key modes, which are layered above the focused node.
+    pub fn context_stack_for_path(&self, dispatch_path: &[DispatchNodeId]) -> Vec<KeyContext> {
+        dispatch_path
+            .iter()
+            .filter_map(|node_id| self.node(*node_id).context.clone())
+            .chain(self.key_modes.borrow().contexts().cloned())
+            .collect()
+    }
+
+    /// Returns the bindings that could match if more keystrokes were added to the input, in
+    /// precedence order.
+    pub fn pending_bindings_for_input(
+        &self,
+        input: &[Keystroke],
+        dispatch_path: &SmallVec<[DispatchNodeId; 32]>,
+    ) -> Vec<KeyBinding> {
+        let context_stack = self.context_stack_for_path(dispatch_path);
+        self.keymap
+            .borrow()
+            .pending_bindings_for_input(input, &context_stack)
+    }
+
     /// dispatch_key processes the keystroke
     /// input should be set to the value of `pending` from the previous call to dispatch_key.
     /// This returns three instructions to the input handler:
@@ -614,13 +640,14 @@ mod tests {
         Keystroke, LayoutId, Style,
     };
     use core::panic;
+    use itertools::Itertools as _;
     use smallvec::SmallVec;
-    use std::{cell::RefCell, ops::Range, rc::Rc};
+    use std::{cell::RefCell, ops::Range, rc::Rc, time::Duration};
 
     use crate::{
         Action, ActionRegistry, App, Bounds, Context, DispatchTree, FocusHandle, InputHandler,
-        IntoElement, KeyBinding, KeyContext, Keymap, Pixels, Point, Render, TestAppContext,
-        UTF16Selection, Window,
+        InteractiveElement, IntoElement, KeyBinding, KeyContext, Keymap, Pixels, Point, Render,
+        Subscription, TestAppContext, UTF16Selection, Window, div,
     };
 
     #[derive(PartialEq, Eq)]
@@ -668,7 +695,7 @@ mod tests {
 
         let keymap = Rc::new(RefCell::new(keymap));
 
-        let tree = DispatchTree::new(keymap, Rc::new(registry));
+        let tree = DispatchTree::new(keymap, Default::default(), Rc::new(registry));
 
         let contexts = vec![
             KeyContext::parse("Workspace").unwrap(),
@@ -690,7 +717,7 @@ mod tests {
         let keymap = Rc::new(RefCell::new(Keymap::new(bindings)));
         let mut registry = ActionRegistry::default();
         registry.load_action::<TestAction>();
-        let mut tree = DispatchTree::new(keymap, Rc::new(registry));
+        let mut tree = DispatchTree::new(keymap, Default::default(), Rc::new(registry));
 
         type DispatchPath = SmallVec<[super::DispatchNodeId; 32]>;
         fn dispatch(
@@ -883,4 +910,86 @@ mod tests {
         cx.simulate_keystrokes("ctrl-b [");
         test.update(cx, |test, _| assert_eq!(test.text.borrow().as_str(), "["))
     }
+
+    #[crate::test]
+    fn test_pending_input_events(cx: &mut TestAppContext) {
+        struct PendingInputView {
+            focus_handle: FocusHandle,
+            other_focus_handle: FocusHandle,
+            /// The keystrokes of each pending input event, with whether it had a timeout
+            events: Vec<(String, bool)>,
+            dispatched: usize,
+            _subscription: Subscription,
+        }
+        impl Render for PendingInputView {
+            fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+                div()
+                    .key_context("Terminal")
+                    .track_focus(&self.focus_handle)
+                    .on_action(cx.listener(|this, _: &TestAction, _, _| this.dispatched += 1))
+            }
+        }
+
+        cx.update(|cx| {
+            cx.bind_keys([
+                KeyBinding::new("ctrl-k", TestAction, Some("Terminal")),
+                KeyBinding::new("ctrl-k ctrl-c", TestAction, Some("Terminal")),
+            ]);
+            cx.set_multi_stroke_timeout(Duration::from_millis(100));
+        });
+        let (view, cx) = cx.add_window_view(|window, cx| PendingInputView {
+            focus_handle: cx.focus_handle(),
+            other_focus_handle: cx.focus_handle(),
+            events: Vec::new(),
+            dispatched: 0,
+            _subscription: cx.on_pending_input(window, |this, event, _, _| {
+                let keystrokes = event.keystrokes.iter().map(Keystroke::unparse).join(" ");
+                this.events.push((keystrokes, event.timeout.is_some()));
+            }),
+        });
+        cx.update(|window, cx| {
+            window.focus(&view.read(cx).focus_handle);
+            window.activate_window();
+        });
+        cx.run_until_parked();
+
+        // The pending keystroke is dispatched on its own after the timeout, ending the input.
+        cx.simulate_keystrokes("ctrl-k");
+        cx.executor().advance_clock(Duration::from_millis(100));
+        cx.run_until_parked();
+        view.update(cx, |view, _| {
+            assert_eq!(
+                view.events,
+                [("ctrl-k".to_string(), true), (String::new(), false)]
+            );
+            assert_eq!(view.dispatched, 1);
+            view.events.clear();
+        });
+
+        // Cancelling the input ends it without dispatching anything.
+        cx.simulate_keystrokes("ctrl-k");
+        cx.update(|window, cx| window.cancel_pending_input(cx));
+        cx.executor().advance_clock(Duration::from_millis(100));
+        cx.run_until_parked();
+        view.update(cx, |view, _| {
+            assert_eq!(
+                view.events,
+                [("ctrl-k".to_string(), true), (String::new(), false)]
+            );
+            assert_eq!(view.dispatched, 1);
+            view.events.clear();
+        });
+
+        // Moving focus clears the input, which is reported as ended once the window draws.
+        cx.simulate_keystrokes("ctrl-k");
+        cx.update(|window, cx| window.focus(&view.read(cx).other_focus_handle));
+        cx.run_until_parked();
+        view.update(cx, |view, _| {
+            assert_eq!(
+                view.events,
+                [("ctrl-k".to_string(), true), (String::new(), false)]
+            );
+            assert_eq!(view.dispatched, 1);
+        });
+    }
 }
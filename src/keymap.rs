@@ -1,8 +1,12 @@
+mod analysis;
 mod binding;
 mod context;
+mod file;
 
+pub use analysis::*;
 pub use binding::*;
 pub use context::*;
+pub use file::*;
 
 use crate::{Action, AsKeystroke, Keystroke, is_no_action};
 use collections::{HashMap, HashSet};
@@ -66,6 +70,23 @@ impl Keymap {
         self.version.0 += 1;
     }
 
+    /// Replace the bindings for which the predicate returns true with the given ones, which take
+    /// the position of the first binding removed, or are added last if none were.
+    pub fn replace_bindings(
+        &mut self,
+        mut f: impl FnMut(&KeyBinding) -> bool,
+        replacements: impl IntoIterator<Item = KeyBinding>,
+    ) {
+        let bindings = std::mem::take(&mut self.bindings);
+        let position = bindings.iter().position(&mut f).unwrap_or(bindings.len());
+        let mut bindings = bindings.into_iter();
+        let mut kept = bindings.by_ref().take(position).collect::<Vec<_>>();
+        kept.extend(replacements);
+        kept.extend(bindings.filter(|binding| !f(binding)));
+        self.clear();
+        self.add_bindings(kept);
+    }
+
     /// Iterate over all bindings, in the order they were added.
     pub fn bindings(&self) -> impl DoubleEndedIterator<Item = &KeyBinding> + ExactSizeIterator {
         self.bindings.iter()
@@ -206,6 +227,38 @@ impl Keymap {
 
         (bindings, !pending.is_empty())
     }
+
+    /// Returns the bindings that could still match if the input was longer, such as those that
+    /// a "which-key" popup would list. Bindings are returned in precedence order, and those
+    /// disabled with [`NoAction`](crate::NoAction) are left out.
+    pub fn pending_bindings_for_input(
+        &self,
+        input: &[impl AsKeystroke],
+        context_stack: &[KeyContext],
+    ) -> Vec<KeyBinding> {
+        let mut pending = Vec::<(usize, &KeyBinding)>::new();
+        for binding in self.bindings() {
+            let Some(depth) = self.binding_enabled(binding, context_stack) else {
+                continue;
+            };
+            if binding.match_keystrokes(input) != Some(true) {
+                continue;
+            }
+            if is_no_action(&*binding.action) {
+                pending.retain(|(_, pending)| pending.keystrokes != binding.keystrokes);
+                continue;
+            }
+            pending.push((depth, binding));
+        }
+
+        // Sorting is stable, so later bindings stay ahead of earlier ones at the same depth.
+        pending.reverse();
+        pending.sort_by(|(depth_a, _), (depth_b, _)| depth_b.cmp(depth_a));
+        pending
+            .into_iter()
+            .map(|(_, binding)| binding.clone())
+            .collect()
+    }
     /// Check if the given binding is enabled, given a certain key context.
     /// Returns the deepest depth at which the binding matches, or None if it doesn't match.
     fn binding_enabled(&self, binding: &KeyBinding, contexts: &[KeyContext]) -> Option<usize> {
@@ -712,4 +765,29 @@ mod tests {
         assert!(result[0].action.partial_eq(&ActionBeta {}));
         assert!(result[1].action.partial_eq(&ActionAlpha {}));
     }
+
+    #[test]
+    fn test_pending_bindings_for_input() {
+        let keymap = Keymap::new(vec![
+            KeyBinding::new("ctrl-k ctrl-a", ActionAlpha {}, Some("pane")),
+            KeyBinding::new("ctrl-k ctrl-b", ActionBeta {}, Some("editor")),
+            KeyBinding::new("ctrl-k ctrl-c", ActionGamma {}, None),
+            KeyBinding::new("ctrl-k ctrl-d", ActionDelta {}, Some("terminal")),
+            KeyBinding::new("ctrl-k ctrl-c", NoAction {}, Some("editor")),
+            KeyBinding::new("ctrl-k", ActionDelta {}, None),
+        ]);
+
+        let pending = keymap.pending_bindings_for_input(
+            &[Keystroke::parse("ctrl-k").unwrap()],
+            &[
+                KeyContext::parse("pane").unwrap(),
+                KeyContext::parse("editor").unwrap(),
+            ],
+        );
+        let keystrokes = pending
+            .iter()
+            .map(|binding| binding.keystrokes[1].inner().unparse())
+            .collect::<Vec<_>>();
+        assert_eq!(keystrokes, ["ctrl-b", "ctrl-a"]);
+    }
 }
@@ -0,0 +1,237 @@
+use std::rc::Rc;
+
+use collections::{HashMap, HashSet};
+
+use crate::{Action, KeyBinding, KeyBindingContextPredicate, Keymap, NoAction, is_no_action};
+
+/// A binding that doesn't behave as it might be expected to, because of another binding in the
+/// same keymap. See [`Keymap::analyze`].
+#[derive(Clone, Debug)]
+pub enum KeymapConflict {
+    /// The binding can never fire, because a binding added later has the same keystrokes and
+    /// applies in every context that it does.
+    Overridden {
+        /// The binding that can never fire
+        binding: KeyBinding,
+        /// The binding that fires instead
+        by: KeyBinding,
+    },
+    /// The multi-stroke binding can never fire, because a binding added later for a prefix of
+    /// its keystrokes applies in every context that it does, and fires as soon as the prefix is
+    /// typed.
+    PrefixOverridden {
+        /// The multi-stroke binding that can never fire
+        binding: KeyBinding,
+        /// The binding for the prefix, which fires instead
+        by: KeyBinding,
+    },
+    /// The binding only fires once the multi-stroke timeout has passed, because a binding added
+    /// later starts with its keystrokes and applies in every context that it does, so typing them
+    /// waits for the rest of the longer binding.
+    Delayed {
+        /// The binding that's delayed
+        binding: KeyBinding,
+        /// The multi-stroke binding that it's a prefix of
+        by: KeyBinding,
+    },
+}
+
+impl KeymapConflict {
+    /// The binding that doesn't behave as expected.
+    pub fn binding(&self) -> &KeyBinding {
+        match self {
+            Self::Overridden { binding, .. }
+            | Self::PrefixOverridden { binding, .. }
+            | Self::Delayed { binding, .. } => binding,
+        }
+    }
+}
+
+/// The problems found in a keymap by [`Keymap::analyze`].
+#[derive(Clone, Debug, Default)]
+pub struct KeymapAnalysis {
+    /// The bindings that don't behave as expected, in the order they were added to the keymap
+    pub conflicts: Vec<KeymapConflict>,
+    /// The names of the given actions that no binding fires, in the order they were given
+    pub unbound_actions: Vec<&'static str>,
+}
+
+impl Keymap {
+    /// Find the bindings of this keymap that can never fire or are delayed because of other
+    /// bindings, and which of the given action names have no binding that can fire. Pass
+    /// [`crate::App::all_action_names`] to check all registered actions.
+    ///
+    /// Bindings added later take precedence, as they do when matching input. A binding shadows
+    /// another when it has no context, or when its context is a superset of the other's as
+    /// determined by [`KeyBindingContextPredicate::is_superset`], which is conservative: some
+    /// shadowed bindings may not be reported, but those reported are shadowed. Bindings that
+    /// disable others with [`NoAction`] are intentional, so they aren't reported.
+    ///
+    /// A binding that's overridden still fires when the action of the binding that overrides it
+    /// isn't handled where its keystrokes are typed.
+    pub fn analyze(&self, action_names: &[&'static str]) -> KeymapAnalysis {
+        let bindings = self.bindings.as_slice();
+
+        // Only bindings that start with the same keystroke can conflict.
+        let mut indices_by_first_keystroke = HashMap::<_, Vec<usize>>::default();
+        for (ix, binding) in bindings.iter().enumerate() {
+            if let Some(first) = binding.keystrokes.first() {
+                indices_by_first_keystroke
+                    .entry(first)
+                    .or_default()
+                    .push(ix);
+            }
+        }
+
+        let mut conflicts = Vec::new();
+        let mut unreachable = HashSet::default();
+        for (ix, binding) in bindings.iter().enumerate() {
+            if is_no_action(binding.action()) {
+                continue;
+            }
+            let Some(first) = binding.keystrokes.first() else {
+                continue;
+            };
+            let later_bindings = indices_by_first_keystroke[first]
+                .iter()
+                .filter(|later_ix| **later_ix > ix)
+                .map(|later_ix| &bindings[*later_ix])
+                .filter(|later| covers(&later.context_predicate, &binding.context_predicate));
+
+            for later in later_bindings {
+                let conflict = if later.keystrokes == binding.keystrokes {
+                    if is_no_action(later.action()) {
+                        // The binding was disabled on purpose.
+                        unreachable.insert(ix);
+                        break;
+                    }
+                    KeymapConflict::Overridden {
+                        binding: binding.clone(),
+                        by: later.clone(),
+                    }
+                } else if binding.keystrokes.starts_with(&later.keystrokes) {
+                    if is_no_action(later.action()) {
+                        continue;
+                    }
+                    KeymapConflict::PrefixOverridden {
+                        binding: binding.clone(),
+                        by: later.clone(),
+                    }
+                } else if later.keystrokes.starts_with(&binding.keystrokes) {
+                    if is_no_action(later.action()) {
+                        continue;
+                    }
+                    conflicts.push(KeymapConflict::Delayed {
+                        binding: binding.clone(),
+                        by: later.clone(),
+                    });
+                    continue;
+                } else {
+                    continue;
+                };
+                conflicts.push(conflict);
+                unreachable.insert(ix);
+                break;
+            }
+        }
+
+        let bound_actions = bindings
+            .iter()
+            .enumerate()
+            .filter(|(ix, _)| !unreachable.contains(ix))
+            .map(|(_, binding)| binding.action().name())
+            .collect::<HashSet<_>>();
+        let unbound_actions = action_names
+            .iter()
+            .copied()
+            .filter(|name| *name != NoAction.name() && !bound_actions.contains(name))
+            .collect();
+
+        KeymapAnalysis {
+            conflicts,
+            unbound_actions,
+        }
+    }
+}
+
+/// Whether a binding with the first context applies everywhere one with the second does.
+fn covers(
+    predicate: &Option<Rc<KeyBindingContextPredicate>>,
+    other: &Option<Rc<KeyBindingContextPredicate>>,
+) -> bool {
+    match (predicate, other) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(predicate), Some(other)) => predicate.is_superset(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{self as gpui, KeybindingKeystroke, actions};
+
+    actions!(test_only, [CopyLine, CutLine, PasteLine, DeleteLine]);
+
+    fn keystrokes(binding: &KeyBinding) -> String {
+        binding
+            .keystrokes()
+            .iter()
+            .map(KeybindingKeystroke::unparse)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_analyze() {
+        let keymap = Keymap::new(vec![
+            KeyBinding::new("ctrl-c", CopyLine, Some("Editor")),
+            KeyBinding::new("ctrl-x", CutLine, Some("Editor && mode == full")),
+            KeyBinding::new("ctrl-v", PasteLine, Some("Editor")),
+            KeyBinding::new("ctrl-k ctrl-c", CopyLine, None),
+            // Overrides the first binding.
+            KeyBinding::new("ctrl-c", CutLine, None),
+            // Doesn't apply everywhere the binding it has the same keystrokes as does.
+            KeyBinding::new("ctrl-v", CopyLine, Some("Editor && mode == full")),
+            // Delays the binding for its prefix.
+            KeyBinding::new("ctrl-x ctrl-x", PasteLine, Some("Editor")),
+            // Fires before the rest of the earlier multi-stroke binding can be typed.
+            KeyBinding::new("ctrl-k", PasteLine, None),
+            KeyBinding::new("ctrl-v", NoAction, None),
+        ]);
+
+        let analysis = keymap.analyze(&[
+            CopyLine.name(),
+            CutLine.name(),
+            PasteLine.name(),
+            DeleteLine.name(),
+            NoAction.name(),
+        ]);
+
+        let conflicts = analysis
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                let (kind, by) = match conflict {
+                    KeymapConflict::Overridden { by, .. } => ("overridden", by),
+                    KeymapConflict::PrefixOverridden { by, .. } => ("prefix overridden", by),
+                    KeymapConflict::Delayed { by, .. } => ("delayed", by),
+                };
+                (keystrokes(conflict.binding()), kind, keystrokes(by))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            [
+                ("ctrl-c".into(), "overridden", "ctrl-c".into()),
+                ("ctrl-x".into(), "delayed", "ctrl-x ctrl-x".into()),
+                ("ctrl-k ctrl-c".into(), "prefix overridden", "ctrl-k".into()),
+            ]
+        );
+        // Every binding for copying is shadowed.
+        assert_eq!(
+            analysis.unbound_actions,
+            [CopyLine.name(), DeleteLine.name()]
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Loads key bindings from JSON keymap files.
+
+use std::{borrow::Cow, fmt, marker::PhantomData, rc::Rc};
+
+use serde::{
+    Deserialize, Deserializer,
+    de::{MapAccess, Visitor},
+};
+use serde_json::value::RawValue;
+
+use crate::{
+    App, KeyBinding, KeyBindingContextPredicate, KeybindingKeystroke, Keymap, NoAction,
+    SharedString,
+};
+
+/// An error in a keymap file, at the position it was found.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}, column {column}: {message}")]
+pub struct KeymapLoadError {
+    /// The line of the error, starting at 1
+    pub line: usize,
+    /// The column of the error within its line, in characters starting at 1
+    pub column: usize,
+    /// A description of the error
+    pub message: String,
+}
+
+/// The bindings loaded from a keymap file, see [`Keymap::load_from_json`].
+pub struct LoadedKeymap {
+    /// The bindings that loaded, in the order they appear in the file
+    pub bindings: Vec<KeyBinding>,
+    /// The errors in the bindings that didn't load, in the order they appear in the file
+    pub errors: Vec<KeymapLoadError>,
+}
+
+impl Keymap {
+    /// Load key bindings from the JSON of a keymap file. Actions are built with the registered
+    /// actions, so their names and arguments are checked against what their
+    /// [`crate::App::action_schemas`] describe.
+    ///
+    /// A keymap file is an array of sections, each of which binds keystrokes to actions within an
+    /// optional context:
+    ///
+    /// ```json
+    /// [
+    ///   {
+    ///     "bindings": {
+    ///       "ctrl-s": "workspace::Save"
+    ///     }
+    ///   },
+    ///   {
+    ///     "context": "Editor && mode == full",
+    ///     "use_key_equivalents": true,
+    ///     "bindings": {
+    ///       "ctrl-k ctrl-c": ["editor::ToggleComments", { "advance_downwards": true }],
+    ///       "ctrl-x": null
+    ///     }
+    ///   }
+    /// ]
+    /// ```
+    ///
+    /// - `context` is a [`KeyBindingContextPredicate`] that must match for the section's bindings
+    ///   to apply. Sections without one apply everywhere.
+    /// - `use_key_equivalents` maps the keystrokes to the keys at the same position on the current
+    ///   keyboard layout, see [`KeybindingKeystroke::new_with_mapper`].
+    /// - `bindings` maps space-separated keystrokes to an action, given either by its name, by an
+    ///   array of its name and its arguments, or as `null` to unbind the keystrokes with
+    ///   [`NoAction`].
+    ///
+    /// Later bindings take precedence over earlier ones, as with [`Keymap::add_bindings`].
+    ///
+    /// A binding that fails to load doesn't prevent the others from loading, its error is
+    /// returned alongside them instead.
+    pub fn load_from_json(source: &str, cx: &App) -> LoadedKeymap {
+        let sections = match serde_json::from_str::<Vec<KeymapSection>>(source) {
+            Ok(sections) => sections,
+            Err(error) => {
+                // The message includes the position, which is reported separately.
+                let message = error.to_string();
+                let position = format!(" at line {} column {}", error.line(), error.column());
+                return LoadedKeymap {
+                    bindings: Vec::new(),
+                    errors: vec![KeymapLoadError {
+                        line: error.line(),
+                        column: error.column(),
+                        message: message
+                            .strip_suffix(&position)
+                            .unwrap_or(&message)
+                            .to_string(),
+                    }],
+                };
+            }
+        };
+
+        let mut bindings = Vec::new();
+        let mut errors = Vec::new();
+        let mut error_at = |part: &str, message: String| {
+            let (line, column) = position_in(source, part);
+            errors.push(KeymapLoadError {
+                line,
+                column,
+                message,
+            });
+        };
+
+        for section in sections {
+            let context_predicate = match section.context {
+                None => None,
+                Some(context) => {
+                    match serde_json::from_str::<String>(context.get())
+                        .map_err(anyhow::Error::from)
+                        .and_then(|context| KeyBindingContextPredicate::parse(&context))
+                    {
+                        Ok(predicate) => Some(Rc::new(predicate)),
+                        Err(error) => {
+                            error_at(context.get(), format!("invalid context: {error}"));
+                            // None of the section's bindings could apply where they're meant to.
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            for (keystrokes, action) in section.bindings.0 {
+                // Escaped keystrokes aren't borrowed from the source, so the action is the
+                // closest position for them.
+                let keystrokes_position = match &keystrokes {
+                    Cow::Borrowed(keystrokes) => *keystrokes,
+                    Cow::Owned(_) => action.get(),
+                };
+                let (action, action_input) = match build_action(action, cx) {
+                    Ok(action) => action,
+                    Err(message) => {
+                        error_at(action.get(), message);
+                        continue;
+                    }
+                };
+                match KeyBinding::load(
+                    &keystrokes,
+                    action,
+                    context_predicate.clone(),
+                    section.use_key_equivalents,
+                    action_input,
+                    cx.keyboard_mapper().as_ref(),
+                ) {
+                    Ok(binding) => bindings.push(binding),
+                    Err(error) => error_at(keystrokes_position, error.to_string()),
+                }
+            }
+        }
+
+        LoadedKeymap { bindings, errors }
+    }
+}
+
+/// Builds the action of a binding, returning it along with the JSON of its arguments.
+fn build_action(
+    action: &RawValue,
+    cx: &App,
+) -> Result<(Box<dyn crate::Action>, Option<SharedString>), String> {
+    let value = serde_json::from_str::<serde_json::Value>(action.get())
+        .map_err(|error| error.to_string())?;
+    let (name, arguments) = match value {
+        serde_json::Value::Null => return Ok((Box::new(NoAction), None)),
+        serde_json::Value::String(name) => (name, None),
+        serde_json::Value::Array(mut items) => match items.as_slice() {
+            [serde_json::Value::String(_), _] => {
+                let arguments = items.pop();
+                let Some(serde_json::Value::String(name)) = items.pop() else {
+                    unreachable!()
+                };
+                (name, arguments)
+            }
+            _ => {
+                return Err(
+                    "expected an array of an action name and its arguments, like \
+                    [\"editor::MoveUp\", { \"lines\": 2 }]"
+                        .to_string(),
+                );
+            }
+        },
+        _ => {
+            return Err(
+                "expected an action name, an array of an action name and its arguments, or null"
+                    .to_string(),
+            );
+        }
+    };
+    let action_input = arguments
+        .as_ref()
+        .map(|arguments| SharedString::from(arguments.to_string()));
+    let action = cx
+        .build_action(&name, arguments)
+        .map_err(|error| error.to_string())?;
+    Ok((action, action_input))
+}
+
+/// Returns the line and column at which `part`, a slice of `source`, starts.
+fn position_in(source: &str, part: &str) -> (usize, usize) {
+    let offset = (part.as_ptr() as usize).wrapping_sub(source.as_ptr() as usize);
+    let before = source.get(..offset).unwrap_or("");
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+    (line, column)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapSection<'a> {
+    #[serde(default, borrow)]
+    context: Option<&'a RawValue>,
+    #[serde(default)]
+    use_key_equivalents: bool,
+    #[serde(default, borrow)]
+    bindings: Bindings<'a>,
+}
+
+/// The bindings of a section, in the order they're written in, which is their precedence.
+#[derive(Default)]
+struct Bindings<'a>(Vec<(Cow<'a, str>, &'a RawValue)>);
+
+impl<'de: 'a, 'a> Deserialize<'de> for Bindings<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BindingsVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for BindingsVisitor<'a> {
+            type Value = Bindings<'a>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map from keystrokes to actions")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut bindings = Vec::new();
+                while let Some((Keystrokes(keystrokes), action)) = map.next_entry()? {
+                    bindings.push((keystrokes, action));
+                }
+                Ok(Bindings(bindings))
+            }
+        }
+
+        deserializer.deserialize_map(BindingsVisitor(PhantomData))
+    }
+}
+
+/// Keystrokes that are borrowed from the source when possible, to find their position in it.
+struct Keystrokes<'a>(Cow<'a, str>);
+
+impl<'de: 'a, 'a> Deserialize<'de> for Keystrokes<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeystrokesVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for KeystrokesVisitor<'a> {
+            type Value = Keystrokes<'a>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("keystrokes")
+            }
+
+            fn visit_borrowed_str<E>(self, keystrokes: &'de str) -> Result<Self::Value, E> {
+                Ok(Keystrokes(Cow::Borrowed(keystrokes)))
+            }
+
+            fn visit_str<E>(self, keystrokes: &str) -> Result<Self::Value, E> {
+                Ok(Keystrokes(Cow::Owned(keystrokes.to_string())))
+            }
+        }
+
+        deserializer.deserialize_str(KeystrokesVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{self as gpui, Action, KeyBindingMetaIndex, TestAppContext, actions};
+    use schemars::JsonSchema;
+    use std::{
+        cell::RefCell,
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    actions!(test_only, [SaveDocument]);
+
+    #[derive(Clone, Debug, PartialEq, Deserialize, JsonSchema, Action)]
+    #[action(namespace = test_only)]
+    struct ScrollLines {
+        lines: u32,
+    }
+
+    #[gpui::test]
+    fn test_load_from_json(cx: &mut TestAppContext) {
+        let source = r#"[
+            {
+                "bindings": {
+                    "ctrl-s": "test_only::SaveDocument",
+                    "ctrl-x": null
+                }
+            },
+            {
+                "context": "Editor && mode == full",
+                "bindings": {
+                    "ctrl-e ctrl-e": ["test_only::ScrollLines", { "lines": 3 }],
+                    "ctrl-d": "test_only::Missing",
+                    "ctrl-f": ["test_only::ScrollLines", { "lines": "many" }],
+                    "ctrl-a-b": "test_only::SaveDocument"
+                }
+            },
+            {
+                "context": "Editor &&",
+                "bindings": { "ctrl-g": "test_only::SaveDocument" }
+            }
+        ]"#;
+        let loaded = cx.update(|cx| Keymap::load_from_json(source, cx));
+
+        let bindings = loaded
+            .bindings
+            .iter()
+            .map(|binding| {
+                let keystrokes = binding
+                    .keystrokes()
+                    .iter()
+                    .map(KeybindingKeystroke::unparse)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (keystrokes, binding.action().name())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bindings,
+            [
+                ("ctrl-s".to_string(), "test_only::SaveDocument"),
+                ("ctrl-x".to_string(), "zed::NoAction"),
+                ("ctrl-e ctrl-e".to_string(), "test_only::ScrollLines"),
+            ]
+        );
+        assert!(
+            loaded.bindings[2]
+                .action()
+                .partial_eq(&ScrollLines { lines: 3 })
+        );
+        assert_eq!(
+            loaded.bindings[2].action_input().as_deref(),
+            Some(r#"{"lines":3}"#)
+        );
+
+        let positions = loaded
+            .errors
+            .iter()
+            .map(|error| (error.line, error.column))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [(12, 31), (13, 31), (14, 22), (18, 28)]);
+        assert!(loaded.errors[0].message.contains("test_only::Missing"));
+    }
+
+    #[gpui::test]
+    fn test_load_invalid_json(cx: &mut TestAppContext) {
+        let loaded = cx.update(|cx| {
+            Keymap::load_from_json("[\n  { \"bindings\": {}, \"bindigns\": {} }\n]", cx)
+        });
+        assert!(loaded.bindings.is_empty());
+        assert_eq!(loaded.errors.len(), 1);
+        assert_eq!(loaded.errors[0].line, 2);
+        assert!(
+            loaded.errors[0]
+                .message
+                .starts_with("unknown field `bindigns`")
+        );
+    }
+
+    #[gpui::test]
+    fn test_watch_keymap_file(cx: &mut TestAppContext) {
+        let path = std::env::temp_dir().join(format!("gpui-keymap-{}.json", std::process::id()));
+        // Modification times are set explicitly, as edits can be closer than their resolution.
+        let write = |source: &str, secs: u64| {
+            fs::write(&path, source).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        };
+        let keystrokes = |cx: &mut TestAppContext| {
+            cx.update(|cx| {
+                cx.key_bindings()
+                    .borrow()
+                    .bindings()
+                    .map(|binding| binding.keystrokes()[0].unparse())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        write(
+            r#"[{ "bindings": { "ctrl-s": "test_only::SaveDocument" } }]"#,
+            1,
+        );
+        cx.update(|cx| cx.bind_keys([KeyBinding::new("ctrl-a", SaveDocument, None)]));
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let _watch = cx.update(|cx| {
+            let errors = errors.clone();
+            cx.watch_keymap_file(&path, KeyBindingMetaIndex(1), move |load_errors, _| {
+                *errors.borrow_mut() = load_errors;
+            })
+        });
+        cx.run_until_parked();
+        cx.update(|cx| cx.bind_keys([KeyBinding::new("ctrl-b", SaveDocument, None)]));
+        assert_eq!(keystrokes(cx), ["ctrl-a", "ctrl-s", "ctrl-b"]);
+        assert!(errors.borrow().is_empty());
+
+        // Reloaded bindings take the place of the ones they replace.
+        let source = r#"[
+  { "bindings": {
+    "ctrl-t": "test_only::SaveDocument",
+    "ctrl-u": "test_only::Missing"
+  } }
+]"#;
+        write(source, 2);
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert_eq!(keystrokes(cx), ["ctrl-a", "ctrl-t", "ctrl-b"]);
+        let positions = errors
+            .borrow()
+            .iter()
+            .map(|error| (error.line, error.column))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [(4, 15)]);
+        assert!(errors.borrow()[0].message.contains("test_only::Missing"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,19 +1,23 @@
 use anyhow::Error;
+use collections::{FxHashMap, FxHasher};
 use etagere::euclid::{Point2D, Vector2D};
 use lyon::geom::Angle;
 use lyon::math::{Vector, vector};
 use lyon::path::traits::SvgPathBuilder;
-use lyon::path::{ArcFlags, Polygon};
+use lyon::path::{ArcFlags, PathEvent, Polygon};
 use lyon::tessellation::{
     BuffersBuilder, FillTessellator, FillVertex, StrokeTessellator, StrokeVertex, VertexBuffers,
 };
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 pub use lyon::math::Transform;
-pub use lyon::tessellation::{FillOptions, FillRule, StrokeOptions};
+pub use lyon::tessellation::{FillOptions, FillRule, LineCap, LineJoin, StrokeOptions};
 
 use crate::{Path, Pixels, Point, point, px};
 
 /// Style of the PathBuilder
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PathStyle {
     /// Stroke style
     Stroke(StrokeOptions),
@@ -28,6 +32,7 @@ pub struct PathBuilder {
     /// PathStyle of the PathBuilder
     pub style: PathStyle,
     dash_array: Option<Vec<Pixels>>,
+    dash_offset: Pixels,
 }
 
 impl From<lyon::path::Builder> for PathBuilder {
@@ -84,6 +89,7 @@ impl Default for PathBuilder {
             style: PathStyle::Fill(FillOptions::default().with_tolerance(DEFAULT_TOLERANCE)),
             transform: None,
             dash_array: None,
+            dash_offset: Pixels::ZERO,
         }
     }
 }
@@ -129,6 +135,52 @@ impl PathBuilder {
         self
     }
 
+    /// Sets how far into the dash array the dashes start, which can be animated to make the
+    /// dashes move along the path.
+    ///
+    /// [MDN](https://developer.mozilla.org/en-US/docs/Web/SVG/Reference/Attribute/stroke-dashoffset)
+    pub fn dash_offset(mut self, dash_offset: Pixels) -> Self {
+        self.dash_offset = dash_offset;
+        self
+    }
+
+    /// Sets the shape of the corners where the segments of a stroke path meet. This has no effect
+    /// on fill paths.
+    pub fn line_join(self, line_join: LineJoin) -> Self {
+        self.map_stroke_options(|options| options.with_line_join(line_join))
+    }
+
+    /// Sets the shape of the ends of a stroke path. This has no effect on fill paths.
+    pub fn line_cap(self, line_cap: LineCap) -> Self {
+        self.map_stroke_options(|options| options.with_line_cap(line_cap))
+    }
+
+    /// Sets how far, as a multiple of the stroke width, a [`LineJoin::Miter`] corner can extend
+    /// before it's beveled instead. The limit is at least 1. This has no effect on fill paths.
+    ///
+    /// [MDN](https://developer.mozilla.org/en-US/docs/Web/SVG/Reference/Attribute/stroke-miterlimit)
+    pub fn miter_limit(self, miter_limit: f32) -> Self {
+        self.map_stroke_options(|options| {
+            options.with_miter_limit(miter_limit.max(StrokeOptions::MINIMUM_MITER_LIMIT))
+        })
+    }
+
+    /// Sets the rule that decides which parts of a fill path are inside it, where they overlap.
+    /// This has no effect on stroke paths.
+    pub fn fill_rule(mut self, fill_rule: FillRule) -> Self {
+        if let PathStyle::Fill(options) = self.style {
+            self.style = PathStyle::Fill(options.with_fill_rule(fill_rule));
+        }
+        self
+    }
+
+    fn map_stroke_options(mut self, f: impl FnOnce(StrokeOptions) -> StrokeOptions) -> Self {
+        if let PathStyle::Stroke(options) = self.style {
+            self.style = PathStyle::Stroke(f(options));
+        }
+        self
+    }
+
     /// Move the current point to the given point.
     #[inline]
     pub fn move_to(&mut self, to: Point<Pixels>) {
@@ -248,6 +300,31 @@ impl PathBuilder {
         }
     }
 
+    /// Builds into a [`CachedPath`], which is tessellated when it's first painted rather than
+    /// now, so building one each frame is cheap.
+    pub fn build_cached(self) -> CachedPath {
+        let path = if let Some(transform) = self.transform {
+            self.raw.build().transformed(&transform)
+        } else {
+            self.raw.build()
+        };
+        let dash_array = self.dash_array.map(Arc::from);
+
+        let mut hasher = FxHasher::default();
+        hash_path(&path, &mut hasher);
+        hash_style(&self.style, &mut hasher);
+        dash_array.hash(&mut hasher);
+        self.dash_offset.hash(&mut hasher);
+
+        CachedPath {
+            key: hasher.finish(),
+            path: Arc::new(path),
+            style: self.style,
+            dash_array,
+            dash_offset: self.dash_offset,
+        }
+    }
+
     /// Builds into a [`Path`].
     #[inline]
     pub fn build(self) -> Result<Path<Pixels>, Error> {
@@ -258,7 +335,12 @@ impl PathBuilder {
         };
 
         match self.style {
-            PathStyle::Stroke(options) => Self::tessellate_stroke(self.dash_array, &path, &options),
+            PathStyle::Stroke(options) => Self::tessellate_stroke(
+                self.dash_array.as_deref(),
+                self.dash_offset,
+                &path,
+                &options,
+            ),
             PathStyle::Fill(options) => Self::tessellate_fill(&path, &options),
         }
     }
@@ -282,10 +364,16 @@ impl PathBuilder {
     }
 
     fn tessellate_stroke(
-        dash_array: Option<Vec<Pixels>>,
+        dash_array: Option<&[Pixels]>,
+        dash_offset: Pixels,
         path: &lyon::path::Path,
         options: &StrokeOptions,
     ) -> Result<Path<Pixels>, Error> {
+        // Dash arrays without any length are ignored, as in SVG.
+        let dash_array = dash_array.filter(|dash_array| {
+            dash_array.iter().map(|dash| dash.0).sum::<f32>() > 0.
+                && dash_array.iter().all(|dash| dash.0 >= 0.)
+        });
         let path = if let Some(dash_array) = dash_array {
             let measurements = lyon::algorithms::measure::PathMeasurements::from_path(path, 0.01);
             let mut sampler = measurements
@@ -294,13 +382,14 @@ impl PathBuilder {
 
             let total_length = sampler.length();
             let dash_array_len = dash_array.len();
-            let mut pos = 0.;
-            let mut dash_index = 0;
+            let (mut dash_index, dash_start) = dash_phase(dash_array, dash_offset);
+            // The first dash starts before the path does when the offset is part way into it.
+            let mut pos = -dash_start;
             while pos < total_length {
                 let dash_length = dash_array[dash_index % dash_array_len].0;
                 let next_pos = (pos + dash_length).min(total_length);
                 if dash_index % 2 == 0 {
-                    let start = pos / total_length;
+                    let start = pos.max(0.) / total_length;
                     let end = next_pos / total_length;
                     sampler.split_range(start..end, &mut builder);
                 }
@@ -335,22 +424,442 @@ impl PathBuilder {
 
         let first_point = buf.vertices[0];
 
-        let mut path = Path::new(first_point.into());
-        for i in 0..buf.indices.len() / 3 {
-            let i0 = buf.indices[i * 3] as usize;
-            let i1 = buf.indices[i * 3 + 1] as usize;
-            let i2 = buf.indices[i * 3 + 2] as usize;
-
-            let v0 = buf.vertices[i0];
-            let v1 = buf.vertices[i1];
-            let v2 = buf.vertices[i2];
+        // Edges that belong to only one triangle are on the outline of the path, and are
+        // anti-aliased. Edges are keyed by their positions, as the tessellators can emit the
+        // same position more than once.
+        let edge_key = |a: lyon::math::Point, b: lyon::math::Point| {
+            let a = (a.x.to_bits(), a.y.to_bits());
+            let b = (b.x.to_bits(), b.y.to_bits());
+            if a < b { (a, b) } else { (b, a) }
+        };
+        let triangles = buf
+            .indices
+            .chunks_exact(3)
+            .map(|indices| {
+                [
+                    buf.vertices[indices[0] as usize],
+                    buf.vertices[indices[1] as usize],
+                    buf.vertices[indices[2] as usize],
+                ]
+            })
+            .collect::<Vec<_>>();
+        let mut edge_counts = FxHashMap::default();
+        for [v0, v1, v2] in &triangles {
+            for (a, b) in [(v1, v2), (v2, v0), (v0, v1)] {
+                *edge_counts.entry(edge_key(*a, *b)).or_insert(0) += 1;
+            }
+        }
 
-            path.push_triangle(
+        let mut path = Path::new(first_point.into());
+        for [v0, v1, v2] in triangles {
+            let outline = [(v1, v2), (v2, v0), (v0, v1)]
+                .map(|(a, b)| edge_counts.get(&edge_key(a, b)) == Some(&1));
+            path.push_outlined_triangle(
                 (v0.into(), v1.into(), v2.into()),
                 (point(0., 1.), point(0., 1.), point(0., 1.)),
+                outline,
             );
         }
 
         path
     }
 }
+
+/// A path that's tessellated once for each scale factor it's painted at, rather than each time,
+/// and can be painted with a transform, such as to move it, without being tessellated again. See
+/// [`crate::Window::paint_cached_path`].
+///
+/// Tessellations are cached by the content of the path, so paths can be built each frame, and
+/// identical paths share a tessellation.
+#[derive(Clone)]
+pub struct CachedPath {
+    key: u64,
+    path: Arc<lyon::path::Path>,
+    style: PathStyle,
+    dash_array: Option<Arc<[Pixels]>>,
+    dash_offset: Pixels,
+}
+
+impl CachedPath {
+    /// A hash of the path and its style, which its tessellations are cached by.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Whether the two paths are tessellated the same way, which is checked as well as their keys
+    /// so that paths whose keys collide aren't painted with each other's tessellations.
+    fn has_same_content(&self, other: &CachedPath) -> bool {
+        self.style == other.style
+            && self.dash_array == other.dash_array
+            && self.dash_offset == other.dash_offset
+            && (Arc::ptr_eq(&self.path, &other.path) || self.path.iter().eq(other.path.iter()))
+    }
+
+    /// Tessellates the path for painting at the given scale factor, with a tolerance that's the
+    /// same in device pixels at any scale factor.
+    fn tessellate(&self, scale_factor: f32) -> Result<Path<Pixels>, Error> {
+        match self.style {
+            PathStyle::Stroke(options) => PathBuilder::tessellate_stroke(
+                self.dash_array.as_deref(),
+                self.dash_offset,
+                &self.path,
+                &options.with_tolerance(options.tolerance / scale_factor),
+            ),
+            PathStyle::Fill(options) => PathBuilder::tessellate_fill(
+                &self.path,
+                &options.with_tolerance(options.tolerance / scale_factor),
+            ),
+        }
+    }
+}
+
+/// The tessellations of the [`CachedPath`]s painted in a window, keyed by the content of the path
+/// and the scale factor. Tessellations that weren't painted in the previous frame are dropped,
+/// which also drops those for a scale factor that the window no longer has.
+#[derive(Default)]
+pub(crate) struct PathCache {
+    current_frame: FxHashMap<(u64, u32), PathCacheEntry>,
+    previous_frame: FxHashMap<(u64, u32), PathCacheEntry>,
+}
+
+struct PathCacheEntry {
+    path: CachedPath,
+    tessellation: Arc<Path<Pixels>>,
+}
+
+impl PathCache {
+    pub(crate) fn get_or_tessellate(
+        &mut self,
+        path: &CachedPath,
+        scale_factor: f32,
+    ) -> Result<Arc<Path<Pixels>>, Error> {
+        let key = (path.key, scale_factor.to_bits());
+        if let Some(entry) = self.current_frame.get(&key)
+            && entry.path.has_same_content(path)
+        {
+            return Ok(entry.tessellation.clone());
+        }
+        let entry = match self.previous_frame.remove(&key) {
+            Some(entry) if entry.path.has_same_content(path) => entry,
+            _ => PathCacheEntry {
+                path: path.clone(),
+                tessellation: Arc::new(path.tessellate(scale_factor)?),
+            },
+        };
+        let tessellation = entry.tessellation.clone();
+        self.current_frame.insert(key, entry);
+        Ok(tessellation)
+    }
+
+    /// Drops the tessellations that weren't painted in the frame before the one that's finished.
+    pub(crate) fn finish_frame(&mut self) {
+        self.previous_frame = std::mem::take(&mut self.current_frame);
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.current_frame.len() + self.previous_frame.len()
+    }
+}
+
+/// Returns the index of the dash that the dash offset is in, and how far into that dash it is.
+fn dash_phase(dash_array: &[Pixels], dash_offset: Pixels) -> (usize, f32) {
+    let pattern_length = dash_array.iter().map(|dash| dash.0).sum::<f32>();
+    let mut offset = dash_offset.0.rem_euclid(pattern_length);
+    for (index, dash) in dash_array.iter().enumerate() {
+        if offset < dash.0 {
+            return (index, offset);
+        }
+        offset -= dash.0;
+    }
+    (0, 0.)
+}
+
+fn hash_path(path: &lyon::path::Path, hasher: &mut impl Hasher) {
+    fn hash_point(point: lyon::math::Point, hasher: &mut impl Hasher) {
+        hasher.write_u32(point.x.to_bits());
+        hasher.write_u32(point.y.to_bits());
+    }
+
+    for event in path.iter() {
+        match event {
+            PathEvent::Begin { at } => {
+                hasher.write_u8(0);
+                hash_point(at, hasher);
+            }
+            PathEvent::Line { to, .. } => {
+                hasher.write_u8(1);
+                hash_point(to, hasher);
+            }
+            PathEvent::Quadratic { ctrl, to, .. } => {
+                hasher.write_u8(2);
+                hash_point(ctrl, hasher);
+                hash_point(to, hasher);
+            }
+            PathEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                hasher.write_u8(3);
+                hash_point(ctrl1, hasher);
+                hash_point(ctrl2, hasher);
+                hash_point(to, hasher);
+            }
+            PathEvent::End { close, .. } => {
+                hasher.write_u8(4);
+                close.hash(hasher);
+            }
+        }
+    }
+}
+
+fn hash_style(style: &PathStyle, hasher: &mut impl Hasher) {
+    match style {
+        PathStyle::Stroke(options) => {
+            hasher.write_u8(0);
+            hasher.write_u8(options.start_cap as u8);
+            hasher.write_u8(options.end_cap as u8);
+            hasher.write_u8(options.line_join as u8);
+            hasher.write_u32(options.line_width.to_bits());
+            options.variable_line_width.hash(hasher);
+            hasher.write_u32(options.miter_limit.to_bits());
+            hasher.write_u32(options.tolerance.to_bits());
+        }
+        PathStyle::Fill(options) => {
+            hasher.write_u8(1);
+            hasher.write_u8(options.fill_rule as u8);
+            hasher.write_u8(options.sweep_orientation as u8);
+            options.handle_intersections.hash(hasher);
+            hasher.write_u32(options.tolerance.to_bits());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScaledPixels;
+
+    fn star(offset: f32) -> PathBuilder {
+        let mut builder = PathBuilder::fill();
+        builder.move_to(point(px(offset + 50.), px(0.)));
+        builder.line_to(point(px(offset + 100.), px(100.)));
+        builder.line_to(point(px(offset), px(100.)));
+        builder.close();
+        builder
+    }
+
+    #[test]
+    fn test_path_cache() {
+        let a = star(0.).build_cached();
+        assert_eq!(a.key(), star(0.).build_cached().key());
+        assert_ne!(a.key(), star(1.).build_cached().key());
+        assert_ne!(
+            a.key(),
+            star(0.)
+                .with_style(PathStyle::Stroke(StrokeOptions::default()))
+                .build_cached()
+                .key()
+        );
+
+        let mut cache = PathCache::default();
+        let tessellation = cache.get_or_tessellate(&a, 1.).unwrap();
+        assert_eq!(tessellation.vertices.len(), 3);
+        // Identical paths share a tessellation, but each scale factor has its own.
+        let b = star(0.).build_cached();
+        assert!(Arc::ptr_eq(
+            &tessellation,
+            &cache.get_or_tessellate(&b, 1.).unwrap()
+        ));
+        cache.get_or_tessellate(&a, 2.).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // Tessellations are kept while they're painted in each frame.
+        cache.finish_frame();
+        assert!(Arc::ptr_eq(
+            &tessellation,
+            &cache.get_or_tessellate(&a, 1.).unwrap()
+        ));
+        cache.finish_frame();
+        assert_eq!(cache.len(), 1);
+        cache.finish_frame();
+        assert_eq!(cache.len(), 0);
+
+        // Paths whose keys collide aren't painted with each other's tessellations.
+        let mut c = star(1.).build_cached();
+        c.key = a.key;
+        let tessellation = cache.get_or_tessellate(&a, 1.).unwrap();
+        let other_tessellation = cache.get_or_tessellate(&c, 1.).unwrap();
+        assert_ne!(tessellation.bounds, other_tessellation.bounds);
+        cache.finish_frame();
+        assert!(Arc::ptr_eq(
+            &other_tessellation,
+            &cache.get_or_tessellate(&c, 1.).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_outline_edges() {
+        let mut builder = PathBuilder::fill();
+        builder.move_to(point(px(0.), px(0.)));
+        builder.line_to(point(px(10.), px(0.)));
+        builder.line_to(point(px(10.), px(10.)));
+        builder.line_to(point(px(0.), px(10.)));
+        builder.close();
+        let path = builder.build().unwrap();
+
+        // The rectangle is split into two triangles along a diagonal, which is the only edge of
+        // each triangle that isn't on the outline, and so isn't faded.
+        assert_eq!(path.vertices.len(), 6);
+        for triangle in path.vertices.chunks(3) {
+            let inner_edges = (0..3)
+                .filter(|&edge| {
+                    triangle
+                        .iter()
+                        .all(|vertex| vertex.edge_distances[edge] == 1.)
+                })
+                .count();
+            assert_eq!(inner_edges, 1);
+        }
+    }
+
+    /// How much of the pixel centered on `center` the path covers, worked out from the edge
+    /// distances of the triangle containing it the way `edge_coverage` in the path shaders does
+    /// when paths aren't multisampled, and so have had their outline pushed out.
+    fn pixel_coverage(path: &Path<ScaledPixels>, center: Point<f32>) -> f32 {
+        path.vertices
+            .chunks_exact(3)
+            .find_map(|triangle| {
+                let position = |index: usize| {
+                    let position = triangle[index % 3].xy_position;
+                    point(position.x.0, position.y.0)
+                };
+                let cross = |a: Point<f32>, b: Point<f32>| a.x * b.y - a.y * b.x;
+                let area = cross(position(1) - position(0), position(2) - position(0));
+                if area.abs() < 1e-6 {
+                    return None;
+                }
+                // The barycentric weight of each vertex at the center, and how it changes across
+                // the screen.
+                let weights = (0..3).map(|index| {
+                    let (next, last) = (position(index + 1), position(index + 2));
+                    let weight = cross(next - center, last - center) / area;
+                    let gradient = point(next.y - last.y, last.x - next.x) / area;
+                    (weight, gradient)
+                });
+                if weights.clone().any(|(weight, _)| weight < -1e-4) {
+                    return None;
+                }
+
+                let distance = (0..3)
+                    .map(|edge| {
+                        let (distance, gradient) = weights.clone().zip(triangle).fold(
+                            (0., point(0., 0.)),
+                            |(distance, gradient), ((weight, weight_gradient), vertex)| {
+                                let edge_distance = vertex.edge_distances[edge];
+                                (
+                                    distance + weight * edge_distance,
+                                    gradient + weight_gradient * edge_distance,
+                                )
+                            },
+                        );
+                        distance / gradient.x.hypot(gradient.y).max(1e-6)
+                    })
+                    .fold(f32::INFINITY, f32::min);
+                Some(distance.clamp(0., 1.))
+            })
+            .unwrap_or(0.)
+    }
+
+    #[test]
+    fn test_thin_stroke_coverage() {
+        let stroke = |y: f32| {
+            let mut builder = PathBuilder::stroke(px(1.));
+            builder.move_to(point(px(0.), px(y)));
+            builder.line_to(point(px(10.), px(y)));
+            let path = builder.build().unwrap().scale(1.);
+            path.outset_outline(ScaledPixels(0.5))
+        };
+        let column_coverage = |path: &Path<ScaledPixels>| {
+            (0..10)
+                .map(|row| pixel_coverage(path, point(4.5, row as f32 + 0.5)))
+                .collect::<Vec<_>>()
+        };
+
+        // A 1px stroke through the middle of a row of pixels covers them fully, and isn't faded
+        // out by its edges being half a pixel away.
+        let coverage = column_coverage(&stroke(5.5));
+        assert!((coverage[5] - 1.).abs() < 0.01, "{coverage:?}");
+        assert!(
+            (coverage.iter().sum::<f32>() - 1.).abs() < 0.01,
+            "{coverage:?}"
+        );
+
+        // A 1px stroke between two rows covers half of each.
+        let coverage = column_coverage(&stroke(5.));
+        assert!((coverage[4] - 0.5).abs() < 0.01, "{coverage:?}");
+        assert!((coverage[5] - 0.5).abs() < 0.01, "{coverage:?}");
+        assert!(
+            (coverage.iter().sum::<f32>() - 1.).abs() < 0.01,
+            "{coverage:?}"
+        );
+
+        // A 1px stroke that isn't aligned to the pixels covers the row whose center is outside
+        // of it by as much as it overlaps that row.
+        let coverage = column_coverage(&stroke(5.25));
+        assert!((coverage[4] - 0.25).abs() < 0.01, "{coverage:?}");
+        assert!((coverage[5] - 0.75).abs() < 0.01, "{coverage:?}");
+        assert!(
+            (coverage.iter().sum::<f32>() - 1.).abs() < 0.01,
+            "{coverage:?}"
+        );
+    }
+
+    #[test]
+    fn test_dash_phase() {
+        let dash_array = [px(4.), px(2.)];
+        assert_eq!(dash_phase(&dash_array, px(0.)), (0, 0.));
+        assert_eq!(dash_phase(&dash_array, px(5.)), (1, 1.));
+        assert_eq!(dash_phase(&dash_array, px(13.)), (0, 1.));
+        // Negative offsets move the dashes the other way along the path.
+        assert_eq!(dash_phase(&dash_array, px(-1.)), (1, 1.));
+    }
+
+    fn assert_approx_eq(actual: Pixels, expected: f32) {
+        assert!(
+            (actual.0 - expected).abs() < 0.01,
+            "expected {expected}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn test_stroke_styles() {
+        let line = |mut builder: PathBuilder| {
+            builder.move_to(point(px(0.), px(0.)));
+            builder.line_to(point(px(12.), px(0.)));
+            builder.build().unwrap()
+        };
+
+        let butt = line(PathBuilder::stroke(px(2.)));
+        let square = line(PathBuilder::stroke(px(2.)).line_cap(LineCap::Square));
+        assert_approx_eq(butt.bounds.origin.x, 0.);
+        assert_approx_eq(square.bounds.origin.x, -1.);
+
+        // Offsetting the dashes by half a dash moves where they start and end.
+        let dashes = line(PathBuilder::stroke(px(2.)).dash_array(&[px(4.), px(2.)]));
+        let offset = line(
+            PathBuilder::stroke(px(2.))
+                .dash_array(&[px(4.), px(2.)])
+                .dash_offset(px(2.)),
+        );
+        assert_approx_eq(dashes.bounds.size.width, 10.);
+        assert_approx_eq(offset.bounds.size.width, 12.);
+        // An empty dash array is ignored rather than looping forever.
+        let solid = line(PathBuilder::stroke(px(2.)).dash_array(&[px(0.)]));
+        assert_approx_eq(solid.bounds.size.width, 12.);
+
+        let builder = PathBuilder::fill().fill_rule(FillRule::NonZero);
+        assert!(
+            matches!(builder.style, PathStyle::Fill(options) if options.fill_rule == FillRule::NonZero)
+        );
+    }
+}
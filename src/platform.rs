@@ -1,6 +1,9 @@
 mod app_menu;
+mod global_hotkey;
 mod keyboard;
 mod keystroke;
+mod notification;
+mod status_item;
 
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 mod linux;
@@ -77,8 +80,11 @@ use strum::EnumIter;
 use uuid::Uuid;
 
 pub use app_menu::*;
+pub use global_hotkey::*;
 pub use keyboard::*;
 pub use keystroke::*;
+pub use notification::*;
+pub use status_item::*;
 
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 pub(crate) use linux::*;
@@ -263,6 +269,46 @@ pub(crate) trait Platform: 'static {
 
     fn set_dock_menu(&self, menu: Vec<MenuItem>, keymap: &Keymap);
     fn perform_dock_menu_action(&self, _action: usize) {}
+    fn add_status_item(
+        &self,
+        _item: StatusItem,
+        _on_action: Box<dyn FnMut(&dyn Action)>,
+    ) -> Result<StatusItemId> {
+        Err(anyhow::anyhow!(
+            "status items are not supported on this platform"
+        ))
+    }
+    fn update_status_item(&self, _id: StatusItemId, _item: StatusItem) {}
+    fn remove_status_item(&self, _id: StatusItemId) {}
+
+    fn show_notification(
+        &self,
+        _id: NotificationId,
+        _notification: Notification,
+        _on_event: Box<dyn FnMut(NotificationEvent)>,
+    ) -> oneshot::Receiver<Result<()>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx
+            .send(Err(anyhow::anyhow!(
+                "desktop notifications are not supported on this platform"
+            )))
+            .ok();
+        done_rx
+    }
+    fn remove_notification(&self, _id: NotificationId) {}
+
+    fn register_global_hotkey(
+        &self,
+        _id: GlobalHotkeyId,
+        _keystroke: &Keystroke,
+        _on_trigger: Box<dyn FnMut()>,
+    ) -> oneshot::Receiver<Result<(), GlobalHotkeyError>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx.send(Err(GlobalHotkeyError::Unsupported)).ok();
+        done_rx
+    }
+    fn unregister_global_hotkey(&self, _id: GlobalHotkeyId) {}
+
     fn add_recent_document(&self, _path: &Path) {}
     fn update_jump_list(
         &self,
@@ -567,6 +613,9 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn show_window_menu(&self, _position: Point<Pixels>) {}
     fn start_window_move(&self) {}
     fn start_window_resize(&self, _edge: ResizeEdge) {}
+    fn start_external_drag(&self, _drag: ExternalDrag) -> Option<oneshot::Receiver<DropAction>> {
+        None
+    }
     fn window_decorations(&self) -> Decorations {
         Decorations::Server
     }
@@ -821,7 +870,13 @@ impl AtlasKey {
                     AtlasTextureKind::Monochrome
                 }
             }
-            AtlasKey::Svg(_) => AtlasTextureKind::Monochrome,
+            AtlasKey::Svg(params) => {
+                if params.full_color {
+                    AtlasTextureKind::Polychrome
+                } else {
+                    AtlasTextureKind::Monochrome
+                }
+            }
             AtlasKey::Image(_) => AtlasTextureKind::Polychrome,
         }
     }
@@ -992,6 +1047,7 @@ impl PlatformInputHandler {
     fn replace_text_in_range(&mut self, replacement_range: Option<Range<usize>>, text: &str) {
         self.cx
             .update(|window, cx| {
+                window.record_text_input(text);
                 self.handler
                     .replace_text_in_range(replacement_range, text, window, cx);
             })
@@ -1037,6 +1093,7 @@ impl PlatformInputHandler {
     }
 
     pub(crate) fn dispatch_input(&mut self, input: &str, window: &mut Window, cx: &mut App) {
+        window.record_text_input(input);
         self.handler.replace_text_in_range(None, input, window, cx);
     }
 
@@ -1600,7 +1657,7 @@ pub struct ClipboardItem {
     entries: Vec<ClipboardEntry>,
 }
 
-/// Either a ClipboardString or a ClipboardImage
+/// Either a ClipboardString, a ClipboardImage, external paths or data of an arbitrary mime type
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ClipboardEntry {
     /// A string entry
@@ -1609,6 +1666,8 @@ pub enum ClipboardEntry {
     Image(Image),
     /// A file entry
     ExternalPaths(crate::ExternalPaths),
+    /// An entry of an arbitrary mime type, such as `text/html`
+    Data(ClipboardData),
 }
 
 impl ClipboardItem {
@@ -1645,6 +1704,20 @@ impl ClipboardItem {
         }
     }
 
+    /// Create a new ClipboardItem offering each of the given entries.
+    /// Readers that understand several of them will prefer the earlier entries.
+    pub fn from_entries(entries: impl IntoIterator<Item = ClipboardEntry>) -> Self {
+        Self {
+            entries: entries.into_iter().collect(),
+        }
+    }
+
+    /// Return this item with the given entry appended to it
+    pub fn with_entry(mut self, entry: impl Into<ClipboardEntry>) -> Self {
+        self.entries.push(entry.into());
+        self
+    }
+
     /// Concatenates together all the ClipboardString entries in the item.
     /// Returns None if there were no ClipboardString entries.
     pub fn text(&self) -> Option<String> {
@@ -1690,6 +1763,68 @@ impl ClipboardItem {
         &self.entries
     }
 
+    /// Returns the first ClipboardEntry::Data entry with the given mime type.
+    pub fn data(&self, mime_type: &str) -> Option<&ClipboardData> {
+        self.entries.iter().find_map(|entry| match entry {
+            ClipboardEntry::Data(data) if data.mime_type() == mime_type => Some(data),
+            _ => None,
+        })
+    }
+
+    /// The mime types this item is offered as to other applications, in order of preference.
+    pub fn mime_types(&self) -> Vec<SharedString> {
+        let mut mime_types: Vec<SharedString> = Vec::new();
+        for entry in &self.entries {
+            let entry_mime_types: SmallVec<[SharedString; 3]> = match entry {
+                ClipboardEntry::String(_) => PLAIN_TEXT_MIME_TYPES
+                    .into_iter()
+                    .map(SharedString::new_static)
+                    .collect(),
+                ClipboardEntry::Image(image) => {
+                    SmallVec::from_elem(SharedString::new_static(image.format.mime_type()), 1)
+                }
+                ClipboardEntry::ExternalPaths(_) => {
+                    SmallVec::from_elem(SharedString::new_static(URI_LIST_MIME_TYPE), 1)
+                }
+                ClipboardEntry::Data(data) => SmallVec::from_elem(data.mime_type.clone(), 1),
+            };
+            for mime_type in entry_mime_types {
+                if !mime_types.contains(&mime_type) {
+                    mime_types.push(mime_type);
+                }
+            }
+        }
+        mime_types
+    }
+
+    /// The bytes to send to another application that requested the given mime type,
+    /// generating promised data if necessary.
+    #[cfg_attr(not(any(target_os = "linux", target_os = "freebsd")), allow(dead_code))]
+    pub(crate) fn bytes_for_mime_type(&self, mime_type: &str) -> Option<Arc<[u8]>> {
+        if PLAIN_TEXT_MIME_TYPES.contains(&mime_type)
+            && self
+                .entries
+                .iter()
+                .any(|entry| matches!(entry, ClipboardEntry::String(_)))
+        {
+            return self.text().map(|text| text.into_bytes().into());
+        }
+
+        self.entries.iter().find_map(|entry| match entry {
+            ClipboardEntry::Image(image) if image.format.mime_type() == mime_type => {
+                Some(image.bytes.as_slice().into())
+            }
+            ClipboardEntry::ExternalPaths(paths) if mime_type == URI_LIST_MIME_TYPE => {
+                match ExternalDragData::paths(paths.paths().iter().cloned()) {
+                    ExternalDragData::Uris(uris) => Some(uris.join("\r\n").into_bytes().into()),
+                    _ => None,
+                }
+            }
+            ClipboardEntry::Data(data) if data.mime_type() == mime_type => Some(data.bytes()),
+            _ => None,
+        })
+    }
+
     /// Get owned versions of the item's entries
     pub fn into_entries(self) -> impl Iterator<Item = ClipboardEntry> {
         self.entries.into_iter()
@@ -1714,6 +1849,12 @@ impl From<Image> for ClipboardEntry {
     }
 }
 
+impl From<ClipboardData> for ClipboardEntry {
+    fn from(value: ClipboardData) -> Self {
+        Self::Data(value)
+    }
+}
+
 impl From<ClipboardEntry> for ClipboardItem {
     fn from(value: ClipboardEntry) -> Self {
         Self {
@@ -1833,7 +1974,7 @@ impl Image {
         cx: &mut App,
     ) -> Option<Arc<RenderImage>> {
         ImageSource::Image(self)
-            .use_data(None, window, cx)
+            .use_data(None, None, window, cx)
             .and_then(|result| result.ok())
     }
 
@@ -1844,7 +1985,7 @@ impl Image {
         cx: &mut App,
     ) -> Option<Arc<RenderImage>> {
         ImageSource::Image(self)
-            .get_data(None, window, cx)
+            .get_data(None, None, window, cx)
             .and_then(|result| result.ok())
     }
 
@@ -1963,6 +2104,111 @@ impl ClipboardString {
     }
 }
 
+/// Clipboard data of an arbitrary mime type, such as `text/html`, `text/rtf` or an
+/// application-specific format like `application/x-my-app+json`.
+///
+/// The data can either be provided up front, or promised: generated only once a consumer
+/// actually asks for it.
+#[derive(Clone)]
+pub struct ClipboardData {
+    mime_type: SharedString,
+    content: ClipboardDataContent,
+}
+
+#[derive(Clone)]
+enum ClipboardDataContent {
+    Bytes(Arc<[u8]>),
+    Promised(Arc<PromisedClipboardData>),
+}
+
+struct PromisedClipboardData {
+    generate: Box<dyn Fn() -> Vec<u8> + Send + Sync>,
+    bytes: std::sync::OnceLock<Arc<[u8]>>,
+}
+
+impl ClipboardData {
+    /// Create clipboard data of the given mime type from the given bytes
+    pub fn new(mime_type: impl Into<SharedString>, bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            mime_type: mime_type.into(),
+            content: ClipboardDataContent::Bytes(bytes.into()),
+        }
+    }
+
+    /// Create clipboard data of the given mime type that is generated by calling `generate`
+    /// the first time its bytes are requested, either by this application or another one.
+    ///
+    /// `generate` may be called from a background thread.
+    pub fn promised(
+        mime_type: impl Into<SharedString>,
+        generate: impl Fn() -> Vec<u8> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            mime_type: mime_type.into(),
+            content: ClipboardDataContent::Promised(Arc::new(PromisedClipboardData {
+                generate: Box::new(generate),
+                bytes: std::sync::OnceLock::new(),
+            })),
+        }
+    }
+
+    /// The mime type of this data
+    pub fn mime_type(&self) -> &SharedString {
+        &self.mime_type
+    }
+
+    /// Whether this data is promised and hasn't been generated yet
+    pub fn is_pending(&self) -> bool {
+        match &self.content {
+            ClipboardDataContent::Bytes(_) => false,
+            ClipboardDataContent::Promised(promised) => promised.bytes.get().is_none(),
+        }
+    }
+
+    /// The bytes of this data, generating them first if they were promised
+    pub fn bytes(&self) -> Arc<[u8]> {
+        match &self.content {
+            ClipboardDataContent::Bytes(bytes) => bytes.clone(),
+            ClipboardDataContent::Promised(promised) => promised
+                .bytes
+                .get_or_init(|| (promised.generate)().into())
+                .clone(),
+        }
+    }
+
+    /// The bytes of this data decoded as UTF-8, if they are valid UTF-8
+    pub fn text(&self) -> Option<String> {
+        String::from_utf8(self.bytes().to_vec()).ok()
+    }
+}
+
+impl Debug for ClipboardData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ClipboardData");
+        debug.field("mime_type", &self.mime_type);
+        match &self.content {
+            ClipboardDataContent::Bytes(bytes) => debug.field("len", &bytes.len()),
+            ClipboardDataContent::Promised(_) => debug.field("promised", &true),
+        };
+        debug.finish()
+    }
+}
+
+impl PartialEq for ClipboardData {
+    fn eq(&self, other: &Self) -> bool {
+        self.mime_type == other.mime_type
+            && match (&self.content, &other.content) {
+                (ClipboardDataContent::Bytes(a), ClipboardDataContent::Bytes(b)) => a == b,
+                (ClipboardDataContent::Promised(a), ClipboardDataContent::Promised(b)) => {
+                    Arc::ptr_eq(a, b)
+                }
+                _ => false,
+            }
+    }
+}
+
+impl Eq for ClipboardData {}
+
 impl From<String> for ClipboardString {
     fn from(value: String) -> Self {
         Self {
@@ -1971,3 +2217,246 @@ impl From<String> for ClipboardString {
         }
     }
 }
+
+/// The action negotiated with the drop target of a drag started with
+/// [`Window::start_external_drag`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DropAction {
+    /// The drag was cancelled, or the target refused the data
+    #[default]
+    None,
+    /// The target copied the data
+    Copy,
+    /// The target moved the data, the source should remove its copy
+    Move,
+    /// The target created a link to the data
+    Link,
+}
+
+/// The data offered to other applications by a drag started with
+/// [`Window::start_external_drag`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExternalDragData {
+    /// Plain text, offered as `text/plain;charset=utf-8` and its aliases
+    Text(String),
+    /// A list of URIs, offered as `text/uri-list`
+    Uris(Vec<String>),
+    /// An encoded image, offered under the mime type of its format
+    Image(Image),
+}
+
+/// Text mime types offered for text on the clipboard or in drags, in order of preference.
+const PLAIN_TEXT_MIME_TYPES: [&str; 3] = ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
+const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+
+impl ExternalDragData {
+    /// Create a URI list pointing at the given local paths
+    pub fn paths(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self::Uris(
+            paths
+                .into_iter()
+                .filter_map(|path| {
+                    let path = path.to_str()?;
+                    let mut uri = String::from("file://");
+                    for byte in path.bytes() {
+                        match byte {
+                            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => uri.push(byte as char),
+                            b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+                            _ => {
+                                use std::fmt::Write as _;
+                                _ = write!(uri, "%{byte:02X}");
+                            }
+                        }
+                    }
+                    Some(uri)
+                })
+                .collect(),
+        )
+    }
+
+    /// The mime types this data is offered as, in order of preference.
+    #[cfg_attr(not(any(target_os = "linux", target_os = "freebsd")), allow(dead_code))]
+    pub(crate) fn mime_types(&self) -> SmallVec<[&'static str; 4]> {
+        match self {
+            ExternalDragData::Text(_) => PLAIN_TEXT_MIME_TYPES.into_iter().collect(),
+            ExternalDragData::Uris(_) => std::iter::once(URI_LIST_MIME_TYPE)
+                .chain(PLAIN_TEXT_MIME_TYPES)
+                .collect(),
+            ExternalDragData::Image(image) => SmallVec::from_elem(image.format.mime_type(), 1),
+        }
+    }
+
+    /// The bytes to send to a drop target that requested the given mime type.
+    #[cfg_attr(not(any(target_os = "linux", target_os = "freebsd")), allow(dead_code))]
+    pub(crate) fn bytes_for_mime_type(&self, mime_type: &str) -> Option<Vec<u8>> {
+        match self {
+            ExternalDragData::Text(text) if PLAIN_TEXT_MIME_TYPES.contains(&mime_type) => {
+                Some(text.as_bytes().to_vec())
+            }
+            ExternalDragData::Uris(uris) if mime_type == URI_LIST_MIME_TYPE => {
+                // text/uri-list is specified to use CRLF line endings.
+                Some(uris.join("\r\n").into_bytes())
+            }
+            ExternalDragData::Uris(uris) if PLAIN_TEXT_MIME_TYPES.contains(&mime_type) => {
+                Some(uris.join("\n").into_bytes())
+            }
+            ExternalDragData::Image(image) if image.format.mime_type() == mime_type => {
+                Some(image.bytes.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// An OS-level drag that carries data out of the application, started with
+/// [`Window::start_external_drag`].
+#[derive(Clone, Debug)]
+pub struct ExternalDrag {
+    pub(crate) data: ExternalDragData,
+    pub(crate) actions: SmallVec<[DropAction; 3]>,
+    pub(crate) icon: Option<Image>,
+}
+
+impl ExternalDrag {
+    /// Create a drag offering the given data, allowing only [`DropAction::Copy`]
+    pub fn new(data: ExternalDragData) -> Self {
+        Self {
+            data,
+            actions: SmallVec::from_elem(DropAction::Copy, 1),
+            icon: None,
+        }
+    }
+
+    /// Set the actions the drop target may choose from, the first one being the preferred action.
+    pub fn actions(mut self, actions: impl IntoIterator<Item = DropAction>) -> Self {
+        self.actions = actions
+            .into_iter()
+            .filter(|action| *action != DropAction::None)
+            .collect();
+        self
+    }
+
+    /// Set the image shown under the cursor while dragging (Wayland)
+    pub fn icon(mut self, icon: Image) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// The data offered by this drag
+    pub fn data(&self) -> &ExternalDragData {
+        &self.data
+    }
+
+    /// The action the drop target should prefer
+    #[cfg_attr(not(any(target_os = "linux", target_os = "freebsd")), allow(dead_code))]
+    pub(crate) fn preferred_action(&self) -> DropAction {
+        self.actions.first().copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_clipboard_item_mime_types() {
+        let item = ClipboardItem::new_string("hello".into())
+            .with_entry(ClipboardData::new("text/html", b"<b>hello</b>".as_slice()));
+
+        assert_eq!(
+            item.mime_types(),
+            [
+                "text/plain;charset=utf-8",
+                "UTF8_STRING",
+                "text/plain",
+                "text/html"
+            ]
+        );
+        assert_eq!(
+            item.bytes_for_mime_type("text/plain").as_deref(),
+            Some(b"hello".as_slice())
+        );
+        assert_eq!(
+            item.bytes_for_mime_type("text/html").as_deref(),
+            Some(b"<b>hello</b>".as_slice())
+        );
+        assert_eq!(item.bytes_for_mime_type("image/png"), None);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    #[test]
+    fn test_external_drag_data_uri_list() {
+        let paths = [
+            PathBuf::from("/home/user/My Documents/notes.txt"),
+            PathBuf::from("/tmp/50%+ü.png"),
+        ];
+        let data = ExternalDragData::paths(paths.clone());
+        let uri_list =
+            "file:///home/user/My%20Documents/notes.txt\r\nfile:///tmp/50%25%2B%C3%BC.png";
+        assert_eq!(
+            data.bytes_for_mime_type("text/uri-list").as_deref(),
+            Some(uri_list.as_bytes())
+        );
+
+        // Drop targets split the list into lines, and percent-decode each URI back to its path.
+        let decoded = uri_list
+            .lines()
+            .map(|uri| {
+                http_client::Url::parse(uri)
+                    .unwrap()
+                    .to_file_path()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, paths);
+
+        // Other URIs are offered as they are, and as lines of text.
+        let data = ExternalDragData::Uris(vec![
+            "https://example.com/a%20b".into(),
+            "mailto:someone@example.com".into(),
+        ]);
+        assert_eq!(
+            data.bytes_for_mime_type("text/uri-list").as_deref(),
+            Some(b"https://example.com/a%20b\r\nmailto:someone@example.com".as_slice())
+        );
+        assert_eq!(
+            data.bytes_for_mime_type("UTF8_STRING").as_deref(),
+            Some(b"https://example.com/a%20b\nmailto:someone@example.com".as_slice())
+        );
+        assert_eq!(data.bytes_for_mime_type("image/png"), None);
+
+        let text = ExternalDragData::Text("hello".into());
+        assert_eq!(
+            text.bytes_for_mime_type("text/plain").as_deref(),
+            Some(b"hello".as_slice())
+        );
+        assert_eq!(text.bytes_for_mime_type("text/uri-list"), None);
+    }
+
+    #[test]
+    fn test_promised_clipboard_data_is_generated_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let data = ClipboardData::promised("application/json", {
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                b"{}".to_vec()
+            }
+        });
+        let item = ClipboardItem::from_entries([ClipboardEntry::Data(data)]);
+
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert!(item.data("application/json").unwrap().is_pending());
+        assert_eq!(
+            item.data("application/json").unwrap().text().as_deref(),
+            Some("{}")
+        );
+        assert_eq!(
+            item.bytes_for_mime_type("application/json").as_deref(),
+            Some(b"{}".as_slice())
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
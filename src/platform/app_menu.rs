@@ -1,4 +1,4 @@
-use crate::{Action, App, Platform, SharedString};
+use crate::{Action, App, Platform, SharedString, Window};
 use crate::util::ResultExt;
 
 /// A menu of the application, either a main menu or a submenu
@@ -282,3 +282,13 @@ pub(crate) fn init_app_menus(platform: &dyn Platform, cx: &App) {
         }
     }));
 }
+
+/// Whether a menu item's action can currently be performed in the given window. This is the
+/// check that [`init_app_menus`] installs with `on_validate_app_menu_command` for native menus,
+/// for menus rendered within a window while it's being drawn.
+pub(crate) fn is_menu_action_available(action: &dyn Action, window: &Window, cx: &mut App) -> bool {
+    window.is_action_available(action, cx)
+        || cx
+            .global_action_listeners
+            .contains_key(&action.as_any().type_id())
+}
@@ -21,7 +21,7 @@ const MAX_FRAME_TIME_MS: u32 = 10000;
 struct GlobalParams {
     viewport_size: [f32; 2],
     premultiplied_alpha: u32,
-    pad: u32,
+    path_sample_count: u32,
 }
 
 //Note: we can't use `Bounds` directly here because
@@ -119,6 +119,8 @@ struct PathSprite {
 struct PathRasterizationVertex {
     xy_position: Point<ScaledPixels>,
     st_position: Point<f32>,
+    edge_distances: [f32; 3],
+    pad: u32,
     color: Background,
     bounds: Bounds<ScaledPixels>,
 }
@@ -597,7 +599,7 @@ impl BladeRenderer {
             let globals = GlobalParams {
                 viewport_size: [width, height],
                 premultiplied_alpha: 0,
-                pad: 0,
+                path_sample_count: self.rendering_parameters.path_sample_count,
             };
             let mut encoder = pass.with(&self.pipelines.path_rasterization);
 
@@ -606,6 +608,8 @@ impl BladeRenderer {
                 vertices.extend(path.vertices.iter().map(|v| PathRasterizationVertex {
                     xy_position: v.xy_position,
                     st_position: v.st_position,
+                    edge_distances: v.edge_distances,
+                    pad: 0,
                     color: path.color,
                     bounds: path.clipped_bounds(),
                 }));
@@ -660,7 +664,7 @@ impl BladeRenderer {
                 gpu::AlphaMode::Ignored | gpu::AlphaMode::PostMultiplied => 0,
                 gpu::AlphaMode::PreMultiplied => 1,
             },
-            pad: 0,
+            path_sample_count: self.rendering_parameters.path_sample_count,
         };
 
         let mut pass = self.command_encoder.render(
@@ -704,6 +708,19 @@ impl BladeRenderer {
                     encoder.draw(0, 4, 0, shadows.len() as u32);
                 }
                 PrimitiveBatch::Paths(paths) => {
+                    // Without multisampling, the outline edges of paths are anti-aliased by
+                    // fading them out over a pixel around them, which needs the pixels just
+                    // outside of them to be drawn too.
+                    let outset_paths;
+                    let paths = if self.rendering_parameters.path_sample_count == 1 {
+                        outset_paths = paths
+                            .iter()
+                            .map(|path| path.outset_outline(ScaledPixels(0.5)))
+                            .collect::<Vec<_>>();
+                        &outset_paths[..]
+                    } else {
+                        paths
+                    };
                     let Some(first_path) = paths.first() else {
                         continue;
                     };
@@ -730,9 +747,11 @@ impl BladeRenderer {
                     //
                     // If all paths have the same draw order, then their bounds are all
                     // disjoint, so we can copy each path's bounds individually. If this
-                    // batch combines different draw orders, we perform a single copy
-                    // for a minimal spanning rect.
-                    let sprites = if paths.last().unwrap().order == first_path.order {
+                    // batch combines different draw orders, or the paths were pushed out
+                    // past their bounds, we perform a single copy for a minimal spanning rect.
+                    let sprites = if paths.last().unwrap().order == first_path.order
+                        && self.rendering_parameters.path_sample_count > 1
+                    {
                         paths
                             .iter()
                             .map(|path| PathSprite {
@@ -63,7 +63,7 @@ fn apply_contrast_and_gamma_correction(sample: f32, color: vec3<f32>, enhanced_c
 struct GlobalParams {
     viewport_size: vec2<f32>,
     premultiplied_alpha: u32,
-    pad: u32,
+    path_sample_count: u32,
 }
 
 var<uniform> globals: GlobalParams;
@@ -986,6 +986,8 @@ fn fs_shadow(input: ShadowVarying) -> @location(0) vec4<f32> {
 struct PathRasterizationVertex {
     xy_position: vec2<f32>,
     st_position: vec2<f32>,
+    edge_distances: vec3<f32>,
+    pad: u32,
     color: Background,
     bounds: Bounds,
 }
@@ -996,6 +998,7 @@ struct PathRasterizationVarying {
     @builtin(position) position: vec4<f32>,
     @location(0) st_position: vec2<f32>,
     @location(1) vertex_id: u32,
+    @location(2) edge_distances: vec3<f32>,
     //TODO: use `clip_distance` once Naga supports it
     @location(3) clip_distances: vec4<f32>,
 }
@@ -1008,14 +1011,30 @@ fn vs_path_rasterization(@builtin(vertex_index) vertex_id: u32) -> PathRasteriza
     out.position = to_device_position_impl(v.xy_position);
     out.st_position = v.st_position;
     out.vertex_id = vertex_id;
+    out.edge_distances = v.edge_distances;
     out.clip_distances = distance_from_clip_rect_impl(v.xy_position, v.bounds);
     return out;
 }
 
+// Returns how much of the pixel is covered by the triangle, going by its outline edges. The
+// distances are barycentric, so dividing them by their screen-space derivatives gives the
+// distance to each edge in device pixels. Without MSAA, outline edges were pushed out by half a
+// pixel (see `Path::outset_outline`), so fading them out over a pixel centers the fade on the
+// true edge. Paths rasterized with MSAA are anti-aliased by it instead, so they're fully covered.
+// Must be called in uniform control flow, as it takes derivatives.
+fn edge_coverage(edge_distances: vec3<f32>) -> f32 {
+    let gradient = sqrt(dpdx(edge_distances) * dpdx(edge_distances)
+        + dpdy(edge_distances) * dpdy(edge_distances));
+    let distances = edge_distances / max(gradient, vec3<f32>(1e-6));
+    let distance = min(distances.x, min(distances.y, distances.z));
+    return select(1.0, saturate(distance), globals.path_sample_count == 1u);
+}
+
 @fragment
 fn fs_path_rasterization(input: PathRasterizationVarying) -> @location(0) vec4<f32> {
     let dx = dpdx(input.st_position);
     let dy = dpdy(input.st_position);
+    let coverage = edge_coverage(input.edge_distances);
     if (any(input.clip_distances < vec4<f32>(0.0))) {
         return vec4<f32>(0.0);
     }
@@ -1034,6 +1053,7 @@ fn fs_path_rasterization(input: PathRasterizationVarying) -> @location(0) vec4<f
         let distance = f / length(gradient);
         alpha = saturate(0.5 - distance);
     }
+    alpha *= coverage;
     let gradient_color = prepare_gradient_color(
         background.tag,
         background.color_space,
@@ -0,0 +1,21 @@
+use crate::Keystroke;
+
+/// Identifies a hotkey registered with [`crate::App::register_global_hotkey`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlobalHotkeyId(pub(crate) usize);
+
+/// The reason a global hotkey could not be registered
+#[derive(Debug, thiserror::Error)]
+pub enum GlobalHotkeyError {
+    /// The keystroke is already bound by another application, or the user declined to bind it
+    #[error("the global hotkey {0} is already in use")]
+    Conflict(Keystroke),
+
+    /// The platform or desktop environment doesn't support global hotkeys
+    #[error("global hotkeys are not supported on this platform")]
+    Unsupported,
+
+    /// The hotkey could not be registered for another reason
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
@@ -9,10 +9,18 @@ mod wayland;
 #[cfg(feature = "x11")]
 mod x11;
 
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod dbus_menu;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod global_menu;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod status_notifier;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 mod xdg_desktop_portal;
 
 pub(crate) use dispatcher::*;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(crate) use global_menu::*;
 pub(crate) use headless::*;
 pub(crate) use keyboard::*;
 pub(crate) use platform::*;
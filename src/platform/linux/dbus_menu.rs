@@ -0,0 +1,422 @@
+//! Exports menus over D-Bus through the [DBusMenu] protocol, which is how status items and
+//! global menu bars show the menus of other processes.
+//!
+//! This module uses the [zbus] crate
+//!
+//! [DBusMenu]: https://github.com/AyatanaIndicators/libdbusmenu/blob/master/libdbusmenu-glib/dbus-menu.xml
+
+use std::collections::HashMap;
+
+use futures::channel::mpsc;
+use zbus::{
+    interface,
+    object_server::SignalEmitter,
+    zvariant::{OwnedValue, Value},
+};
+
+use crate::{Action, OwnedMenuItem};
+
+/// The object path at which menus are exported.
+pub(crate) const MENU_PATH: &str = "/MenuBar";
+pub(crate) const ROOT_ID: i32 = 0;
+
+/// The properties of a menu item, and those of its children up to the requested depth.
+type Layout = (i32, HashMap<String, Value<'static>>, Vec<Value<'static>>);
+
+/// Something the user did with an exported menu.
+pub(crate) enum DBusMenuEvent {
+    /// The menu item with the given id was clicked
+    Clicked(i32),
+    /// The menu with the given id is about to be shown
+    AboutToShow(i32),
+}
+
+enum MenuEntryKind {
+    Submenu,
+    Separator,
+    Action { checked: bool },
+}
+
+/// A menu item as exported over D-Bus, identified by its index in [`DBusMenu::entries`].
+/// The entry at [`ROOT_ID`] is the menu itself.
+pub(crate) struct MenuEntry {
+    label: String,
+    kind: MenuEntryKind,
+    enabled: bool,
+    children: Vec<i32>,
+}
+
+impl MenuEntry {
+    fn new(label: String, kind: MenuEntryKind) -> Self {
+        Self {
+            label,
+            kind,
+            enabled: true,
+            children: Vec::new(),
+        }
+    }
+
+    fn properties(&self, id: i32) -> HashMap<String, Value<'static>> {
+        let mut properties = HashMap::default();
+        match self.kind {
+            MenuEntryKind::Separator => {
+                properties.insert("type".to_string(), Value::from("separator"));
+            }
+            MenuEntryKind::Submenu => {
+                if id != ROOT_ID {
+                    properties.insert("children-display".to_string(), Value::from("submenu"));
+                }
+            }
+            MenuEntryKind::Action { checked } => {
+                if checked {
+                    properties.insert("toggle-type".to_string(), Value::from("checkmark"));
+                    properties.insert("toggle-state".to_string(), Value::from(1i32));
+                }
+            }
+        }
+        if !self.label.is_empty() {
+            // Underscores mark mnemonics in DBusMenu labels.
+            properties.insert(
+                "label".to_string(),
+                Value::from(self.label.replace('_', "__")),
+            );
+        }
+        if !self.enabled {
+            properties.insert("enabled".to_string(), Value::from(false));
+        }
+        properties
+    }
+}
+
+/// Flattens the menu into entries, returning them along with the action of each entry
+/// that has one.
+pub(crate) fn menu_entries(
+    items: Vec<OwnedMenuItem>,
+) -> (Vec<MenuEntry>, HashMap<i32, Box<dyn Action>>) {
+    fn add_entries(
+        items: Vec<OwnedMenuItem>,
+        entries: &mut Vec<MenuEntry>,
+        actions: &mut HashMap<i32, Box<dyn Action>>,
+    ) -> Vec<i32> {
+        let mut ids = Vec::with_capacity(items.len());
+        for item in items {
+            let id = entries.len() as i32;
+            match item {
+                OwnedMenuItem::Separator => {
+                    entries.push(MenuEntry::new(String::new(), MenuEntryKind::Separator))
+                }
+                OwnedMenuItem::Submenu(menu) => {
+                    entries.push(MenuEntry::new(
+                        menu.name.to_string(),
+                        MenuEntryKind::Submenu,
+                    ));
+                    let children = add_entries(menu.items, entries, actions);
+                    entries[id as usize].children = children;
+                }
+                OwnedMenuItem::SystemMenu(_) => continue,
+                OwnedMenuItem::Action {
+                    name,
+                    action,
+                    checked,
+                    ..
+                } => {
+                    entries.push(MenuEntry::new(name, MenuEntryKind::Action { checked }));
+                    actions.insert(id, action);
+                }
+            }
+            ids.push(id);
+        }
+        ids
+    }
+
+    let mut entries = vec![MenuEntry::new(String::new(), MenuEntryKind::Submenu)];
+    let mut actions = HashMap::default();
+    let children = add_entries(items, &mut entries, &mut actions);
+    entries[ROOT_ID as usize].children = children;
+    (entries, actions)
+}
+
+/// Replaces the entries of the menu exported at [`MENU_PATH`], telling clients to reload it.
+pub(crate) async fn set_menu_entries(
+    connection: &zbus::Connection,
+    entries: Vec<MenuEntry>,
+) -> zbus::Result<()> {
+    let menu = connection
+        .object_server()
+        .interface::<_, DBusMenu>(MENU_PATH)
+        .await?;
+    let revision = {
+        let mut menu = menu.get_mut().await;
+        menu.entries = entries;
+        menu.revision += 1;
+        menu.revision
+    };
+    DBusMenu::layout_updated(menu.signal_emitter(), revision, ROOT_ID).await
+}
+
+/// Enables or disables the entries of the menu exported at [`MENU_PATH`], telling clients
+/// about those that changed.
+pub(crate) async fn set_menu_entries_enabled(
+    connection: &zbus::Connection,
+    enabled: HashMap<i32, bool>,
+) -> zbus::Result<()> {
+    let menu = connection
+        .object_server()
+        .interface::<_, DBusMenu>(MENU_PATH)
+        .await?;
+    let mut updated = Vec::new();
+    {
+        let mut menu = menu.get_mut().await;
+        for (id, enabled) in enabled {
+            let entry = usize::try_from(id)
+                .ok()
+                .and_then(|index| menu.entries.get_mut(index));
+            if let Some(entry) = entry
+                && entry.enabled != enabled
+            {
+                entry.enabled = enabled;
+                let properties = HashMap::from_iter([("enabled".to_string(), enabled.into())]);
+                updated.push((id, properties));
+            }
+        }
+    }
+    if updated.is_empty() {
+        return Ok(());
+    }
+    DBusMenu::items_properties_updated(menu.signal_emitter(), updated, Vec::new()).await
+}
+
+pub(crate) struct DBusMenu {
+    entries: Vec<MenuEntry>,
+    revision: u32,
+    events: mpsc::UnboundedSender<DBusMenuEvent>,
+}
+
+impl DBusMenu {
+    pub fn new(entries: Vec<MenuEntry>, events: mpsc::UnboundedSender<DBusMenuEvent>) -> Self {
+        Self {
+            entries,
+            revision: 0,
+            events,
+        }
+    }
+
+    fn entry(&self, id: i32) -> zbus::fdo::Result<&MenuEntry> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|index| self.entries.get(index))
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("unknown menu item {id}")))
+    }
+
+    fn layout(&self, id: i32, depth: i32) -> zbus::fdo::Result<Layout> {
+        let entry = self.entry(id)?;
+        let mut children = Vec::new();
+        if depth != 0 {
+            for child in &entry.children {
+                children.push(Value::from(self.layout(*child, depth - 1)?));
+            }
+        }
+        Ok((id, entry.properties(id), children))
+    }
+
+    fn handle_event(&self, id: i32, event_id: &str) -> zbus::fdo::Result<()> {
+        let entry = self.entry(id)?;
+        match event_id {
+            "clicked" if entry.enabled => {
+                self.events.unbounded_send(DBusMenuEvent::Clicked(id)).ok();
+            }
+            // Some clients only send this event rather than calling `AboutToShow`.
+            "opened" => {
+                self.events
+                    .unbounded_send(DBusMenuEvent::AboutToShow(id))
+                    .ok();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[interface(name = "com.canonical.dbusmenu")]
+impl DBusMenu {
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> String {
+        "ltr".to_string()
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> String {
+        "normal".to_string()
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> zbus::fdo::Result<(u32, Layout)> {
+        Ok((self.revision, self.layout(parent_id, recursion_depth)?))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, Value<'static>>)> {
+        // An empty list of ids means all of them.
+        let ids = if ids.is_empty() {
+            (0..self.entries.len() as i32).collect()
+        } else {
+            ids
+        };
+        ids.into_iter()
+            .filter_map(|id| Some((id, self.entry(id).ok()?.properties(id))))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: String) -> zbus::fdo::Result<Value<'static>> {
+        self.entry(id)?
+            .properties(id)
+            .remove(&name)
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("unknown property {name}")))
+    }
+
+    fn event(&self, id: i32, event_id: String, _data: OwnedValue, _timestamp: u32) {
+        self.handle_event(id, &event_id).ok();
+    }
+
+    /// Returns the ids of the events' menu items that could not be found.
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        events
+            .into_iter()
+            .filter_map(|(id, event_id, _, _)| self.handle_event(id, &event_id).err().map(|_| id))
+            .collect()
+    }
+
+    /// The layout never changes while a menu is shown, but the entries that are enabled may,
+    /// which is reported through `ItemsPropertiesUpdated` instead.
+    fn about_to_show(&self, id: i32) -> bool {
+        self.events
+            .unbounded_send(DBusMenuEvent::AboutToShow(id))
+            .ok();
+        false
+    }
+
+    fn about_to_show_group(&self, ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        for id in ids {
+            self.events
+                .unbounded_send(DBusMenuEvent::AboutToShow(id))
+                .ok();
+        }
+        (Vec::new(), Vec::new())
+    }
+
+    #[zbus(signal)]
+    async fn layout_updated(
+        emitter: &SignalEmitter<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn items_properties_updated(
+        emitter: &SignalEmitter<'_>,
+        updated: Vec<(i32, HashMap<String, Value<'_>>)>,
+        removed: Vec<(i32, Vec<String>)>,
+    ) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OwnedMenu, actions};
+    use futures::StreamExt as _;
+
+    actions!(dbus_menu_test, [Open, ToggleWrap]);
+
+    fn action(name: &str, action: impl Action, checked: bool) -> OwnedMenuItem {
+        OwnedMenuItem::Action {
+            name: name.to_string(),
+            action: Box::new(action),
+            os_action: None,
+            checked,
+        }
+    }
+
+    #[test]
+    fn test_menu_entries() {
+        let (entries, actions) = menu_entries(vec![
+            action("_Open", Open, false),
+            OwnedMenuItem::Separator,
+            OwnedMenuItem::Submenu(OwnedMenu {
+                name: "View".into(),
+                items: vec![action("Wrap", ToggleWrap, true)],
+            }),
+        ]);
+
+        // Entries are numbered depth-first, after the root at `ROOT_ID`.
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[ROOT_ID as usize].children, [1, 2, 3]);
+        assert_eq!(entries[3].children, [4]);
+        let mut action_ids = actions.keys().copied().collect::<Vec<_>>();
+        action_ids.sort();
+        assert_eq!(action_ids, [1, 4]);
+        assert!(actions[&4].partial_eq(&ToggleWrap));
+
+        let root = entries[ROOT_ID as usize].properties(ROOT_ID);
+        assert!(root.is_empty());
+
+        let open = entries[1].properties(1);
+        assert_eq!(open.get("label"), Some(&Value::from("__Open")));
+        assert_eq!(open.get("toggle-type"), None);
+        assert_eq!(open.get("enabled"), None);
+
+        let separator = entries[2].properties(2);
+        assert_eq!(separator.get("type"), Some(&Value::from("separator")));
+        assert_eq!(separator.get("label"), None);
+
+        let submenu = entries[3].properties(3);
+        assert_eq!(
+            submenu.get("children-display"),
+            Some(&Value::from("submenu"))
+        );
+        assert_eq!(submenu.get("label"), Some(&Value::from("View")));
+
+        let wrap = entries[4].properties(4);
+        assert_eq!(wrap.get("toggle-type"), Some(&Value::from("checkmark")));
+        assert_eq!(wrap.get("toggle-state"), Some(&Value::from(1i32)));
+    }
+
+    #[test]
+    fn test_disabled_menu_entries() {
+        let (mut entries, _) = menu_entries(vec![
+            action("Open", Open, false),
+            action("Wrap", ToggleWrap, false),
+        ]);
+        entries[2].enabled = false;
+        assert_eq!(
+            entries[2].properties(2).get("enabled"),
+            Some(&Value::from(false))
+        );
+
+        // Clicks on disabled entries are ignored.
+        let (events_tx, events_rx) = mpsc::unbounded();
+        let menu = DBusMenu::new(entries, events_tx);
+        menu.handle_event(2, "clicked").unwrap();
+        menu.handle_event(1, "clicked").unwrap();
+        assert!(menu.handle_event(3, "clicked").is_err());
+        drop(menu);
+        let events = futures::executor::block_on(events_rx.collect::<Vec<_>>());
+        assert!(matches!(events[..], [DBusMenuEvent::Clicked(1)]));
+    }
+}
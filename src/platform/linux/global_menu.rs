@@ -0,0 +1,210 @@
+//! Exports the application menus through [`super::dbus_menu`], for desktops that show them in
+//! a global menu bar rather than in each window. Windows point at the exported menus with the
+//! `_KDE_NET_WM_APPMENU_*` properties and `com.canonical.AppMenu.Registrar` on X11, and with the
+//! `org_kde_kwin_appmenu` protocol on Wayland.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use anyhow::Context as _;
+use futures::{StreamExt as _, channel::mpsc};
+use util::ResultExt as _;
+use zbus::zvariant::ObjectPath;
+
+use super::AppMenuHandlers;
+use super::dbus_menu::{
+    DBusMenu, DBusMenuEvent, MENU_PATH, menu_entries, set_menu_entries, set_menu_entries_enabled,
+};
+use crate::{Action, ForegroundExecutor, OwnedMenu, OwnedMenuItem, Task};
+
+/// The service global menu bars other than KDE's find the menus of windows through. KDE runs
+/// it too, so when it isn't on the bus there's no global menu bar to export the menus for.
+const REGISTRAR_NAME: &str = "com.canonical.AppMenu.Registrar";
+
+/// Where the application menus are exported on the session bus.
+#[derive(Clone)]
+pub(crate) struct GlobalMenuAddress {
+    connection: zbus::Connection,
+    service_name: String,
+}
+
+impl GlobalMenuAddress {
+    pub fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    pub fn object_path(&self) -> &'static str {
+        MENU_PATH
+    }
+
+    /// Registers the menus of an X11 window with `com.canonical.AppMenu.Registrar`, which is
+    /// how global menu bars other than KDE's find them.
+    #[cfg_attr(not(feature = "x11"), allow(dead_code))]
+    pub async fn register_x11_window(self, window: u32) -> zbus::Result<()> {
+        self.connection
+            .call_method(
+                Some(REGISTRAR_NAME),
+                "/com/canonical/AppMenu/Registrar",
+                Some(REGISTRAR_NAME),
+                "RegisterWindow",
+                &(window, ObjectPath::from_static_str_unchecked(MENU_PATH)),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// The application menus, exported on the session bus until this is dropped.
+pub(crate) struct GlobalMenu {
+    address: Rc<RefCell<Option<GlobalMenuAddress>>>,
+    updates: mpsc::UnboundedSender<Vec<OwnedMenu>>,
+    _task: Task<()>,
+}
+
+impl GlobalMenu {
+    /// Exports the menus in the background if a global menu bar is running, calling
+    /// `on_exported` with their address once windows can be pointed at them.
+    pub fn export(
+        menus: Vec<OwnedMenu>,
+        handlers: Rc<RefCell<AppMenuHandlers>>,
+        executor: &ForegroundExecutor,
+        on_exported: Box<dyn Fn(&GlobalMenuAddress)>,
+    ) -> Self {
+        let address = Rc::new(RefCell::new(None));
+        let (updates, updates_rx) = mpsc::unbounded();
+        let task = executor.spawn({
+            let address = address.clone();
+            async move {
+                export(menus, handlers, address, on_exported, updates_rx)
+                    .await
+                    .context("failed to export global menu")
+                    .log_err();
+            }
+        });
+
+        Self {
+            address,
+            updates,
+            _task: task,
+        }
+    }
+
+    /// The address of the menus, once they've been exported.
+    pub fn address(&self) -> Option<GlobalMenuAddress> {
+        self.address.borrow().clone()
+    }
+
+    pub fn update(&self, menus: Vec<OwnedMenu>) {
+        self.updates.unbounded_send(menus).ok();
+    }
+}
+
+async fn export(
+    menus: Vec<OwnedMenu>,
+    handlers: Rc<RefCell<AppMenuHandlers>>,
+    address: Rc<RefCell<Option<GlobalMenuAddress>>>,
+    on_exported: Box<dyn Fn(&GlobalMenuAddress)>,
+    updates: mpsc::UnboundedReceiver<Vec<OwnedMenu>>,
+) -> anyhow::Result<()> {
+    let connection = zbus::Connection::session()
+        .await
+        .context("failed to connect to the session bus")?;
+    let registrar_running = zbus::fdo::DBusProxy::new(&connection)
+        .await?
+        .name_has_owner(REGISTRAR_NAME.try_into()?)
+        .await?;
+    if !registrar_running {
+        return Ok(());
+    }
+
+    let (events_tx, events) = mpsc::unbounded();
+    let (entries, actions) = menu_entries(menu_items(menus));
+    connection
+        .object_server()
+        .at(MENU_PATH, DBusMenu::new(entries, events_tx))
+        .await?;
+    let service_name = connection
+        .unique_name()
+        .context("the session bus connection has no name")?
+        .to_string();
+
+    let exported = GlobalMenuAddress {
+        connection: connection.clone(),
+        service_name,
+    };
+    on_exported(&exported);
+    *address.borrow_mut() = Some(exported);
+
+    serve(connection, actions, updates, events, handlers).await?;
+    Ok(())
+}
+
+async fn serve(
+    connection: zbus::Connection,
+    mut actions: HashMap<i32, Box<dyn Action>>,
+    mut updates: mpsc::UnboundedReceiver<Vec<OwnedMenu>>,
+    mut events: mpsc::UnboundedReceiver<DBusMenuEvent>,
+    handlers: Rc<RefCell<AppMenuHandlers>>,
+) -> zbus::Result<()> {
+    loop {
+        futures::select! {
+            update = updates.next() => {
+                let Some(menus) = update else {
+                    break;
+                };
+                let (entries, new_actions) = menu_entries(menu_items(menus));
+                set_menu_entries(&connection, entries).await?;
+                actions = new_actions;
+            }
+            event = events.next() => match event {
+                Some(DBusMenuEvent::Clicked(id)) => {
+                    if let Some(action) = actions.get(&id) {
+                        perform_action(&handlers, action.as_ref());
+                    }
+                }
+                Some(DBusMenuEvent::AboutToShow(_)) => {
+                    let enabled = validate_actions(&handlers, &actions);
+                    set_menu_entries_enabled(&connection, enabled).await?;
+                }
+                None => break,
+            },
+        }
+    }
+    Ok(())
+}
+
+/// The handlers are taken out while they run, since they can cause the menus to be set again.
+fn perform_action(handlers: &RefCell<AppMenuHandlers>, action: &dyn Action) {
+    let callback = handlers.borrow_mut().action.take();
+    if let Some(mut callback) = callback {
+        callback(action);
+        handlers.borrow_mut().action.get_or_insert(callback);
+    }
+}
+
+fn validate_actions(
+    handlers: &RefCell<AppMenuHandlers>,
+    actions: &HashMap<i32, Box<dyn Action>>,
+) -> HashMap<i32, bool> {
+    let will_open = handlers.borrow_mut().will_open.take();
+    if let Some(mut will_open) = will_open {
+        will_open();
+        handlers.borrow_mut().will_open.get_or_insert(will_open);
+    }
+
+    let Some(mut validate) = handlers.borrow_mut().validate_command.take() else {
+        return HashMap::default();
+    };
+    let enabled = actions
+        .iter()
+        .map(|(id, action)| (*id, validate(action.as_ref())))
+        .collect();
+    handlers
+        .borrow_mut()
+        .validate_command
+        .get_or_insert(validate);
+    enabled
+}
+
+fn menu_items(menus: Vec<OwnedMenu>) -> Vec<OwnedMenuItem> {
+    menus.into_iter().map(OwnedMenuItem::Submenu).collect()
+}
@@ -7,14 +7,16 @@ use util::ResultExt;
 use crate::platform::linux::LinuxClient;
 use crate::platform::{LinuxCommon, PlatformWindow};
 use crate::{
-    AnyWindowHandle, CursorStyle, DisplayId, LinuxKeyboardLayout, PlatformDisplay,
-    PlatformKeyboardLayout, WindowParams,
+    AnyWindowHandle, CursorStyle, DisplayId, LinuxKeyboardLayout, LinuxKeyboardMapper,
+    PlatformDisplay, PlatformKeyboardLayout, PlatformKeyboardMapper, WindowParams,
 };
 
 pub struct HeadlessClientState {
     pub(crate) _loop_handle: LoopHandle<'static, HeadlessClient>,
     pub(crate) event_loop: Option<calloop::EventLoop<'static, HeadlessClient>>,
     pub(crate) common: LinuxCommon,
+    keyboard_layout: LinuxKeyboardLayout,
+    keyboard_mapper: Rc<LinuxKeyboardMapper>,
 }
 
 #[derive(Clone)]
@@ -39,10 +41,24 @@ impl HeadlessClient {
             })
             .ok();
 
+        // There's no keyboard, but bindings are mapped as they would be on the default layout.
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        let keyboard = LinuxKeyboardMapper::default_layout();
+        #[cfg(not(any(feature = "wayland", feature = "x11")))]
+        let keyboard = None;
+        let (keyboard_layout, keyboard_mapper) = keyboard.unwrap_or_else(|| {
+            (
+                LinuxKeyboardLayout::new("unknown".into()),
+                LinuxKeyboardMapper::default(),
+            )
+        });
+
         HeadlessClient(Rc::new(RefCell::new(HeadlessClientState {
             event_loop: Some(event_loop),
             _loop_handle: handle,
             common,
+            keyboard_layout,
+            keyboard_mapper: Rc::new(keyboard_mapper),
         })))
     }
 }
@@ -53,7 +69,11 @@ impl LinuxClient for HeadlessClient {
     }
 
     fn keyboard_layout(&self) -> Box<dyn PlatformKeyboardLayout> {
-        Box::new(LinuxKeyboardLayout::new("unknown".into()))
+        Box::new(self.0.borrow().keyboard_layout.clone())
+    }
+
+    fn keyboard_mapper(&self) -> Rc<dyn PlatformKeyboardMapper> {
+        self.0.borrow().keyboard_mapper.clone()
     }
 
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
@@ -1,10 +1,25 @@
-use crate::{PlatformKeyboardLayout, SharedString};
+use collections::HashMap;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+use xkbcommon::xkb::{self, Keycode};
+
+use crate::{
+    KeybindingKeystroke, Keystroke, PlatformKeyboardLayout, PlatformKeyboardMapper, SharedString,
+};
 
 #[derive(Clone)]
 pub(crate) struct LinuxKeyboardLayout {
     name: SharedString,
 }
 
+/// Maps keystrokes written for the US layout to those typed with the keys at the same positions
+/// on the current layout.
+#[derive(Default)]
+pub(crate) struct LinuxKeyboardMapper {
+    /// The keystrokes that the current layout produces for the keycodes of [`us_layout_keycode`],
+    /// with and without shift.
+    key_equivalents: HashMap<(u32, bool), Keystroke>,
+}
+
 impl PlatformKeyboardLayout for LinuxKeyboardLayout {
     fn id(&self) -> &str {
         &self.name
@@ -15,8 +30,226 @@ impl PlatformKeyboardLayout for LinuxKeyboardLayout {
     }
 }
 
+impl PlatformKeyboardMapper for LinuxKeyboardMapper {
+    fn map_key_equivalent(
+        &self,
+        mut keystroke: Keystroke,
+        use_key_equivalents: bool,
+    ) -> KeybindingKeystroke {
+        // Without key equivalents, bindings match the keysym that's typed, whichever key types it.
+        if !use_key_equivalents {
+            return KeybindingKeystroke::from_keystroke(keystroke);
+        }
+        let Some((keycode, shifted_key)) = us_layout_keycode(&keystroke.key) else {
+            return KeybindingKeystroke::from_keystroke(keystroke);
+        };
+        let shift = shifted_key || keystroke.modifiers.shift;
+        if let Some(equivalent) = self.key_equivalents.get(&(keycode, shift)) {
+            keystroke.key = equivalent.key.clone();
+            // Shift is only part of keystrokes for some keys, as in `Keystroke::from_xkb`.
+            keystroke.modifiers.shift = equivalent.modifiers.shift;
+        }
+        KeybindingKeystroke::from_keystroke(keystroke)
+    }
+
+    fn get_key_equivalents(&self) -> Option<&HashMap<char, char>> {
+        None
+    }
+}
+
 impl LinuxKeyboardLayout {
     pub(crate) fn new(name: SharedString) -> Self {
         Self { name }
     }
+
+    /// The layout that's in effect in the given state.
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) fn from_xkb(state: &xkb::State) -> Self {
+        let layout_idx = state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE);
+        let layout_name = state.get_keymap().layout_get_name(layout_idx).to_string();
+        Self::new(layout_name.into())
+    }
+}
+
+impl LinuxKeyboardMapper {
+    /// Create a mapper for the layout that's in effect in the given state, by typing each key of
+    /// the US layout on it, with and without shift.
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) fn from_xkb(state: &xkb::State) -> Self {
+        let keymap = state.get_keymap();
+        let layout_idx = state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE);
+        let shift_mask = 1 << keymap.mod_get_index(xkb::MOD_NAME_SHIFT);
+
+        let mut key_equivalents = HashMap::default();
+        for shift in [false, true] {
+            let mut state = xkb::State::new(&keymap);
+            let mods = if shift { shift_mask } else { 0 };
+            state.update_mask(mods, 0, 0, 0, 0, layout_idx);
+            let modifiers = crate::Modifiers {
+                shift,
+                ..Default::default()
+            };
+            for keycode in US_LAYOUT_KEYCODES.iter().map(|(keycode, _, _)| *keycode) {
+                let keystroke = Keystroke::from_xkb(&state, modifiers, Keycode::new(keycode));
+                if !keystroke.key.is_empty() {
+                    key_equivalents.insert((keycode, shift), keystroke);
+                }
+            }
+        }
+        Self { key_equivalents }
+    }
+
+    /// Create a mapper for the layout that xkbcommon uses by default, which can be set with the
+    /// `XKB_DEFAULT_LAYOUT` and `XKB_DEFAULT_VARIANT` environment variables, along with that layout.
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) fn default_layout() -> Option<(LinuxKeyboardLayout, Self)> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb::Keymap::new_from_names(
+            &context,
+            "",
+            "",
+            "",
+            "",
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )?;
+        let state = xkb::State::new(&keymap);
+        Some((
+            LinuxKeyboardLayout::from_xkb(&state),
+            Self::from_xkb(&state),
+        ))
+    }
+}
+
+/// Returns the keycode of the key that types the given key on the US layout, and whether shift
+/// is needed to type it.
+fn us_layout_keycode(key: &str) -> Option<(u32, bool)> {
+    US_LAYOUT_KEYCODES
+        .iter()
+        .find_map(|(keycode, unshifted, shifted)| {
+            if key == *unshifted {
+                Some((*keycode, false))
+            } else if key == *shifted {
+                Some((*keycode, true))
+            } else {
+                None
+            }
+        })
+}
+
+/// The X11 keycodes of the keys that type characters on the US layout, along with what they type
+/// without and with shift. Letters are typed with shift as the lowercase key with shift held.
+const US_LAYOUT_KEYCODES: &[(u32, &str, &str)] = &[
+    (49, "`", "~"),
+    (10, "1", "!"),
+    (11, "2", "@"),
+    (12, "3", "#"),
+    (13, "4", "$"),
+    (14, "5", "%"),
+    (15, "6", "^"),
+    (16, "7", "&"),
+    (17, "8", "*"),
+    (18, "9", "("),
+    (19, "0", ")"),
+    (20, "-", "_"),
+    (21, "=", "+"),
+    (24, "q", ""),
+    (25, "w", ""),
+    (26, "e", ""),
+    (27, "r", ""),
+    (28, "t", ""),
+    (29, "y", ""),
+    (30, "u", ""),
+    (31, "i", ""),
+    (32, "o", ""),
+    (33, "p", ""),
+    (34, "[", "{"),
+    (35, "]", "}"),
+    (51, "\\", "|"),
+    (38, "a", ""),
+    (39, "s", ""),
+    (40, "d", ""),
+    (41, "f", ""),
+    (42, "g", ""),
+    (43, "h", ""),
+    (44, "j", ""),
+    (45, "k", ""),
+    (46, "l", ""),
+    (47, ";", ":"),
+    (48, "'", "\""),
+    (52, "z", ""),
+    (53, "x", ""),
+    (54, "c", ""),
+    (55, "v", ""),
+    (56, "b", ""),
+    (57, "n", ""),
+    (58, "m", ""),
+    (59, ",", "<"),
+    (60, ".", ">"),
+    (61, "/", "?"),
+];
+
+#[cfg(all(test, any(feature = "wayland", feature = "x11")))]
+mod tests {
+    use super::*;
+    use crate::Modifiers;
+
+    /// Returns `None` when the layout can't be compiled, such as on machines without
+    /// xkeyboard-config installed, so that the checks that use it are skipped.
+    fn mapper(layout: &str, variant: &str) -> Option<LinuxKeyboardMapper> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let Some(keymap) = xkb::Keymap::new_from_names(
+            &context,
+            "evdev",
+            "pc105",
+            layout,
+            variant,
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        ) else {
+            eprintln!(
+                "skipping the {layout:?} {variant:?} keyboard layout, which can't be compiled"
+            );
+            return None;
+        };
+        Some(LinuxKeyboardMapper::from_xkb(&xkb::State::new(&keymap)))
+    }
+
+    fn map(mapper: &LinuxKeyboardMapper, keystroke: &str, use_key_equivalents: bool) -> String {
+        mapper
+            .map_key_equivalent(Keystroke::parse(keystroke).unwrap(), use_key_equivalents)
+            .unparse()
+    }
+
+    #[test]
+    fn test_keyboard_mapper() {
+        if let Some(us) = mapper("us", "") {
+            for keystroke in ["ctrl-z", "ctrl-[", "ctrl-{", "ctrl-shift-a", "ctrl-1", "f1"] {
+                assert_eq!(map(&us, keystroke, true), keystroke);
+            }
+        }
+
+        if let Some(german) = mapper("de", "") {
+            assert_eq!(map(&german, "ctrl-z", true), "ctrl-y");
+            assert_eq!(map(&german, "ctrl-shift-z", true), "ctrl-shift-y");
+            assert_eq!(map(&german, "ctrl-/", true), "ctrl--");
+            // Keysyms match whichever key types them.
+            assert_eq!(map(&german, "ctrl-z", false), "ctrl-z");
+        }
+
+        if let Some(french) = mapper("fr", "") {
+            assert_eq!(map(&french, "ctrl-q", true), "ctrl-a");
+            assert_eq!(map(&french, "ctrl-1", true), "ctrl-&");
+            assert_eq!(map(&french, "ctrl-m", true), "ctrl-,");
+        }
+
+        if let Some(dvorak) = mapper("us", "dvorak") {
+            assert_eq!(map(&dvorak, "ctrl-x", true), "ctrl-q");
+            assert_eq!(map(&dvorak, "ctrl-[", true), "ctrl-/");
+            assert_eq!(map(&dvorak, "ctrl-{", true), "ctrl-?");
+
+            let keystroke = dvorak.map_key_equivalent(Keystroke::parse("ctrl-x").unwrap(), true);
+            assert_eq!(*keystroke.modifiers(), Modifiers::control());
+        }
+    }
 }
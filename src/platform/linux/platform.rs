@@ -1,4 +1,6 @@
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     env,
     path::{Path, PathBuf},
     rc::Rc,
@@ -15,7 +17,7 @@ use std::{
 
 use anyhow::{Context as _, anyhow};
 use calloop::LoopSignal;
-use futures::channel::oneshot;
+use futures::channel::{mpsc, oneshot};
 use util::ResultExt as _;
 use util::command::{new_smol_command, new_std_command};
 #[cfg(any(feature = "wayland", feature = "x11"))]
@@ -23,10 +25,11 @@ use xkbcommon::xkb::{self, Keycode, Keysym, State};
 
 use crate::{
     Action, AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, DisplayId,
-    ForegroundExecutor, Keymap, LinuxDispatcher, Menu, MenuItem, OwnedMenu, PathPromptOptions,
-    Pixels, Platform, PlatformDisplay, PlatformKeyboardLayout, PlatformKeyboardMapper,
-    PlatformTextSystem, PlatformWindow, Point, PriorityQueueCalloopReceiver, Result,
-    RunnableVariant, Task, WindowAppearance, WindowParams, px,
+    ForegroundExecutor, GlobalHotkeyError, GlobalHotkeyId, Keymap, Keystroke, LinuxDispatcher,
+    Menu, MenuItem, OwnedMenu, PathPromptOptions, Pixels, Platform, PlatformDisplay,
+    PlatformKeyboardLayout, PlatformKeyboardMapper, PlatformTextSystem, PlatformWindow, Point,
+    PriorityQueueCalloopReceiver, Result, RunnableVariant, StatusItem, StatusItemId, Task,
+    WindowAppearance, WindowParams, px,
 };
 
 #[cfg(any(feature = "wayland", feature = "x11"))]
@@ -91,6 +94,7 @@ pub trait LinuxClient {
     fn compositor_name(&self) -> &'static str;
     fn with_common<R>(&self, f: impl FnOnce(&mut LinuxCommon) -> R) -> R;
     fn keyboard_layout(&self) -> Box<dyn PlatformKeyboardLayout>;
+    fn keyboard_mapper(&self) -> Rc<dyn PlatformKeyboardMapper>;
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>>;
     #[allow(unused)]
     fn display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>>;
@@ -118,6 +122,25 @@ pub trait LinuxClient {
     fn window_stack(&self) -> Option<Vec<AnyWindowHandle>>;
     fn run(&self);
 
+    fn register_global_hotkey(
+        &self,
+        _id: GlobalHotkeyId,
+        _keystroke: &Keystroke,
+        _on_trigger: Box<dyn FnMut()>,
+    ) -> oneshot::Receiver<Result<(), GlobalHotkeyError>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx.send(Err(GlobalHotkeyError::Unsupported)).ok();
+        done_rx
+    }
+    fn unregister_global_hotkey(&self, _id: GlobalHotkeyId) {}
+
+    /// Returns a function that points every open window at the application menus, which is
+    /// called once they've been exported.
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    fn global_menu_setter(&self) -> Box<dyn Fn(&super::GlobalMenuAddress)> {
+        Box::new(|_| {})
+    }
+
     #[cfg(any(feature = "wayland", feature = "x11"))]
     fn window_identifier(
         &self,
@@ -131,12 +154,19 @@ pub(crate) struct PlatformHandlers {
     pub(crate) open_urls: Option<Box<dyn FnMut(Vec<String>)>>,
     pub(crate) quit: Option<Box<dyn FnMut()>>,
     pub(crate) reopen: Option<Box<dyn FnMut()>>,
-    pub(crate) app_menu_action: Option<Box<dyn FnMut(&dyn Action)>>,
-    pub(crate) will_open_app_menu: Option<Box<dyn FnMut()>>,
-    pub(crate) validate_app_menu_command: Option<Box<dyn FnMut(&dyn Action) -> bool>>,
+    pub(crate) app_menu: Rc<RefCell<AppMenuHandlers>>,
     pub(crate) keyboard_layout_change: Option<Box<dyn FnMut()>>,
 }
 
+/// The callbacks for the application menus, which are shared with the task exporting them
+/// as a global menu.
+#[derive(Default)]
+pub(crate) struct AppMenuHandlers {
+    pub(crate) action: Option<Box<dyn FnMut(&dyn Action)>>,
+    pub(crate) will_open: Option<Box<dyn FnMut()>>,
+    pub(crate) validate_command: Option<Box<dyn FnMut(&dyn Action) -> bool>>,
+}
+
 pub(crate) struct LinuxCommon {
     pub(crate) background_executor: BackgroundExecutor,
     pub(crate) foreground_executor: ForegroundExecutor,
@@ -146,6 +176,20 @@ pub(crate) struct LinuxCommon {
     pub(crate) callbacks: PlatformHandlers,
     pub(crate) signal: LoopSignal,
     pub(crate) menus: Vec<OwnedMenu>,
+    pub(crate) status_items: HashMap<StatusItemId, LinuxStatusItem>,
+    #[cfg_attr(not(any(feature = "wayland", feature = "x11")), allow(dead_code))]
+    next_status_item_id: usize,
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) notifications: super::xdg_desktop_portal::Notifications,
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) global_menu: Option<super::GlobalMenu>,
+}
+
+/// A status item exported by a task on the foreground executor, which removes it from the
+/// tray once `updates` is dropped.
+pub(crate) struct LinuxStatusItem {
+    updates: mpsc::UnboundedSender<StatusItem>,
+    _task: Task<()>,
 }
 
 impl LinuxCommon {
@@ -172,6 +216,12 @@ impl LinuxCommon {
             callbacks,
             signal,
             menus: Vec::new(),
+            status_items: HashMap::default(),
+            next_status_item_id: 0,
+            #[cfg(any(feature = "wayland", feature = "x11"))]
+            notifications: Default::default(),
+            #[cfg(any(feature = "wayland", feature = "x11"))]
+            global_menu: None,
         };
 
         (common, main_receiver)
@@ -196,7 +246,7 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn keyboard_mapper(&self) -> Rc<dyn PlatformKeyboardMapper> {
-        Rc::new(crate::DummyKeyboardMapper)
+        self.keyboard_mapper()
     }
 
     fn on_keyboard_layout_change(&self, callback: Box<dyn FnMut()>) {
@@ -493,19 +543,19 @@ impl<P: LinuxClient + 'static> Platform for P {
 
     fn on_app_menu_action(&self, callback: Box<dyn FnMut(&dyn Action)>) {
         self.with_common(|common| {
-            common.callbacks.app_menu_action = Some(callback);
+            common.callbacks.app_menu.borrow_mut().action = Some(callback);
         });
     }
 
     fn on_will_open_app_menu(&self, callback: Box<dyn FnMut()>) {
         self.with_common(|common| {
-            common.callbacks.will_open_app_menu = Some(callback);
+            common.callbacks.app_menu.borrow_mut().will_open = Some(callback);
         });
     }
 
     fn on_validate_app_menu_command(&self, callback: Box<dyn FnMut(&dyn Action) -> bool>) {
         self.with_common(|common| {
-            common.callbacks.validate_app_menu_command = Some(callback);
+            common.callbacks.app_menu.borrow_mut().validate_command = Some(callback);
         });
     }
 
@@ -516,9 +566,26 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn set_menus(&self, menus: Vec<Menu>, _keymap: &Keymap) {
-        self.with_common(|common| {
-            common.menus = menus.into_iter().map(|menu| menu.owned()).collect();
-        })
+        let menus: Vec<OwnedMenu> = menus.into_iter().map(|menu| menu.owned()).collect();
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        {
+            let global_menu_setter = self.global_menu_setter();
+            self.with_common(|common| {
+                if let Some(global_menu) = &common.global_menu {
+                    global_menu.update(menus.clone());
+                } else if !menus.is_empty() {
+                    common.global_menu = Some(super::GlobalMenu::export(
+                        menus.clone(),
+                        common.callbacks.app_menu.clone(),
+                        &common.foreground_executor,
+                        global_menu_setter,
+                    ));
+                }
+            });
+        }
+
+        self.with_common(|common| common.menus = menus);
     }
 
     fn get_menus(&self) -> Option<Vec<OwnedMenu>> {
@@ -529,6 +596,78 @@ impl<P: LinuxClient + 'static> Platform for P {
         // todo(linux)
     }
 
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    fn add_status_item(
+        &self,
+        item: StatusItem,
+        on_action: Box<dyn FnMut(&dyn Action)>,
+    ) -> Result<StatusItemId> {
+        self.with_common(|common| {
+            let id = StatusItemId(common.next_status_item_id);
+            common.next_status_item_id += 1;
+
+            let (updates, updates_rx) = mpsc::unbounded();
+            let task = common.foreground_executor.spawn(async move {
+                super::status_notifier::serve(id, item, updates_rx, on_action)
+                    .await
+                    .context("failed to export status item")
+                    .log_err();
+            });
+            common.status_items.insert(
+                id,
+                LinuxStatusItem {
+                    updates,
+                    _task: task,
+                },
+            );
+            Ok(id)
+        })
+    }
+
+    fn update_status_item(&self, id: StatusItemId, item: StatusItem) {
+        self.with_common(|common| {
+            if let Some(status_item) = common.status_items.get(&id) {
+                status_item.updates.unbounded_send(item).ok();
+            }
+        });
+    }
+
+    fn remove_status_item(&self, id: StatusItemId) {
+        self.with_common(|common| common.status_items.remove(&id));
+    }
+
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    fn show_notification(
+        &self,
+        id: crate::NotificationId,
+        notification: crate::Notification,
+        on_event: Box<dyn FnMut(crate::NotificationEvent)>,
+    ) -> oneshot::Receiver<Result<()>> {
+        self.with_common(|common| {
+            common
+                .notifications
+                .show(&common.foreground_executor, id, notification, on_event)
+        })
+    }
+
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    fn remove_notification(&self, id: crate::NotificationId) {
+        self.with_common(|common| common.notifications.remove(&common.foreground_executor, id));
+    }
+
+    fn register_global_hotkey(
+        &self,
+        id: GlobalHotkeyId,
+        keystroke: &Keystroke,
+        on_trigger: Box<dyn FnMut()>,
+    ) -> oneshot::Receiver<Result<(), GlobalHotkeyError>> {
+        self.register_global_hotkey(id, keystroke, on_trigger)
+    }
+
+    fn unregister_global_hotkey(&self, id: GlobalHotkeyId) {
+        self.unregister_global_hotkey(id)
+    }
+
     fn path_for_auxiliary_executable(&self, _name: &str) -> Result<PathBuf> {
         Err(anyhow::Error::msg(
             "Platform<LinuxPlatform>::path_for_auxiliary_executable is not implemented yet",
@@ -972,6 +1111,45 @@ impl crate::Keystroke {
         }
     }
 
+    /// Returns the keysym that produces this keystroke's key, which is the reverse of the
+    /// mapping done by [`Self::from_xkb`].
+    pub(super) fn xkb_keysym(&self) -> Option<Keysym> {
+        let keysym = match self.key.as_str() {
+            "enter" => Keysym::Return,
+            "pageup" => Keysym::Prior,
+            "pagedown" => Keysym::Next,
+            "back" => Keysym::XF86_Back,
+            "forward" => Keysym::XF86_Forward,
+            "cut" => Keysym::XF86_Cut,
+            "copy" => Keysym::XF86_Copy,
+            "paste" => Keysym::XF86_Paste,
+            "new" => Keysym::XF86_New,
+            "open" => Keysym::XF86_Open,
+            "save" => Keysym::XF86_Save,
+            "space" => Keysym::space,
+            "backspace" => Keysym::BackSpace,
+            "tab" => Keysym::Tab,
+            "delete" => Keysym::Delete,
+            "escape" => Keysym::Escape,
+            "left" => Keysym::Left,
+            "right" => Keysym::Right,
+            "up" => Keysym::Up,
+            "down" => Keysym::Down,
+            "home" => Keysym::Home,
+            "end" => Keysym::End,
+            "insert" => Keysym::Insert,
+            key => {
+                let mut chars = key.chars();
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    xkb::utf32_to_keysym(c as u32)
+                } else {
+                    xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE)
+                }
+            }
+        };
+        (keysym != Keysym::NoSymbol).then_some(keysym)
+    }
+
     /**
      * Returns which symbol the dead key represents
      * <https://developer.mozilla.org/en-US/docs/Web/API/UI_Events/Keyboard_event_key_values#dead_keycodes_for_linux>
@@ -0,0 +1,264 @@
+//! Provides system tray icons through the [StatusNotifierItem] D-Bus protocol, with their
+//! menus exported through [`super::dbus_menu`].
+//!
+//! This module uses the [zbus] crate
+//!
+//! [StatusNotifierItem]: https://www.freedesktop.org/wiki/Specifications/StatusNotifierItem/
+
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use futures::{StreamExt as _, channel::mpsc};
+use zbus::{
+    interface,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedObjectPath},
+};
+
+use super::dbus_menu::{DBusMenu, DBusMenuEvent, MENU_PATH, menu_entries, set_menu_entries};
+use crate::{Action, Image, MenuItem, OwnedMenuItem, StatusItem, StatusItemId};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+
+/// ARGB32 images in network byte order, as used by the `IconPixmap` and `ToolTip` properties.
+type Pixmaps = Vec<(i32, i32, Vec<u8>)>;
+
+/// Exports the status item on the session bus until `updates` is closed, applying every
+/// update sent through it and invoking `on_action` when one of its menu items is clicked.
+pub(crate) async fn serve(
+    id: StatusItemId,
+    item: StatusItem,
+    mut updates: mpsc::UnboundedReceiver<StatusItem>,
+    mut on_action: Box<dyn FnMut(&dyn Action)>,
+) -> anyhow::Result<()> {
+    let (events_tx, mut events) = mpsc::unbounded();
+    let (entries, mut actions) = menu_entries(owned_items(item.menu.items));
+
+    let name = format!("org.kde.StatusNotifierItem-{}-{}", std::process::id(), id.0);
+    let status_notifier_item = StatusNotifierItem {
+        id: app_id(),
+        title: item.tooltip.to_string(),
+        icon: pixmaps(&item.icon),
+    };
+    let menu = DBusMenu::new(entries, events_tx);
+    let connection = zbus::connection::Builder::session()?
+        .name(name.as_str())?
+        .serve_at(ITEM_PATH, status_notifier_item)?
+        .serve_at(MENU_PATH, menu)?
+        .build()
+        .await?;
+
+    connection
+        .call_method(
+            Some("org.kde.StatusNotifierWatcher"),
+            "/StatusNotifierWatcher",
+            Some("org.kde.StatusNotifierWatcher"),
+            "RegisterStatusNotifierItem",
+            &name,
+        )
+        .await
+        .context("failed to register with the StatusNotifierWatcher, is a system tray running?")?;
+
+    loop {
+        futures::select! {
+            update = updates.next() => {
+                let Some(item) = update else {
+                    break;
+                };
+                // The item keeps being served after a failed update, so that later ones apply.
+                match apply_update(&connection, item).await {
+                    Ok(new_actions) => actions = new_actions,
+                    Err(error) => log::error!("failed to update status item: {error}"),
+                }
+            }
+            event = events.next() => {
+                if let Some(DBusMenuEvent::Clicked(id)) = event
+                    && let Some(action) = actions.get(&id)
+                {
+                    on_action(action.as_ref());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn apply_update(
+    connection: &zbus::Connection,
+    item: StatusItem,
+) -> zbus::Result<HashMap<i32, Box<dyn Action>>> {
+    let (entries, actions) = menu_entries(owned_items(item.menu.items));
+    let object_server = connection.object_server();
+
+    let status_notifier_item = object_server
+        .interface::<_, StatusNotifierItem>(ITEM_PATH)
+        .await?;
+    {
+        let mut status_notifier_item = status_notifier_item.get_mut().await;
+        status_notifier_item.title = item.tooltip.to_string();
+        status_notifier_item.icon = pixmaps(&item.icon);
+    }
+    let emitter = status_notifier_item.signal_emitter();
+    StatusNotifierItem::new_icon(emitter).await?;
+    StatusNotifierItem::new_title(emitter).await?;
+    StatusNotifierItem::new_tool_tip(emitter).await?;
+
+    set_menu_entries(connection, entries).await?;
+
+    Ok(actions)
+}
+
+fn owned_items(items: Vec<MenuItem>) -> Vec<OwnedMenuItem> {
+    items.into_iter().map(MenuItem::owned).collect()
+}
+
+fn app_id() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "gpui".to_string())
+}
+
+fn pixmaps(icon: &Image) -> Pixmaps {
+    let image = match image::load_from_memory(&icon.bytes) {
+        Ok(image) => image.into_rgba8(),
+        Err(err) => {
+            log::error!("failed to decode status item icon: {err}");
+            return Vec::new();
+        }
+    };
+    let (width, height) = image.dimensions();
+    let bytes = image
+        .pixels()
+        .flat_map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            [a, r, g, b]
+        })
+        .collect();
+    vec![(width as i32, height as i32, bytes)]
+}
+
+struct StatusNotifierItem {
+    id: String,
+    title: String,
+    icon: Pixmaps,
+}
+
+#[interface(name = "org.kde.StatusNotifierItem")]
+impl StatusNotifierItem {
+    #[zbus(property)]
+    fn category(&self) -> String {
+        "ApplicationStatus".to_string()
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> String {
+        "Active".to_string()
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> String {
+        String::new()
+    }
+
+    #[zbus(property)]
+    fn icon_pixmap(&self) -> Pixmaps {
+        self.icon.clone()
+    }
+
+    #[zbus(property)]
+    fn tool_tip(&self) -> (String, Pixmaps, String, String) {
+        (String::new(), Vec::new(), self.title.clone(), String::new())
+    }
+
+    /// Asks hosts to show the menu when the icon is clicked, since we have no other way
+    /// of reacting to clicks.
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        ObjectPath::from_static_str_unchecked(MENU_PATH).into()
+    }
+
+    fn activate(&self, _x: i32, _y: i32) {}
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {}
+
+    fn context_menu(&self, _x: i32, _y: i32) {}
+
+    fn scroll(&self, _delta: i32, _orientation: String) {}
+
+    #[zbus(signal)]
+    async fn new_icon(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_title(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_tool_tip(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImageFormat;
+    use image::{ImageBuffer, Rgba};
+    use std::io::Cursor;
+
+    fn png(pixels: &[[u8; 4]]) -> Image {
+        let image = ImageBuffer::from_fn(pixels.len() as u32, 1, |x, _| Rgba(pixels[x as usize]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        Image::from_bytes(ImageFormat::Png, bytes)
+    }
+
+    #[test]
+    fn test_pixmaps() {
+        // Pixels are ARGB, in network byte order.
+        let icon = png(&[[1, 2, 3, 4], [5, 6, 7, 8]]);
+        assert_eq!(pixmaps(&icon), [(2, 1, vec![4, 1, 2, 3, 8, 5, 6, 7])]);
+
+        let invalid = Image::from_bytes(ImageFormat::Png, b"not a png".to_vec());
+        assert!(pixmaps(&invalid).is_empty());
+    }
+
+    #[test]
+    fn test_status_notifier_item_properties() {
+        let item = StatusNotifierItem {
+            id: "app".to_string(),
+            title: "Syncing".to_string(),
+            icon: pixmaps(&png(&[[0, 0, 0, 255]])),
+        };
+        assert_eq!(item.id(), "app");
+        assert_eq!(item.title(), "Syncing");
+        assert_eq!(item.icon_pixmap(), [(1, 1, vec![255, 0, 0, 0])]);
+        // The tooltip has no icon of its own, and shows the title as its text.
+        assert_eq!(
+            item.tool_tip(),
+            (
+                String::new(),
+                Vec::new(),
+                "Syncing".to_string(),
+                String::new()
+            )
+        );
+        assert_eq!(item.menu().as_str(), MENU_PATH);
+        assert!(item.item_is_menu());
+    }
+}
@@ -15,6 +15,7 @@ use calloop::{
 use calloop_wayland_source::WaylandSource;
 use collections::HashMap;
 use filedescriptor::Pipe;
+use futures::channel::oneshot;
 use http_client::Url;
 use smallvec::SmallVec;
 use util::ResultExt as _;
@@ -61,6 +62,9 @@ use wayland_protocols::xdg::decoration::zv1::client::{
     zxdg_decoration_manager_v1, zxdg_toplevel_decoration_v1,
 };
 use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+use wayland_protocols_plasma::appmenu::client::{
+    org_kde_kwin_appmenu, org_kde_kwin_appmenu_manager,
+};
 use wayland_protocols_plasma::blur::client::{org_kde_kwin_blur, org_kde_kwin_blur_manager};
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 use xkbcommon::xkb::ffi::XKB_KEYMAP_FORMAT_TEXT_V1;
@@ -73,11 +77,12 @@ use super::{
 
 use crate::{
     AnyWindowHandle, Bounds, Capslock, CursorStyle, DOUBLE_CLICK_INTERVAL, DevicePixels, DisplayId,
-    FileDropEvent, ForegroundExecutor, KeyDownEvent, KeyUpEvent, Keystroke, LinuxCommon,
-    LinuxKeyboardLayout, Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent,
-    MouseExitEvent, MouseMoveEvent, MouseUpEvent, NavigationDirection, Pixels, PlatformDisplay,
-    PlatformInput, PlatformKeyboardLayout, Point, ResultExt as _, SCROLL_LINES, ScrollDelta,
-    ScrollWheelEvent, Size, TouchPhase, WindowParams, point, profiler, px, size,
+    DropAction, ExternalDrag, FileDropEvent, ForegroundExecutor, GlobalHotkeyError, GlobalHotkeyId,
+    KeyDownEvent, KeyUpEvent, Keystroke, LinuxCommon, LinuxKeyboardLayout, LinuxKeyboardMapper,
+    Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseExitEvent, MouseMoveEvent,
+    MouseUpEvent, NavigationDirection, Pixels, PlatformDisplay, PlatformInput,
+    PlatformKeyboardLayout, PlatformKeyboardMapper, Point, ResultExt as _, SCROLL_LINES,
+    ScrollDelta, ScrollWheelEvent, Size, TouchPhase, WindowParams, point, profiler, px, size,
 };
 use crate::{
     RunnableVariant, TaskTiming,
@@ -86,15 +91,16 @@ use crate::{
 use crate::{
     SharedString,
     platform::linux::{
-        LinuxClient, get_xkb_compose_state, is_within_click_distance, open_uri_internal, read_fd,
-        reveal_path_internal,
+        GlobalMenuAddress, LinuxClient, get_xkb_compose_state, is_within_click_distance,
+        open_uri_internal, read_fd, reveal_path_internal,
         wayland::{
-            clipboard::{Clipboard, DataOffer, FILE_LIST_MIME_TYPE, TEXT_MIME_TYPES},
+            clipboard::{Clipboard, DataOffer, FILE_LIST_MIME_TYPE},
             cursor::Cursor,
+            drag::{DragIcon, OutgoingDrag, dnd_actions, drop_action},
             serial::{SerialKind, SerialTracker},
             window::WaylandWindow,
         },
-        xdg_desktop_portal::{Event as XDPEvent, XDPEventSource},
+        xdg_desktop_portal::{Event as XDPEvent, GlobalShortcuts, XDPEventSource},
     },
 };
 
@@ -121,6 +127,7 @@ pub struct Globals {
     pub decoration_manager: Option<zxdg_decoration_manager_v1::ZxdgDecorationManagerV1>,
     pub layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
     pub blur_manager: Option<org_kde_kwin_blur_manager::OrgKdeKwinBlurManager>,
+    pub appmenu_manager: Option<org_kde_kwin_appmenu_manager::OrgKdeKwinAppmenuManager>,
     pub text_input_manager: Option<zwp_text_input_manager_v3::ZwpTextInputManagerV3>,
     pub executor: ForegroundExecutor,
 }
@@ -159,6 +166,7 @@ impl Globals {
             decoration_manager: globals.bind(&qh, 1..=1, ()).ok(),
             layer_shell: globals.bind(&qh, 1..=5, ()).ok(),
             blur_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            appmenu_manager: globals.bind(&qh, 1..=2, ()).ok(),
             text_input_manager: globals.bind(&qh, 1..=1, ()).ok(),
             executor,
             qh,
@@ -216,9 +224,11 @@ pub(crate) struct WaylandClientState {
     outputs: HashMap<ObjectId, Output>,
     in_progress_outputs: HashMap<ObjectId, InProgressOutput>,
     keyboard_layout: LinuxKeyboardLayout,
+    keyboard_mapper: Rc<LinuxKeyboardMapper>,
     keymap_state: Option<xkb::State>,
     compose_state: Option<xkb::compose::State>,
     drag: DragState,
+    outgoing_drag: Option<OutgoingDrag>,
     click: ClickState,
     repeat: KeyRepeat,
     pub modifiers: Modifiers,
@@ -243,6 +253,7 @@ pub(crate) struct WaylandClientState {
     pending_activation: Option<PendingActivation>,
     event_loop: Option<EventLoop<'static, WaylandClientStatePtr>>,
     common: LinuxCommon,
+    global_shortcuts: GlobalShortcuts,
 }
 
 pub struct DragState {
@@ -347,25 +358,26 @@ impl WaylandClientStatePtr {
         text_input.commit();
     }
 
-    pub fn handle_keyboard_layout_change(&self) {
+    /// Updates the keyboard layout and its mapper, reporting the change if the layout is another
+    /// one or `keymap_changed` is set.
+    pub fn handle_keyboard_layout_change(&self, keymap_changed: bool) {
         let client = self.get_client();
         let mut state = client.borrow_mut();
-        let changed = if let Some(keymap_state) = &state.keymap_state {
-            let layout_idx = keymap_state.serialize_layout(xkbcommon::xkb::STATE_LAYOUT_EFFECTIVE);
-            let keymap = keymap_state.get_keymap();
-            let layout_name = keymap.layout_get_name(layout_idx);
-            let changed = layout_name != state.keyboard_layout.name();
-            if changed {
-                state.keyboard_layout = LinuxKeyboardLayout::new(layout_name.to_string().into());
-            }
-            changed
-        } else {
-            let changed = &UNKNOWN_KEYBOARD_LAYOUT_NAME != state.keyboard_layout.name();
-            if changed {
-                state.keyboard_layout = LinuxKeyboardLayout::new(UNKNOWN_KEYBOARD_LAYOUT_NAME);
-            }
-            changed
+        let (keyboard_layout, keyboard_mapper) = match &state.keymap_state {
+            Some(keymap_state) => (
+                LinuxKeyboardLayout::from_xkb(keymap_state),
+                LinuxKeyboardMapper::from_xkb(keymap_state),
+            ),
+            None => (
+                LinuxKeyboardLayout::new(UNKNOWN_KEYBOARD_LAYOUT_NAME),
+                LinuxKeyboardMapper::default(),
+            ),
         };
+        let changed = keymap_changed || keyboard_layout.name() != state.keyboard_layout.name();
+        if changed {
+            state.keyboard_layout = keyboard_layout;
+            state.keyboard_mapper = Rc::new(keyboard_mapper);
+        }
 
         if changed && let Some(mut callback) = state.common.callbacks.keyboard_layout_change.take()
         {
@@ -376,6 +388,50 @@ impl WaylandClientStatePtr {
         }
     }
 
+    pub fn start_drag(
+        &self,
+        origin: &wl_surface::WlSurface,
+        drag: ExternalDrag,
+    ) -> Option<oneshot::Receiver<DropAction>> {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let (Some(data_device_manager), Some(data_device)) = (
+            state.globals.data_device_manager.clone(),
+            state.data_device.clone(),
+        ) else {
+            return None;
+        };
+
+        if let Some(previous_drag) = state.outgoing_drag.take() {
+            previous_drag.finish(DropAction::None);
+        }
+
+        let data_source = data_device_manager.create_data_source(&state.globals.qh, ());
+        for mime_type in drag.data.mime_types() {
+            data_source.offer(mime_type.to_string());
+        }
+        data_source.set_actions(dnd_actions(&drag.actions));
+
+        let icon = drag
+            .icon
+            .as_ref()
+            .and_then(|icon| DragIcon::new(&state.globals, icon).log_err());
+        let serial = state.serial_tracker.get(SerialKind::MousePress);
+        data_device.start_drag(
+            Some(&data_source),
+            origin,
+            icon.as_ref().map(|icon| &icon.surface),
+            serial,
+        );
+        if let Some(icon) = &icon {
+            icon.surface.commit();
+        }
+
+        let (done_tx, done_rx) = oneshot::channel();
+        state.outgoing_drag = Some(OutgoingDrag::new(data_source, drag.data, icon, done_tx));
+        Some(done_rx)
+    }
+
     pub fn drop_window(&self, surface_id: &ObjectId) {
         let mut client = self.get_client();
         let mut state = client.borrow_mut();
@@ -602,7 +658,9 @@ impl WaylandClient {
             in_progress_outputs,
             windows: HashMap::default(),
             common,
+            global_shortcuts: GlobalShortcuts::default(),
             keyboard_layout: LinuxKeyboardLayout::new(UNKNOWN_KEYBOARD_LAYOUT_NAME),
+            keyboard_mapper: Rc::new(LinuxKeyboardMapper::default()),
             keymap_state: None,
             compose_state: None,
             drag: DragState {
@@ -610,6 +668,7 @@ impl WaylandClient {
                 window: None,
                 position: Point::default(),
             },
+            outgoing_drag: None,
             click: ClickState {
                 last_click: Instant::now(),
                 last_mouse_button: None,
@@ -664,6 +723,10 @@ impl LinuxClient for WaylandClient {
         Box::new(self.0.borrow().keyboard_layout.clone())
     }
 
+    fn keyboard_mapper(&self) -> Rc<dyn PlatformKeyboardMapper> {
+        self.0.borrow().keyboard_mapper.clone()
+    }
+
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
         self.0
             .borrow()
@@ -744,6 +807,14 @@ impl LinuxClient for WaylandClient {
             parent,
         )?;
         state.windows.insert(surface_id, window.0.clone());
+        if let Some(address) = state
+            .common
+            .global_menu
+            .as_ref()
+            .and_then(|global_menu| global_menu.address())
+        {
+            window.0.set_global_menu(&address);
+        }
 
         Ok(Box::new(window))
     }
@@ -836,6 +907,37 @@ impl LinuxClient for WaylandClient {
             .log_err();
     }
 
+    fn register_global_hotkey(
+        &self,
+        id: GlobalHotkeyId,
+        keystroke: &Keystroke,
+        on_trigger: Box<dyn FnMut()>,
+    ) -> oneshot::Receiver<Result<(), GlobalHotkeyError>> {
+        let window_identifier = self.window_identifier();
+        let mut state = self.0.borrow_mut();
+        let executor = state.common.foreground_executor.clone();
+        state
+            .global_shortcuts
+            .register(&executor, id, keystroke, window_identifier, on_trigger)
+    }
+
+    fn unregister_global_hotkey(&self, id: GlobalHotkeyId) {
+        self.0.borrow_mut().global_shortcuts.unregister(id);
+    }
+
+    fn global_menu_setter(&self) -> Box<dyn Fn(&GlobalMenuAddress)> {
+        let client = Rc::downgrade(&self.0);
+        Box::new(move |address| {
+            let Some(client) = client.upgrade() else {
+                return;
+            };
+            let state = client.borrow();
+            for window in state.windows.values() {
+                window.set_global_menu(address);
+            }
+        })
+    }
+
     fn write_to_primary(&self, item: crate::ClipboardItem) {
         let mut state = self.0.borrow_mut();
         let (Some(primary_selection_manager), Some(primary_selection)) = (
@@ -845,12 +947,12 @@ impl LinuxClient for WaylandClient {
             return;
         };
         if state.mouse_focused_window.is_some() || state.keyboard_focused_window.is_some() {
-            state.clipboard.set_primary(item);
             let serial = state.serial_tracker.get(SerialKind::KeyPress);
             let data_source = primary_selection_manager.create_source(&state.globals.qh, ());
-            for mime_type in TEXT_MIME_TYPES {
+            for mime_type in item.mime_types() {
                 data_source.offer(mime_type.to_string());
             }
+            state.clipboard.set_primary(item);
             data_source.offer(state.clipboard.self_mime());
             primary_selection.set_selection(Some(&data_source), serial);
         }
@@ -865,12 +967,12 @@ impl LinuxClient for WaylandClient {
             return;
         };
         if state.mouse_focused_window.is_some() || state.keyboard_focused_window.is_some() {
-            state.clipboard.set(item);
             let serial = state.serial_tracker.get(SerialKind::KeyPress);
             let data_source = data_device_manager.create_data_source(&state.globals.qh, ());
-            for mime_type in TEXT_MIME_TYPES {
+            for mime_type in item.mime_types() {
                 data_source.offer(mime_type.to_string());
             }
+            state.clipboard.set(item);
             data_source.offer(state.clipboard.self_mime());
             data_device.set_selection(Some(&data_source), serial);
         }
@@ -986,6 +1088,8 @@ delegate_noop!(WaylandClientStatePtr: ignore zwlr_layer_shell_v1::ZwlrLayerShell
 delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_blur_manager::OrgKdeKwinBlurManager);
 delegate_noop!(WaylandClientStatePtr: ignore zwp_text_input_manager_v3::ZwpTextInputManagerV3);
 delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_blur::OrgKdeKwinBlur);
+delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_appmenu_manager::OrgKdeKwinAppmenuManager);
+delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_appmenu::OrgKdeKwinAppmenu);
 delegate_noop!(WaylandClientStatePtr: ignore wp_viewporter::WpViewporter);
 delegate_noop!(WaylandClientStatePtr: ignore wp_viewport::WpViewport);
 
@@ -1290,7 +1394,8 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                 state.compose_state = get_xkb_compose_state(&xkb_context);
                 drop(state);
 
-                this.handle_keyboard_layout_change();
+                // The keys of the layout may have changed even if its name didn't.
+                this.handle_keyboard_layout_change(true);
             }
             wl_keyboard::Event::Enter { surface, .. } => {
                 state.keyboard_focused_window = get_window(&mut state, &surface.id());
@@ -1327,10 +1432,15 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
             } => {
                 let focused_window = state.keyboard_focused_window.clone();
 
-                let keymap_state = state.keymap_state.as_mut().unwrap();
+                // Compositors may send modifiers before the keymap.
+                let Some(keymap_state) = state.keymap_state.as_mut() else {
+                    return;
+                };
                 let old_layout =
                     keymap_state.serialize_layout(xkbcommon::xkb::STATE_LAYOUT_EFFECTIVE);
                 keymap_state.update_mask(mods_depressed, mods_latched, mods_locked, 0, 0, group);
+                let new_layout =
+                    keymap_state.serialize_layout(xkbcommon::xkb::STATE_LAYOUT_EFFECTIVE);
                 state.modifiers = Modifiers::from_xkb(keymap_state);
                 let keymap_state = state.keymap_state.as_mut().unwrap();
                 state.capslock = Capslock::from_xkb(keymap_state);
@@ -1345,8 +1455,8 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                     focused_window.handle_input(input);
                 }
 
-                if group != old_layout {
-                    this.handle_keyboard_layout_change();
+                if new_layout != old_layout {
+                    this.handle_keyboard_layout_change(false);
                 }
             }
             wl_keyboard::Event::Key {
@@ -2128,6 +2238,39 @@ impl Dispatch<wl_data_source::WlDataSource, ()> for WaylandClientStatePtr {
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
+        let is_outgoing_drag = state
+            .outgoing_drag
+            .as_ref()
+            .is_some_and(|drag| drag.source == *data_source);
+        if is_outgoing_drag {
+            match event {
+                wl_data_source::Event::Send { mime_type, fd } => {
+                    let drag = state.outgoing_drag.as_ref().unwrap();
+                    if let Some(bytes) = drag.data.bytes_for_mime_type(&mime_type) {
+                        state.clipboard.send_internal(fd, bytes);
+                    }
+                }
+                wl_data_source::Event::Action { dnd_action } => {
+                    if let WEnum::Value(dnd_action) = dnd_action {
+                        state.outgoing_drag.as_mut().unwrap().action = drop_action(dnd_action);
+                    }
+                }
+                wl_data_source::Event::DndFinished => {
+                    let drag = state.outgoing_drag.take().unwrap();
+                    let action = drag.action;
+                    drop(state);
+                    drag.finish(action);
+                }
+                wl_data_source::Event::Cancelled => {
+                    let drag = state.outgoing_drag.take().unwrap();
+                    drop(state);
+                    drag.finish(DropAction::None);
+                }
+                _ => {}
+            }
+            return;
+        }
+
         match event {
             wl_data_source::Event::Send { mime_type, fd } => {
                 state.clipboard.send(mime_type, fd);
@@ -11,13 +11,10 @@ use wayland_client::{Connection, protocol::wl_data_offer::WlDataOffer};
 use wayland_protocols::wp::primary_selection::zv1::client::zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1;
 
 use crate::{
-    ClipboardEntry, ClipboardItem, Image, ImageFormat, WaylandClientStatePtr, hash,
+    ClipboardData, ClipboardEntry, ClipboardItem, Image, ImageFormat, WaylandClientStatePtr, hash,
     platform::linux::platform::read_fd,
 };
 
-/// Text mime types that we'll offer to other programs.
-pub(crate) const TEXT_MIME_TYPES: [&str; 3] =
-    ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
 pub(crate) const FILE_LIST_MIME_TYPE: &str = "text/uri-list";
 
 /// Text mime types that we'll accept from other programs.
@@ -135,6 +132,47 @@ impl<T: ReceiveData> DataOffer<T> {
         }
         None
     }
+
+    /// Reads the offer as text or an image, and offers every other mime type as
+    /// [`ClipboardEntry::Data`] that is only received once it's requested.
+    fn read_item(&self, connection: &Connection) -> Option<ClipboardItem>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let mut item = self
+            .read_text(connection)
+            .or_else(|| self.read_image(connection))
+            .unwrap_or_else(|| ClipboardItem {
+                entries: Vec::new(),
+            });
+        let read_mime_types = item.mime_types();
+
+        for mime_type in &self.mime_types {
+            // Skip X11 targets like `UTF8_STRING`, as well as our own pid marker.
+            if !mime_type.contains('/')
+                || mime_type.starts_with("pid/")
+                || mime_type.starts_with("text/plain")
+                || read_mime_types.iter().any(|read| read == mime_type)
+            {
+                continue;
+            }
+
+            let offer = self.clone();
+            let connection = connection.clone();
+            let requested = mime_type.clone();
+            item.entries
+                .push(ClipboardEntry::Data(ClipboardData::promised(
+                    mime_type.clone(),
+                    move || {
+                        offer
+                            .read_bytes(&connection, &requested)
+                            .unwrap_or_default()
+                    },
+                )));
+        }
+
+        (!item.entries.is_empty()).then_some(item)
+    }
 }
 
 impl Clipboard {
@@ -179,19 +217,23 @@ impl Clipboard {
         self.self_mime.clone()
     }
 
-    pub fn send(&self, _mime_type: String, fd: OwnedFd) {
-        if let Some(text) = self.contents.as_ref().and_then(|contents| contents.text()) {
-            self.send_internal(fd, text.as_bytes().to_owned());
+    pub fn send(&self, mime_type: String, fd: OwnedFd) {
+        if let Some(bytes) = self
+            .contents
+            .as_ref()
+            .and_then(|contents| contents.bytes_for_mime_type(&mime_type))
+        {
+            self.send_internal(fd, bytes.to_vec());
         }
     }
 
-    pub fn send_primary(&self, _mime_type: String, fd: OwnedFd) {
-        if let Some(text) = self
+    pub fn send_primary(&self, mime_type: String, fd: OwnedFd) {
+        if let Some(bytes) = self
             .primary_contents
             .as_ref()
-            .and_then(|contents| contents.text())
+            .and_then(|contents| contents.bytes_for_mime_type(&mime_type))
         {
-            self.send_internal(fd, text.as_bytes().to_owned());
+            self.send_internal(fd, bytes.to_vec());
         }
     }
 
@@ -205,9 +247,7 @@ impl Clipboard {
             return self.contents.clone();
         }
 
-        let item = offer
-            .read_text(&self.connection)
-            .or_else(|| offer.read_image(&self.connection))?;
+        let item = offer.read_item(&self.connection)?;
 
         self.cached_read = Some(item.clone());
         Some(item)
@@ -223,15 +263,13 @@ impl Clipboard {
             return self.primary_contents.clone();
         }
 
-        let item = offer
-            .read_text(&self.connection)
-            .or_else(|| offer.read_image(&self.connection))?;
+        let item = offer.read_item(&self.connection)?;
 
         self.cached_primary_read = Some(item.clone());
         Some(item)
     }
 
-    fn send_internal(&self, fd: OwnedFd, bytes: Vec<u8>) {
+    pub fn send_internal(&self, fd: OwnedFd, bytes: Vec<u8>) {
         let mut written = 0;
         self.loop_handle
             .insert_source(
@@ -0,0 +1,130 @@
+use std::{
+    fs::File,
+    io::Write as _,
+    os::fd::{AsFd, FromRawFd},
+};
+
+use anyhow::{Context as _, anyhow};
+use futures::channel::oneshot;
+use wayland_client::protocol::{
+    wl_buffer::WlBuffer, wl_data_device_manager::DndAction, wl_data_source::WlDataSource, wl_shm,
+    wl_surface::WlSurface,
+};
+
+use crate::{DropAction, ExternalDragData, Globals, Image};
+
+/// A drag started by us with `wl_data_device.start_drag`, kept alive until the
+/// compositor reports that it has been finished or cancelled.
+pub(crate) struct OutgoingDrag {
+    pub source: WlDataSource,
+    pub data: ExternalDragData,
+    pub action: DropAction,
+    icon: Option<DragIcon>,
+    done: oneshot::Sender<DropAction>,
+}
+
+impl OutgoingDrag {
+    pub fn new(
+        source: WlDataSource,
+        data: ExternalDragData,
+        icon: Option<DragIcon>,
+        done: oneshot::Sender<DropAction>,
+    ) -> Self {
+        Self {
+            source,
+            data,
+            action: DropAction::None,
+            icon,
+            done,
+        }
+    }
+
+    pub fn finish(self, action: DropAction) {
+        self.source.destroy();
+        drop(self.icon);
+        self.done.send(action).ok();
+    }
+}
+
+/// The surface shown under the cursor while one of our drags is in progress.
+pub(crate) struct DragIcon {
+    pub surface: WlSurface,
+    buffer: WlBuffer,
+}
+
+impl DragIcon {
+    pub fn new(globals: &Globals, image: &Image) -> anyhow::Result<Self> {
+        let pixels = image::load_from_memory(&image.bytes)
+            .context("failed to decode drag icon")?
+            .into_rgba8();
+        let (width, height) = pixels.dimensions();
+        let stride = width * 4;
+        let len = (stride * height) as usize;
+
+        // wl_shm's ARGB8888 is little-endian and premultiplied, so each pixel is stored as BGRA.
+        let mut bytes = Vec::with_capacity(len);
+        for pixel in pixels.pixels() {
+            let [r, g, b, a] = pixel.0;
+            let premultiply = |channel: u8| ((channel as u32 * a as u32 + 127) / 255) as u8;
+            bytes.extend_from_slice(&[premultiply(b), premultiply(g), premultiply(r), a]);
+        }
+
+        let fd = unsafe { libc::memfd_create(c"gpui-drag-icon".as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(anyhow!(
+                "memfd_create failed: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.write_all(&bytes)?;
+
+        let pool = globals
+            .shm
+            .create_pool(file.as_fd(), len as i32, &globals.qh, ());
+        let buffer = pool.create_buffer(
+            0,
+            width as i32,
+            height as i32,
+            stride as i32,
+            wl_shm::Format::Argb8888,
+            &globals.qh,
+            (),
+        );
+        pool.destroy();
+
+        let surface = globals.compositor.create_surface(&globals.qh, ());
+        surface.attach(Some(&buffer), 0, 0);
+        surface.damage_buffer(0, 0, width as i32, height as i32);
+
+        Ok(Self { surface, buffer })
+    }
+}
+
+impl Drop for DragIcon {
+    fn drop(&mut self) {
+        self.surface.destroy();
+        self.buffer.destroy();
+    }
+}
+
+/// Wayland has no link action, so [`DropAction::Link`] is not offered to drop targets.
+pub(crate) fn dnd_actions(actions: &[DropAction]) -> DndAction {
+    actions
+        .iter()
+        .fold(DndAction::empty(), |dnd_actions, action| match action {
+            DropAction::Copy => dnd_actions | DndAction::Copy,
+            DropAction::Move => dnd_actions | DndAction::Move,
+            DropAction::None | DropAction::Link => dnd_actions,
+        })
+}
+
+pub(crate) fn drop_action(dnd_action: DndAction) -> DropAction {
+    if dnd_action.contains(DndAction::Move) {
+        DropAction::Move
+    } else if dnd_action.contains(DndAction::Copy) {
+        DropAction::Copy
+    } else {
+        DropAction::None
+    }
+}
@@ -22,12 +22,13 @@ use wayland_protocols::{
     wp::fractional_scale::v1::client::wp_fractional_scale_v1,
     xdg::shell::client::xdg_toplevel::XdgToplevel,
 };
+use wayland_protocols_plasma::appmenu::client::org_kde_kwin_appmenu;
 use wayland_protocols_plasma::blur::client::org_kde_kwin_blur;
 use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1;
 
 use crate::{
-    AnyWindowHandle, Bounds, Decorations, Globals, GpuSpecs, Modifiers, Output, Pixels,
-    PlatformDisplay, PlatformInput, Point, PromptButton, PromptLevel, RequestFrameOptions,
+    AnyWindowHandle, Bounds, Decorations, GlobalMenuAddress, Globals, GpuSpecs, Modifiers, Output,
+    Pixels, PlatformDisplay, PlatformInput, Point, PromptButton, PromptLevel, RequestFrameOptions,
     ResizeEdge, Size, Tiling, WaylandClientStatePtr, WindowAppearance, WindowBackgroundAppearance,
     WindowBounds, WindowControlArea, WindowControls, WindowDecorations, WindowParams,
     layer_shell::LayerShellNotSupportedError, px, size,
@@ -91,6 +92,7 @@ pub struct WaylandWindowState {
     app_id: Option<String>,
     appearance: WindowAppearance,
     blur: Option<org_kde_kwin_blur::OrgKdeKwinBlur>,
+    appmenu: Option<org_kde_kwin_appmenu::OrgKdeKwinAppmenu>,
     viewport: Option<wp_viewport::WpViewport>,
     outputs: HashMap<ObjectId, Output>,
     display: Option<(ObjectId, Output)>,
@@ -322,6 +324,7 @@ impl WaylandWindowState {
             surface,
             app_id: None,
             blur: None,
+            appmenu: None,
             viewport,
             globals,
             outputs: HashMap::default(),
@@ -399,6 +402,9 @@ impl Drop for WaylandWindow {
         if let Some(blur) = &state.blur {
             blur.release();
         }
+        if let Some(appmenu) = &state.appmenu {
+            appmenu.release();
+        }
 
         // Decorations must be destroyed before the xdg state.
         // See https://wayland.app/protocols/xdg-decoration-unstable-v1#zxdg_toplevel_decoration_v1
@@ -501,6 +507,25 @@ impl WaylandWindowStatePtr {
         Rc::ptr_eq(&self.state, &other.state)
     }
 
+    /// Tells the compositor where to find the application menus, for its global menu bar.
+    pub fn set_global_menu(&self, address: &GlobalMenuAddress) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        let Some(appmenu_manager) = &state.globals.appmenu_manager else {
+            return;
+        };
+        if state.surface_state.toplevel().is_none() {
+            return;
+        }
+        let appmenu = state
+            .appmenu
+            .get_or_insert_with(|| appmenu_manager.create(&state.surface, &state.globals.qh, ()));
+        appmenu.set_address(
+            address.service_name().to_string(),
+            address.object_path().to_string(),
+        );
+    }
+
     pub fn frame(&self) {
         let mut state = self.state.borrow_mut();
         state.surface.frame(&state.globals.qh, state.surface.id());
@@ -1258,6 +1283,17 @@ impl PlatformWindow for WaylandWindow {
         }
     }
 
+    fn start_external_drag(
+        &self,
+        drag: crate::ExternalDrag,
+    ) -> Option<Receiver<crate::DropAction>> {
+        let state = self.borrow();
+        let surface = state.surface.clone();
+        let client = state.client.clone();
+        drop(state);
+        client.start_drag(&surface, drag)
+    }
+
     fn window_decorations(&self) -> Decorations {
         let state = self.borrow();
         match state.decorations {
@@ -2,6 +2,7 @@ mod client;
 mod clipboard;
 mod display;
 mod event;
+mod global_hotkey;
 mod window;
 mod xim_handler;
 
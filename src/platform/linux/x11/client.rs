@@ -7,6 +7,7 @@ use calloop::{
 };
 use collections::HashMap;
 use core::str;
+use futures::channel::oneshot;
 use http_client::Url;
 use log::Level;
 use smallvec::SmallVec;
@@ -44,26 +45,29 @@ use super::{
     ButtonOrScroll, ScrollDirection, X11Display, X11WindowStatePtr, XcbAtoms, XimCallbackEvent,
     XimHandler, button_or_scroll_from_event_detail, check_reply,
     clipboard::{self, Clipboard},
-    get_reply, get_valuator_axis_index, handle_connection_error, modifiers_from_state,
-    pressed_button_from_mask,
+    get_reply, get_valuator_axis_index,
+    global_hotkey::GrabbedHotkey,
+    handle_connection_error, modifiers_from_state, pressed_button_from_mask,
 };
 
 use crate::platform::{
     LinuxCommon, PlatformWindow,
     blade::BladeContext,
     linux::{
-        DEFAULT_CURSOR_ICON_NAME, LinuxClient, get_xkb_compose_state, is_within_click_distance,
-        log_cursor_icon_warning, open_uri_internal,
+        DEFAULT_CURSOR_ICON_NAME, GlobalMenuAddress, LinuxClient, get_xkb_compose_state,
+        is_within_click_distance, log_cursor_icon_warning, open_uri_internal,
         platform::{DOUBLE_CLICK_INTERVAL, SCROLL_LINES},
         reveal_path_internal,
         xdg_desktop_portal::{Event as XDPEvent, XDPEventSource},
     },
 };
 use crate::{
-    AnyWindowHandle, Bounds, ClipboardItem, CursorStyle, DisplayId, FileDropEvent, Keystroke,
-    LinuxKeyboardLayout, Modifiers, ModifiersChangedEvent, MouseButton, Pixels, Platform,
-    PlatformDisplay, PlatformInput, PlatformKeyboardLayout, Point, RequestFrameOptions,
-    ScrollDelta, Size, TouchPhase, WindowParams, X11Window, modifiers_from_xinput_info, point, px,
+    AnyWindowHandle, Bounds, ClipboardItem, CursorStyle, DisplayId, DropAction, ExternalDrag,
+    ExternalDragData, FileDropEvent, GlobalHotkeyError, GlobalHotkeyId, Keystroke,
+    LinuxKeyboardLayout, LinuxKeyboardMapper, Modifiers, ModifiersChangedEvent, MouseButton,
+    Pixels, Platform, PlatformDisplay, PlatformInput, PlatformKeyboardLayout,
+    PlatformKeyboardMapper, Point, RequestFrameOptions, ScrollDelta, Size, TouchPhase,
+    WindowParams, X11Window, modifiers_from_xinput_info, point, px,
 };
 
 /// Value for DeviceId parameters which selects all devices.
@@ -148,6 +152,32 @@ pub struct Xdnd {
     position: Point<Pixels>,
 }
 
+/// The XDND protocol version we speak as a drag source.
+const XDND_VERSION: u32 = 5;
+
+/// State of a drag started by one of our windows, see `X11ClientStatePtr::start_drag`.
+pub struct XdndSource {
+    window: xproto::Window,
+    data: ExternalDragData,
+    mime_types: SmallVec<[&'static str; 4]>,
+    types: Vec<xproto::Atom>,
+    action: xproto::Atom,
+    /// The top-level window under the pointer. The target is only looked up again when this
+    /// changes, since that takes a round trip for each level of windows.
+    top_level: Option<xproto::Window>,
+    /// The XdndAware window under the pointer and the protocol version it supports.
+    target: Option<(xproto::Window, u32)>,
+    accepted: bool,
+    accepted_action: DropAction,
+    done: oneshot::Sender<DropAction>,
+}
+
+impl XdndSource {
+    fn finish(self, action: DropAction) {
+        self.done.send(action).ok();
+    }
+}
+
 #[derive(Debug)]
 struct PointerDeviceState {
     horizontal: ScrollAxisState,
@@ -193,6 +223,7 @@ pub struct X11ClientState {
     pub(crate) keyboard_focused_window: Option<xproto::Window>,
     pub(crate) xkb: xkbc::State,
     keyboard_layout: LinuxKeyboardLayout,
+    keyboard_mapper: Rc<LinuxKeyboardMapper>,
     pub(crate) ximc: Option<X11rbClient<Rc<XCBConnection>>>,
     pub(crate) xim_handler: Option<XimHandler>,
     pub modifiers: Modifiers,
@@ -216,6 +247,11 @@ pub struct X11ClientState {
     pub(crate) clipboard: Clipboard,
     pub(crate) clipboard_item: Option<ClipboardItem>,
     pub(crate) xdnd_state: Xdnd,
+    pub(crate) xdnd_source: Option<XdndSource>,
+    /// Time of the last key or button event, which an outgoing drag claims the XdndSelection
+    /// and grabs with, as they're tied to the input that started it.
+    pub(crate) last_input_time: xproto::Timestamp,
+    pub(crate) global_hotkeys: HashMap<GlobalHotkeyId, GrabbedHotkey>,
 }
 
 #[derive(Clone)]
@@ -248,6 +284,114 @@ impl X11ClientStatePtr {
         state.cursor_styles.remove(&x_window);
     }
 
+    pub fn start_drag(
+        &self,
+        source_window: xproto::Window,
+        drag: ExternalDrag,
+    ) -> Option<oneshot::Receiver<DropAction>> {
+        let client = self.get_client()?;
+        let mut state = client.0.borrow_mut();
+        if let Some(previous_drag) = state.xdnd_source.take() {
+            previous_drag.finish(DropAction::None);
+        }
+
+        let mime_types = drag.data.mime_types();
+        let mut types = Vec::with_capacity(mime_types.len());
+        for mime_type in &mime_types {
+            let reply = get_reply(
+                || "Failed to intern XDnD type",
+                state
+                    .xcb_connection
+                    .intern_atom(false, mime_type.as_bytes()),
+            )
+            .log_err()?;
+            types.push(reply.atom);
+        }
+
+        check_reply(
+            || "Failed to set XdndTypeList property",
+            state.xcb_connection.change_property32(
+                xproto::PropMode::REPLACE,
+                source_window,
+                state.atoms.XdndTypeList,
+                state.atoms.XA_ATOM,
+                &types,
+            ),
+        )
+        .log_err()?;
+        let time = state.last_input_time;
+        check_reply(
+            || "Failed to take ownership of XdndSelection",
+            state.xcb_connection.set_selection_owner(
+                source_window,
+                state.atoms.XdndSelection,
+                time,
+            ),
+        )
+        .log_err()?;
+
+        // Pointer events are only delivered to us while the pointer is over one of our windows,
+        // so the pointer is grabbed for the duration of the drag. The keyboard is grabbed too, so
+        // that Escape cancels the drag wherever the pointer is.
+        let cursor = state
+            .get_cursor_icon(CursorStyle::DragCopy)
+            .unwrap_or(x11rb::NONE);
+        let grab = get_reply(
+            || "Failed to grab pointer for XDnD",
+            state.xcb_connection.grab_pointer(
+                false,
+                source_window,
+                EventMask::POINTER_MOTION | EventMask::BUTTON_RELEASE,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+                x11rb::NONE,
+                cursor,
+                time,
+            ),
+        )
+        .log_err()?;
+        if grab.status != xproto::GrabStatus::SUCCESS {
+            log::warn!("Failed to grab pointer for XDnD: {:?}", grab.status);
+            return None;
+        }
+        let keyboard_grab = get_reply(
+            || "Failed to grab keyboard for XDnD",
+            state.xcb_connection.grab_keyboard(
+                false,
+                source_window,
+                time,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+            ),
+        )
+        .log_err();
+        if let Some(keyboard_grab) = keyboard_grab
+            && keyboard_grab.status != xproto::GrabStatus::SUCCESS
+        {
+            log::warn!(
+                "Failed to grab keyboard for XDnD: {:?}",
+                keyboard_grab.status
+            );
+        }
+        xcb_flush(&state.xcb_connection);
+
+        let action = xdnd_action_atom(&state.atoms, drag.preferred_action());
+        let (done_tx, done_rx) = oneshot::channel();
+        state.xdnd_source = Some(XdndSource {
+            window: source_window,
+            data: drag.data,
+            mime_types,
+            types,
+            action,
+            top_level: None,
+            target: None,
+            accepted: false,
+            accepted_action: DropAction::None,
+            done: done_tx,
+        });
+        Some(done_rx)
+    }
+
     pub fn update_ime_position(&self, bounds: Bounds<Pixels>) {
         let Some(client) = self.get_client() else {
             return;
@@ -430,12 +574,8 @@ impl X11Client {
             xkbc::x11::state_new_from_device(&xkb_keymap, &xcb_connection, xkb_device_id)
         };
         let compose_state = get_xkb_compose_state(&xkb_context);
-        let layout_idx = xkb_state.serialize_layout(STATE_LAYOUT_EFFECTIVE);
-        let layout_name = xkb_state
-            .get_keymap()
-            .layout_get_name(layout_idx)
-            .to_string();
-        let keyboard_layout = LinuxKeyboardLayout::new(layout_name.into());
+        let keyboard_layout = LinuxKeyboardLayout::from_xkb(&xkb_state);
+        let keyboard_mapper = Rc::new(LinuxKeyboardMapper::from_xkb(&xkb_state));
 
         let gpu_context = BladeContext::new().notify_err("Unable to init GPU context");
 
@@ -523,6 +663,7 @@ impl X11Client {
             keyboard_focused_window: None,
             xkb: xkb_state,
             keyboard_layout,
+            keyboard_mapper,
             ximc,
             xim_handler,
 
@@ -540,6 +681,9 @@ impl X11Client {
             clipboard,
             clipboard_item: None,
             xdnd_state: Xdnd::default(),
+            xdnd_source: None,
+            last_input_time: x11rb::CURRENT_TIME,
+            global_hotkeys: HashMap::default(),
         }))))
     }
 
@@ -643,6 +787,20 @@ impl X11Client {
             }
 
             for event in events.into_iter() {
+                // Escape cancels an outgoing drag, before the input method can see it.
+                if let Event::KeyPress(key_press) = &event
+                    && self.handle_xdnd_source_key_press(key_press)
+                {
+                    continue;
+                }
+
+                // Grabbed hotkeys aren't typed into any window, so the input method mustn't see them.
+                if let Event::KeyPress(key_press) = &event
+                    && self.handle_global_hotkey(key_press)
+                {
+                    continue;
+                }
+
                 let mut state = self.0.borrow_mut();
                 if !state.has_xim() {
                     drop(state);
@@ -761,6 +919,32 @@ impl X11Client {
             .map(|window_reference| window_reference.window.clone())
     }
 
+    /// Triggers the global hotkey that a key press is for, if any, and returns whether it was.
+    fn handle_global_hotkey(&self, event: &xproto::KeyPressEvent) -> bool {
+        let mut state = self.0.borrow_mut();
+        let root = state.xcb_connection.setup().roots[state.x_root_index].root;
+        let Some((&id, hotkey)) = state
+            .global_hotkeys
+            .iter_mut()
+            .find(|(_, hotkey)| hotkey.is_triggered_by(root, event))
+        else {
+            return false;
+        };
+        // The hotkey is triggering already, from a nested event loop.
+        let Some(mut on_trigger) = hotkey.on_trigger.take() else {
+            return true;
+        };
+        drop(state);
+
+        on_trigger();
+
+        let mut state = self.0.borrow_mut();
+        if let Some(hotkey) = state.global_hotkeys.get_mut(&id) {
+            hotkey.on_trigger = Some(on_trigger);
+        }
+        true
+    }
+
     fn handle_event(&self, event: Event) -> Option<()> {
         match event {
             Event::UnmapNotify(event) => {
@@ -870,8 +1054,110 @@ impl X11Client {
                     window
                         .handle_input(PlatformInput::FileDrop(FileDropEvent::Submit { position }));
                     self.0.borrow_mut().xdnd_state = Xdnd::default();
+                } else if event.type_ == state.atoms.XdndStatus {
+                    let accepted_action = xdnd_drop_action(&state.atoms, arg4);
+                    if let Some(source) = state.xdnd_source.as_mut()
+                        && source.target.is_some_and(|(target, _)| target == atom)
+                    {
+                        source.accepted = (arg1 & 0x1) == 0x1;
+                        source.accepted_action = accepted_action;
+                    }
+                } else if event.type_ == state.atoms.XdndFinished {
+                    let finished_action = xdnd_drop_action(&state.atoms, arg2);
+                    if state.xdnd_source.as_ref().is_some_and(|source| {
+                        source.target.is_some_and(|(target, _)| target == atom)
+                    }) {
+                        let source = state.xdnd_source.take().unwrap();
+                        let action = match source.target {
+                            // Version 5 reports whether the drop succeeded and the action taken.
+                            Some((_, version)) if version >= 5 => {
+                                if (arg1 & 0x1) == 0x1 {
+                                    finished_action
+                                } else {
+                                    DropAction::None
+                                }
+                            }
+                            _ => source.accepted_action,
+                        };
+                        drop(state);
+                        source.finish(action);
+                    }
                 }
             }
+            Event::SelectionRequest(event) => {
+                let state = self.0.borrow();
+                if event.selection != state.atoms.XdndSelection {
+                    return Some(());
+                }
+
+                let mut property = if event.property == x11rb::NONE {
+                    event.target
+                } else {
+                    event.property
+                };
+                let source = state.xdnd_source.as_ref();
+                let result = if event.target == state.atoms.TARGETS
+                    && let Some(source) = source
+                {
+                    check_reply(
+                        || "Failed to set XDnD targets",
+                        state.xcb_connection.change_property32(
+                            xproto::PropMode::REPLACE,
+                            event.requestor,
+                            property,
+                            state.atoms.XA_ATOM,
+                            &source.types,
+                        ),
+                    )
+                } else if let Some(bytes) = source.and_then(|source| {
+                    let index = source.types.iter().position(|ty| *ty == event.target)?;
+                    source.data.bytes_for_mime_type(source.mime_types[index])
+                }) {
+                    check_reply(
+                        || "Failed to set XDnD data",
+                        state.xcb_connection.change_property8(
+                            xproto::PropMode::REPLACE,
+                            event.requestor,
+                            property,
+                            event.target,
+                            &bytes,
+                        ),
+                    )
+                } else {
+                    Err(anyhow!("No XDnD data for the requested target"))
+                };
+                if result.log_err().is_none() {
+                    property = x11rb::NONE;
+                }
+
+                let notify = xproto::SelectionNotifyEvent {
+                    response_type: xproto::SELECTION_NOTIFY_EVENT,
+                    sequence: 0,
+                    time: event.time,
+                    requestor: event.requestor,
+                    selection: event.selection,
+                    target: event.target,
+                    property,
+                };
+                check_reply(
+                    || "Failed to send XDnD selection notify",
+                    state.xcb_connection.send_event(
+                        false,
+                        event.requestor,
+                        EventMask::NO_EVENT,
+                        notify,
+                    ),
+                )
+                .log_err();
+                xcb_flush(&state.xcb_connection);
+            }
+            // Core pointer events are only selected by the pointer grab of an outgoing drag.
+            Event::MotionNotify(event) => {
+                self.handle_xdnd_source_motion(event.root_x, event.root_y, event.time);
+            }
+            Event::ButtonRelease(event) => {
+                self.handle_xdnd_source_release(event.time);
+            }
             Event::SelectionNotify(event) => {
                 let window = self.get_window(event.requestor)?;
                 let mut state = self.0.borrow_mut();
@@ -970,7 +1256,8 @@ impl X11Client {
                 };
                 state.xkb = xkb_state;
                 drop(state);
-                self.handle_keyboard_layout_change();
+                // The keys of the layout may have changed even if its name didn't.
+                self.handle_keyboard_layout_change(true);
             }
             Event::XkbStateNotify(event) => {
                 let mut state = self.0.borrow_mut();
@@ -986,34 +1273,39 @@ impl X11Client {
                 );
                 let modifiers = Modifiers::from_xkb(&state.xkb);
                 let capslock = Capslock::from_xkb(&state.xkb);
+                // Layouts are often switched with modifiers, so a change of layout is reported
+                // below whether or not a window has focus.
                 if state.last_modifiers_changed_event == modifiers
                     && state.last_capslock_changed_event == capslock
                 {
                     drop(state);
-                } else {
-                    let focused_window_id = state.keyboard_focused_window?;
+                } else if let Some(focused_window_id) = state.keyboard_focused_window {
                     state.modifiers = modifiers;
                     state.last_modifiers_changed_event = modifiers;
                     state.capslock = capslock;
                     state.last_capslock_changed_event = capslock;
                     drop(state);
 
-                    let focused_window = self.get_window(focused_window_id)?;
-                    focused_window.handle_input(PlatformInput::ModifiersChanged(
-                        ModifiersChangedEvent {
-                            modifiers,
-                            capslock,
-                        },
-                    ));
+                    if let Some(focused_window) = self.get_window(focused_window_id) {
+                        focused_window.handle_input(PlatformInput::ModifiersChanged(
+                            ModifiersChangedEvent {
+                                modifiers,
+                                capslock,
+                            },
+                        ));
+                    }
+                } else {
+                    drop(state);
                 }
 
                 if new_layout != old_layout {
-                    self.handle_keyboard_layout_change();
+                    self.handle_keyboard_layout_change(false);
                 }
             }
             Event::KeyPress(event) => {
                 let window = self.get_window(event.event)?;
                 let mut state = self.0.borrow_mut();
+                state.last_input_time = event.time;
 
                 let modifiers = modifiers_from_state(event.state);
                 state.modifiers = modifiers;
@@ -1103,6 +1395,7 @@ impl X11Client {
             Event::XinputButtonPress(event) => {
                 let window = self.get_window(event.event)?;
                 let mut state = self.0.borrow_mut();
+                state.last_input_time = event.time;
 
                 let modifiers = modifiers_from_xinput_info(event.mods);
                 state.modifiers = modifiers;
@@ -1180,6 +1473,7 @@ impl X11Client {
             Event::XinputButtonRelease(event) => {
                 let window = self.get_window(event.event)?;
                 let mut state = self.0.borrow_mut();
+                state.last_input_time = event.time;
                 let modifiers = modifiers_from_xinput_info(event.mods);
                 state.modifiers = modifiers;
 
@@ -1205,6 +1499,7 @@ impl X11Client {
             Event::XinputMotion(event) => {
                 let window = self.get_window(event.event)?;
                 let mut state = self.0.borrow_mut();
+                state.last_input_time = event.time;
                 let pressed_button = pressed_button_from_mask(event.button_mask[0]);
                 let position = point(
                     px(event.event_x as f32 / u16::MAX as f32 / state.scale_factor),
@@ -1389,13 +1684,146 @@ impl X11Client {
         Some(())
     }
 
-    fn handle_keyboard_layout_change(&self) {
+    fn handle_xdnd_source_motion(&self, root_x: i16, root_y: i16, time: xproto::Timestamp) {
         let mut state = self.0.borrow_mut();
-        let layout_idx = state.xkb.serialize_layout(STATE_LAYOUT_EFFECTIVE);
-        let keymap = state.xkb.get_keymap();
-        let layout_name = keymap.layout_get_name(layout_idx);
-        if layout_name != state.keyboard_layout.name() {
-            state.keyboard_layout = LinuxKeyboardLayout::new(layout_name.to_string().into());
+        let state = &mut *state;
+        let Some(source) = state.xdnd_source.as_mut() else {
+            return;
+        };
+
+        let root = state.xcb_connection.setup().roots[state.x_root_index].root;
+        let top_level = xdnd_top_level(&state.xcb_connection, root, root_x, root_y);
+        let target = if top_level == source.top_level {
+            source.target
+        } else {
+            source.top_level = top_level;
+            top_level.and_then(|top_level| {
+                xdnd_find_target(
+                    &state.xcb_connection,
+                    &state.atoms,
+                    root,
+                    top_level,
+                    root_x,
+                    root_y,
+                )
+            })
+        };
+        if target.map(|(window, _)| window) != source.target.map(|(window, _)| window) {
+            if let Some((previous_target, _)) = source.target.take() {
+                xdnd_send_message(
+                    &state.xcb_connection,
+                    previous_target,
+                    state.atoms.XdndLeave,
+                    [source.window, 0, 0, 0, 0],
+                );
+            }
+            source.accepted = false;
+            source.accepted_action = DropAction::None;
+
+            if let Some((target, version)) = target {
+                let more_than_three_types = (source.types.len() > 3) as u32;
+                let mut types = source.types.iter().copied().chain(std::iter::repeat(0));
+                xdnd_send_message(
+                    &state.xcb_connection,
+                    target,
+                    state.atoms.XdndEnter,
+                    [
+                        source.window,
+                        (version.min(XDND_VERSION) << 24) | more_than_three_types,
+                        types.next().unwrap(),
+                        types.next().unwrap(),
+                        types.next().unwrap(),
+                    ],
+                );
+            }
+            source.target = target;
+        }
+
+        if let Some((target, _)) = source.target {
+            xdnd_send_message(
+                &state.xcb_connection,
+                target,
+                state.atoms.XdndPosition,
+                [
+                    source.window,
+                    0,
+                    ((root_x as u32) << 16) | (root_y as u16 as u32),
+                    time,
+                    source.action,
+                ],
+            );
+        }
+        xcb_flush(&state.xcb_connection);
+    }
+
+    fn handle_xdnd_source_release(&self, time: xproto::Timestamp) {
+        let state = self.0.borrow();
+        let Some(source) = state.xdnd_source.as_ref() else {
+            return;
+        };
+        match source.target {
+            // Wait for XdndFinished before reporting the result.
+            Some((target, _)) if source.accepted => {
+                xdnd_ungrab(&state.xcb_connection, time);
+                xdnd_send_message(
+                    &state.xcb_connection,
+                    target,
+                    state.atoms.XdndDrop,
+                    [source.window, 0, time, 0, 0],
+                );
+                xcb_flush(&state.xcb_connection);
+            }
+            _ => {
+                drop(state);
+                self.cancel_xdnd_source(time);
+            }
+        }
+    }
+
+    /// Cancels the outgoing drag when Escape is pressed during it, returning whether the key
+    /// press was handled.
+    fn handle_xdnd_source_key_press(&self, event: &xproto::KeyPressEvent) -> bool {
+        let state = self.0.borrow();
+        if state.xdnd_source.is_none() {
+            return false;
+        }
+        let keysym = state.xkb.key_get_one_sym(event.detail.into());
+        drop(state);
+        if keysym != xkbc::Keysym::Escape {
+            return false;
+        }
+        self.cancel_xdnd_source(event.time);
+        true
+    }
+
+    /// Ends the outgoing drag without dropping, leaving the target it was over.
+    fn cancel_xdnd_source(&self, time: xproto::Timestamp) {
+        let mut state = self.0.borrow_mut();
+        let Some(source) = state.xdnd_source.take() else {
+            return;
+        };
+        xdnd_ungrab(&state.xcb_connection, time);
+        if let Some((target, _)) = source.target {
+            xdnd_send_message(
+                &state.xcb_connection,
+                target,
+                state.atoms.XdndLeave,
+                [source.window, 0, 0, 0, 0],
+            );
+        }
+        xcb_flush(&state.xcb_connection);
+        drop(state);
+        source.finish(DropAction::None);
+    }
+
+    /// Updates the keyboard layout and its mapper, reporting the change if the layout is another
+    /// one or `keymap_changed` is set.
+    fn handle_keyboard_layout_change(&self, keymap_changed: bool) {
+        let mut state = self.0.borrow_mut();
+        let keyboard_layout = LinuxKeyboardLayout::from_xkb(&state.xkb);
+        if keymap_changed || keyboard_layout.name() != state.keyboard_layout.name() {
+            state.keyboard_layout = keyboard_layout;
+            state.keyboard_mapper = Rc::new(LinuxKeyboardMapper::from_xkb(&state.xkb));
             if let Some(mut callback) = state.common.callbacks.keyboard_layout_change.take() {
                 drop(state);
                 callback();
@@ -1420,6 +1848,10 @@ impl LinuxClient for X11Client {
         Box::new(state.keyboard_layout.clone())
     }
 
+    fn keyboard_mapper(&self) -> Rc<dyn PlatformKeyboardMapper> {
+        self.0.borrow().keyboard_mapper.clone()
+    }
+
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
         let state = self.0.borrow();
         let setup = state.xcb_connection.setup();
@@ -1510,6 +1942,14 @@ impl LinuxClient for X11Client {
             ),
         )
         .log_err();
+        if let Some(address) = state
+            .common
+            .global_menu
+            .as_ref()
+            .and_then(|global_menu| global_menu.address())
+        {
+            set_window_global_menu(&state, x_window, &address);
+        }
         xcb_flush(&state.xcb_connection);
 
         let window_ref = WindowRef {
@@ -1570,8 +2010,8 @@ impl LinuxClient for X11Client {
         let state = self.0.borrow_mut();
         state
             .clipboard
-            .set_text(
-                std::borrow::Cow::Owned(item.text().unwrap_or_default()),
+            .set_item(
+                &item,
                 clipboard::ClipboardKind::Primary,
                 clipboard::WaitConfig::None,
             )
@@ -1583,8 +2023,8 @@ impl LinuxClient for X11Client {
         let mut state = self.0.borrow_mut();
         state
             .clipboard
-            .set_text(
-                std::borrow::Cow::Owned(item.text().unwrap_or_default()),
+            .set_item(
+                &item,
                 clipboard::ClipboardKind::Clipboard,
                 clipboard::WaitConfig::None,
             )
@@ -1686,6 +2126,49 @@ impl LinuxClient for X11Client {
         Some(handles)
     }
 
+    fn register_global_hotkey(
+        &self,
+        id: GlobalHotkeyId,
+        keystroke: &Keystroke,
+        on_trigger: Box<dyn FnMut()>,
+    ) -> oneshot::Receiver<Result<(), GlobalHotkeyError>> {
+        let mut state = self.0.borrow_mut();
+        let root = state.xcb_connection.setup().roots[state.x_root_index].root;
+        let keymap = state.xkb.get_keymap();
+        let result =
+            GrabbedHotkey::grab(&state.xcb_connection, root, &keymap, keystroke, on_trigger).map(
+                |hotkey| {
+                    state.global_hotkeys.insert(id, hotkey);
+                },
+            );
+
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx.send(result).ok();
+        done_rx
+    }
+
+    fn unregister_global_hotkey(&self, id: GlobalHotkeyId) {
+        let mut state = self.0.borrow_mut();
+        let root = state.xcb_connection.setup().roots[state.x_root_index].root;
+        if let Some(hotkey) = state.global_hotkeys.remove(&id) {
+            hotkey.ungrab(&state.xcb_connection, root);
+        }
+    }
+
+    fn global_menu_setter(&self) -> Box<dyn Fn(&GlobalMenuAddress)> {
+        let client = X11ClientStatePtr(Rc::downgrade(&self.0));
+        Box::new(move |address| {
+            let Some(client) = client.get_client() else {
+                return;
+            };
+            let state = client.0.borrow();
+            for x_window in state.windows.keys() {
+                set_window_global_menu(&state, *x_window, address);
+            }
+            xcb_flush(&state.xcb_connection);
+        })
+    }
+
     fn window_identifier(&self) -> impl Future<Output = Option<WindowIdentifier>> + Send + 'static {
         let state = self.0.borrow();
         state
@@ -2065,6 +2548,110 @@ fn xdnd_get_supported_atom(
     0
 }
 
+/// Finds the top-level window under the given root coordinates.
+fn xdnd_top_level(
+    xcb_connection: &XCBConnection,
+    root: xproto::Window,
+    root_x: i16,
+    root_y: i16,
+) -> Option<xproto::Window> {
+    let reply = get_reply(
+        || "Failed to translate XDnD coordinates",
+        xcb_connection.translate_coordinates(root, root, root_x, root_y),
+    )
+    .log_err()?;
+    (reply.child != x11rb::NONE).then_some(reply.child)
+}
+
+/// Finds the outermost XdndAware window under the given root coordinates, starting from the
+/// top-level window there, returning it along with the XDnD version it supports.
+fn xdnd_find_target(
+    xcb_connection: &XCBConnection,
+    atoms: &XcbAtoms,
+    root: xproto::Window,
+    top_level: xproto::Window,
+    root_x: i16,
+    root_y: i16,
+) -> Option<(xproto::Window, u32)> {
+    let mut window = top_level;
+    loop {
+        let version = get_reply(
+            || "Failed to get XdndAware property",
+            xcb_connection.get_property(false, window, atoms.XdndAware, AtomEnum::ATOM, 0, 1),
+        )
+        .ok()
+        .and_then(|reply| reply.value32()?.next());
+        if let Some(version) = version {
+            return Some((window, version));
+        }
+
+        let reply = get_reply(
+            || "Failed to translate XDnD coordinates",
+            xcb_connection.translate_coordinates(root, window, root_x, root_y),
+        )
+        .log_err()?;
+        if reply.child == x11rb::NONE {
+            return None;
+        }
+        window = reply.child;
+    }
+}
+
+fn xdnd_action_atom(atoms: &XcbAtoms, action: DropAction) -> xproto::Atom {
+    match action {
+        DropAction::None | DropAction::Copy => atoms.XdndActionCopy,
+        DropAction::Move => atoms.XdndActionMove,
+        DropAction::Link => atoms.XdndActionLink,
+    }
+}
+
+fn xdnd_drop_action(atoms: &XcbAtoms, atom: xproto::Atom) -> DropAction {
+    if atom == atoms.XdndActionCopy {
+        DropAction::Copy
+    } else if atom == atoms.XdndActionMove {
+        DropAction::Move
+    } else if atom == atoms.XdndActionLink {
+        DropAction::Link
+    } else {
+        DropAction::None
+    }
+}
+
+/// Releases the pointer and keyboard grabs of an outgoing drag.
+fn xdnd_ungrab(xcb_connection: &XCBConnection, time: xproto::Timestamp) {
+    check_reply(
+        || "Failed to ungrab pointer after XDnD",
+        xcb_connection.ungrab_pointer(time),
+    )
+    .log_err();
+    check_reply(
+        || "Failed to ungrab keyboard after XDnD",
+        xcb_connection.ungrab_keyboard(time),
+    )
+    .log_err();
+}
+
+fn xdnd_send_message(
+    xcb_connection: &XCBConnection,
+    target: xproto::Window,
+    type_: xproto::Atom,
+    data: [u32; 5],
+) {
+    let message = ClientMessageEvent {
+        format: 32,
+        window: target,
+        type_,
+        data: ClientMessageData::from(data),
+        sequence: 0,
+        response_type: xproto::CLIENT_MESSAGE_EVENT,
+    };
+    check_reply(
+        || "Failed to send XDnD message",
+        xcb_connection.send_event(false, target, EventMask::NO_EVENT, message),
+    )
+    .log_err();
+}
+
 fn xdnd_send_finished(
     xcb_connection: &XCBConnection,
     atoms: &XcbAtoms,
@@ -2265,6 +2852,49 @@ fn create_invisible_cursor(
     Ok(cursor)
 }
 
+/// Points a window at the exported application menus, for global menu bars to show. KDE reads
+/// the window properties, and other desktops ask the registrar.
+fn set_window_global_menu(
+    state: &X11ClientState,
+    x_window: xproto::Window,
+    address: &GlobalMenuAddress,
+) {
+    for (property, value) in [
+        (
+            state.atoms._KDE_NET_WM_APPMENU_SERVICE_NAME,
+            address.service_name(),
+        ),
+        (
+            state.atoms._KDE_NET_WM_APPMENU_OBJECT_PATH,
+            address.object_path(),
+        ),
+    ] {
+        check_reply(
+            || "X11 ChangeProperty8 for the global menu failed.",
+            state.xcb_connection.change_property8(
+                xproto::PropMode::REPLACE,
+                x_window,
+                property,
+                xproto::AtomEnum::STRING,
+                value.as_bytes(),
+            ),
+        )
+        .log_err();
+    }
+
+    let registration = address.clone().register_x11_window(x_window);
+    state
+        .common
+        .foreground_executor
+        .spawn(async move {
+            // Most desktops don't run a registrar, so this failing isn't worth reporting.
+            if let Err(error) = registration.await {
+                log::debug!("failed to register the global menu of a window: {error}");
+            }
+        })
+        .detach();
+}
+
 enum DpiMode {
     Randr,
     Scale(f32),
@@ -19,7 +19,6 @@
 // https://freedesktop.org/wiki/ClipboardManager/
 
 use std::{
-    borrow::Cow,
     cell::RefCell,
     collections::{HashMap, hash_map::Entry},
     sync::{
@@ -47,7 +46,7 @@ use x11rb::{
     wrapper::ConnectionExt as _,
 };
 
-use crate::{ClipboardItem, Image, ImageFormat, hash};
+use crate::{ClipboardEntry, ClipboardItem, Image, ImageFormat, hash};
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -77,7 +76,7 @@ x11rb::atom_manager! {
         TEXT_MIME_UNKNOWN: b"text/plain",
 
         // HTML: b"text/html",
-        // URI_LIST: b"text/uri-list",
+        URI_LIST: b"text/uri-list",
 
         PNG__MIME: ImageFormat::mime_type(ImageFormat::Png ).as_bytes(),
         JPEG_MIME: ImageFormat::mime_type(ImageFormat::Jpeg).as_bytes(),
@@ -183,7 +182,7 @@ impl XContext {
 
 #[derive(Default)]
 struct Selection {
-    data: RwLock<Option<Vec<ClipboardData>>>,
+    data: RwLock<Option<Vec<OfferedData>>>,
     /// Mutex around nothing to use with the below condvar.
     mutex: Mutex<()>,
     /// A condvar that is notified when the contents of this clipboard are changed.
@@ -200,6 +199,15 @@ struct ClipboardData {
     format: Atom,
 }
 
+/// Data we offer to other clients. Promised data is only generated once it's requested.
+#[derive(Debug, Clone)]
+struct OfferedData {
+    data: crate::ClipboardData,
+
+    /// The atom representing the format in which the data is encoded.
+    format: Atom,
+}
+
 enum ReadSelNotifyResult {
     GotData(ClipboardData),
     IncrStarted,
@@ -228,7 +236,7 @@ impl Inner {
 
     fn write(
         &self,
-        data: Vec<ClipboardData>,
+        data: Vec<OfferedData>,
         selection: ClipboardKind,
         wait: WaitConfig,
     ) -> Result<()> {
@@ -289,7 +297,10 @@ impl Inner {
                 for data in data_list {
                     for format in formats {
                         if *format == data.format {
-                            return Ok(data.clone());
+                            return Ok(ClipboardData {
+                                bytes: data.data.bytes().to_vec(),
+                                format: data.format,
+                            });
                         }
                     }
                 }
@@ -362,6 +373,31 @@ impl Inner {
         Err(Error::ContentNotAvailable)
     }
 
+    /// Returns the formats the current owner of the selection offers its contents in.
+    fn targets(&self, selection: ClipboardKind) -> Result<Vec<Atom>> {
+        if self.is_owner(selection)? {
+            let data = self.selection_of(selection).data.read();
+            return Ok(data.iter().flatten().map(|data| data.format).collect());
+        }
+        let reader = XContext::new()?;
+        let data = self.read_single(&reader, selection, self.atoms.TARGETS)?;
+        if data.format != self.atoms.ATOM {
+            return Err(Error::ConversionFailure);
+        }
+        Ok(Self::parse_formats(&data.bytes))
+    }
+
+    fn intern_atom(&self, name: &str) -> Result<Atom> {
+        Ok(self
+            .server
+            .conn
+            .intern_atom(false, name.as_bytes())
+            .map_err(into_unknown)?
+            .reply()
+            .map_err(into_unknown)?
+            .atom)
+    }
+
     fn parse_formats(bytes: &[u8]) -> Vec<Atom> {
         bytes
             .chunks_exact(4)
@@ -723,7 +759,7 @@ impl Inner {
                                 event.requestor,
                                 event.property,
                                 event.target,
-                                &data.bytes,
+                                &data.data.bytes(),
                             )
                             .map_err(into_unknown)?;
                         self.server.conn.flush().map_err(into_unknown)?;
@@ -976,27 +1012,62 @@ impl Clipboard {
         Ok(Self { inner: ctx })
     }
 
-    pub(crate) fn set_text(
+    /// Offers every entry of the item, each in the format matching its mime type.
+    pub(crate) fn set_item(
         &self,
-        message: Cow<'_, str>,
+        item: &ClipboardItem,
         selection: ClipboardKind,
         wait: WaitConfig,
     ) -> Result<()> {
-        let data = vec![ClipboardData {
-            bytes: message.into_owned().into_bytes(),
-            format: self.inner.atoms.UTF8_STRING,
-        }];
+        let mut data = Vec::with_capacity(item.entries().len());
+        for entry in item.entries() {
+            let offered = match entry {
+                ClipboardEntry::String(_) => {
+                    if data
+                        .iter()
+                        .any(|data: &OfferedData| data.format == self.inner.atoms.UTF8_STRING)
+                    {
+                        continue;
+                    }
+                    self.text_data(item.text().unwrap_or_default())
+                }
+                ClipboardEntry::Image(image) => OfferedData {
+                    data: crate::ClipboardData::new(
+                        image.format.mime_type(),
+                        image.bytes.as_slice(),
+                    ),
+                    format: self.image_format_atom(image.format),
+                },
+                ClipboardEntry::ExternalPaths(_) => {
+                    let Some(bytes) = item.bytes_for_mime_type("text/uri-list") else {
+                        continue;
+                    };
+                    OfferedData {
+                        data: crate::ClipboardData::new("text/uri-list", bytes),
+                        format: self.inner.atoms.URI_LIST,
+                    }
+                }
+                ClipboardEntry::Data(entry) => OfferedData {
+                    data: entry.clone(),
+                    format: self.inner.intern_atom(entry.mime_type())?,
+                },
+            };
+            if !data.iter().any(|data| data.format == offered.format) {
+                data.push(offered);
+            }
+        }
         self.inner.write(data, selection, wait)
     }
 
-    #[allow(unused)]
-    pub(crate) fn set_image(
-        &self,
-        image: Image,
-        selection: ClipboardKind,
-        wait: WaitConfig,
-    ) -> Result<()> {
-        let format = match image.format {
+    fn text_data(&self, text: String) -> OfferedData {
+        OfferedData {
+            data: crate::ClipboardData::new("UTF8_STRING", text.into_bytes()),
+            format: self.inner.atoms.UTF8_STRING,
+        }
+    }
+
+    fn image_format_atom(&self, format: ImageFormat) -> Atom {
+        match format {
             ImageFormat::Png => self.inner.atoms.PNG__MIME,
             ImageFormat::Jpeg => self.inner.atoms.JPEG_MIME,
             ImageFormat::Webp => self.inner.atoms.WEBP_MIME,
@@ -1005,10 +1076,19 @@ impl Clipboard {
             ImageFormat::Bmp => self.inner.atoms.BMP__MIME,
             ImageFormat::Tiff => self.inner.atoms.TIFF_MIME,
             ImageFormat::Ico => self.inner.atoms.ICO__MIME,
-        };
-        let data = vec![ClipboardData {
-            bytes: image.bytes,
-            format: self.inner.atoms.PNG__MIME,
+        }
+    }
+
+    #[allow(unused)]
+    pub(crate) fn set_image(
+        &self,
+        image: Image,
+        selection: ClipboardKind,
+        wait: WaitConfig,
+    ) -> Result<()> {
+        let data = vec![OfferedData {
+            data: crate::ClipboardData::new(image.format.mime_type(), image.bytes),
+            format: self.image_format_atom(image.format),
         }];
         self.inner.write(data, selection, wait)
     }
@@ -1056,33 +1136,75 @@ impl Clipboard {
         format_atoms[IMAGE_FORMAT_COUNT..].copy_from_slice(&text_format_atoms);
         debug_assert!(!format_atoms.contains(&atom_none));
 
-        let result = self.inner.read(&format_atoms, selection)?;
+        let mut item = ClipboardItem::from_entries([]);
+        let mut read_format = None;
+        match self.inner.read(&format_atoms, selection) {
+            Ok(result) => {
+                log::trace!(
+                    "read clipboard as format {:?}",
+                    self.inner.atom_name(result.format)
+                );
+                read_format = Some(result.format);
 
-        log::trace!(
-            "read clipboard as format {:?}",
-            self.inner.atom_name(result.format)
-        );
+                let image_format = image_format_atoms.into_iter().zip(image_formats).find_map(
+                    |(format_atom, image_format)| {
+                        (result.format == format_atom).then_some(image_format)
+                    },
+                );
+                if let Some(image_format) = image_format {
+                    let bytes = result.bytes;
+                    let id = hash(&bytes);
+                    item.entries.push(ClipboardEntry::Image(Image {
+                        id,
+                        format: image_format,
+                        bytes,
+                    }));
+                } else {
+                    let text = if result.format == self.inner.atoms.STRING {
+                        // ISO Latin-1
+                        // See: https://stackoverflow.com/questions/28169745/what-are-the-options-to-convert-iso-8859-1-latin-1-to-a-string-utf-8
+                        result.bytes.into_iter().map(|c| c as char).collect()
+                    } else {
+                        String::from_utf8(result.bytes).map_err(|_| Error::ConversionFailure)?
+                    };
+                    item.entries.push(ClipboardEntry::String(text.into()));
+                }
+            }
+            Err(Error::ContentNotAvailable) => {}
+            Err(err) => return Err(err),
+        }
 
-        for (format_atom, image_format) in image_format_atoms.into_iter().zip(image_formats) {
-            if result.format == format_atom {
-                let bytes = result.bytes;
-                let id = hash(&bytes);
-                return Ok(ClipboardItem::new_image(&Image {
-                    id,
-                    format: image_format,
-                    bytes,
-                }));
+        // Every other format with a mime type is only read once it's requested.
+        let targets = self.inner.targets(selection).unwrap_or_else(|err| {
+            log::trace!("Clipboard TARGETS query failed with {err:?}");
+            Vec::new()
+        });
+        for target in targets {
+            let mime_type = self.inner.atom_name(target);
+            if Some(target) == read_format
+                || !mime_type.contains('/')
+                || mime_type.starts_with("text/plain")
+            {
+                continue;
             }
+            let inner = Arc::clone(&self.inner);
+            item.entries
+                .push(ClipboardEntry::Data(crate::ClipboardData::promised(
+                    mime_type,
+                    move || match inner.read(&[target], selection) {
+                        Ok(data) => data.bytes,
+                        Err(err) => {
+                            log::error!("Failed to read {mime_type} from the clipboard: {err}");
+                            Vec::new()
+                        }
+                    },
+                )));
         }
 
-        let text = if result.format == self.inner.atoms.STRING {
-            // ISO Latin-1
-            // See: https://stackoverflow.com/questions/28169745/what-are-the-options-to-convert-iso-8859-1-latin-1-to-a-string-utf-8
-            result.bytes.into_iter().map(|c| c as char).collect()
-        } else {
-            String::from_utf8(result.bytes).map_err(|_| Error::ConversionFailure)?
-        };
-        Ok(ClipboardItem::new_string(text))
+        if item.entries.is_empty() {
+            return Err(Error::ContentNotAvailable);
+        }
+        Ok(item)
     }
 
     pub fn is_owner(&self, selection: ClipboardKind) -> bool {
@@ -0,0 +1,181 @@
+use anyhow::{Context as _, anyhow};
+use util::ResultExt as _;
+use x11rb::{
+    errors::{ErrorKind, ReplyError},
+    protocol::xproto::{self, ConnectionExt as _, GrabMode, ModMask},
+    xcb_ffi::XCBConnection,
+};
+use xkbcommon::xkb::{self as xkbc, Keysym};
+
+use super::{check_reply, handle_connection_error};
+use crate::{GlobalHotkeyError, Keystroke, Modifiers};
+
+/// A hotkey grabbed on the root window with `XGrabKey`, which makes the X server deliver it to
+/// us regardless of which window is focused.
+pub(crate) struct GrabbedHotkey {
+    pub keycode: xproto::Keycode,
+    pub modifiers: ModMask,
+    pub on_trigger: Option<Box<dyn FnMut()>>,
+}
+
+impl GrabbedHotkey {
+    pub fn grab(
+        connection: &XCBConnection,
+        root: xproto::Window,
+        keymap: &xkbc::Keymap,
+        keystroke: &Keystroke,
+        on_trigger: Box<dyn FnMut()>,
+    ) -> Result<Self, GlobalHotkeyError> {
+        let keysym = keystroke
+            .xkb_keysym()
+            .with_context(|| format!("no keysym for global hotkey {keystroke}"))?;
+        let (keycode, shifted) = keycode_for_keysym(keymap, keysym)
+            .with_context(|| format!("no key in the current layout produces {keystroke}"))?;
+        let keycode = u8::try_from(keycode.raw())
+            .with_context(|| format!("keycode for {keystroke} is out of range"))?;
+
+        let mut modifiers = modifier_mask(&keystroke.modifiers);
+        if shifted {
+            modifiers |= ModMask::SHIFT;
+        }
+
+        // The server only delivers a grabbed key if the modifiers match exactly, so the key is
+        // also grabbed with every combination of Caps Lock and Num Lock.
+        for lock_modifiers in lock_modifier_combinations() {
+            let result = connection
+                .grab_key(
+                    false,
+                    root,
+                    modifiers | lock_modifiers,
+                    keycode,
+                    GrabMode::ASYNC,
+                    GrabMode::ASYNC,
+                )
+                .map_err(handle_connection_error)?
+                .check();
+            match result {
+                Ok(()) => {}
+                Err(ReplyError::X11Error(error)) if error.error_kind == ErrorKind::Access => {
+                    ungrab(connection, root, keycode, modifiers);
+                    return Err(GlobalHotkeyError::Conflict(keystroke.clone()));
+                }
+                Err(error) => {
+                    ungrab(connection, root, keycode, modifiers);
+                    return Err(anyhow!(error)
+                        .context(format!("failed to grab global hotkey {keystroke}"))
+                        .into());
+                }
+            }
+        }
+
+        Ok(Self {
+            keycode,
+            modifiers,
+            on_trigger: Some(on_trigger),
+        })
+    }
+
+    pub fn ungrab(&self, connection: &XCBConnection, root: xproto::Window) {
+        ungrab(connection, root, self.keycode, self.modifiers);
+    }
+
+    /// Whether a key press triggers this hotkey, which the server reports on the root window it
+    /// was grabbed on, with whichever lock modifiers were active.
+    pub fn is_triggered_by(&self, root: xproto::Window, event: &xproto::KeyPressEvent) -> bool {
+        let relevant_modifiers = ModMask::SHIFT | ModMask::CONTROL | ModMask::M1 | ModMask::M4;
+        let modifiers = ModMask::from(u16::from(event.state) & u16::from(relevant_modifiers));
+        event.event == root && self.keycode == event.detail && self.modifiers == modifiers
+    }
+}
+
+fn ungrab(
+    connection: &XCBConnection,
+    root: xproto::Window,
+    keycode: xproto::Keycode,
+    modifiers: ModMask,
+) {
+    for lock_modifiers in lock_modifier_combinations() {
+        check_reply(
+            || "failed to ungrab global hotkey",
+            connection.ungrab_key(keycode, root, modifiers | lock_modifiers),
+        )
+        .log_err();
+    }
+}
+
+fn lock_modifier_combinations() -> [ModMask; 4] {
+    [
+        ModMask::from(0u16),
+        ModMask::LOCK,
+        ModMask::M2,
+        ModMask::LOCK | ModMask::M2,
+    ]
+}
+
+fn modifier_mask(modifiers: &Modifiers) -> ModMask {
+    let mut mask = ModMask::from(0u16);
+    if modifiers.control {
+        mask |= ModMask::CONTROL;
+    }
+    if modifiers.alt {
+        mask |= ModMask::M1;
+    }
+    if modifiers.shift {
+        mask |= ModMask::SHIFT;
+    }
+    if modifiers.platform {
+        mask |= ModMask::M4;
+    }
+    mask
+}
+
+/// Finds the key that produces the given keysym in the first layout, and whether Shift is needed
+/// to produce it.
+fn keycode_for_keysym(keymap: &xkbc::Keymap, keysym: Keysym) -> Option<(xkbc::Keycode, bool)> {
+    let keycodes = keymap.min_keycode().raw()..=keymap.max_keycode().raw();
+    for level in 0..2 {
+        for keycode in keycodes.clone().map(xkbc::Keycode::new) {
+            if keymap
+                .key_get_syms_by_level(keycode, 0, level)
+                .contains(&keysym)
+            {
+                return Some((keycode, level == 1));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11rb::protocol::xproto::KeyButMask;
+
+    #[test]
+    fn test_is_triggered_by() {
+        let root = 1;
+        let hotkey = GrabbedHotkey {
+            keycode: 65,
+            modifiers: ModMask::CONTROL | ModMask::M1,
+            on_trigger: None,
+        };
+        let key_press = |window, keycode, state| xproto::KeyPressEvent {
+            event: window,
+            detail: keycode,
+            state,
+            ..Default::default()
+        };
+
+        let ctrl_alt = KeyButMask::CONTROL | KeyButMask::MOD1;
+        assert!(hotkey.is_triggered_by(root, &key_press(root, 65, ctrl_alt)));
+        // Caps Lock and Num Lock are ignored.
+        let locked = ctrl_alt | KeyButMask::LOCK | KeyButMask::MOD2;
+        assert!(hotkey.is_triggered_by(root, &key_press(root, 65, locked)));
+
+        assert!(!hotkey.is_triggered_by(root, &key_press(2, 65, ctrl_alt)));
+        assert!(!hotkey.is_triggered_by(root, &key_press(root, 66, ctrl_alt)));
+        assert!(!hotkey.is_triggered_by(root, &key_press(root, 65, KeyButMask::CONTROL)));
+        let ctrl_alt_shift = ctrl_alt | KeyButMask::SHIFT;
+        assert!(!hotkey.is_triggered_by(root, &key_press(root, 65, ctrl_alt_shift)));
+    }
+}
@@ -2,12 +2,27 @@
 //!
 //! This module uses the [ashpd] crate
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::pin::pin;
+use std::rc::{Rc, Weak};
+
+use anyhow::Context as _;
+use ashpd::WindowIdentifier;
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts as GlobalShortcutsProxy, NewShortcut};
+use ashpd::desktop::notification::{Button, NotificationProxy};
 use ashpd::desktop::settings::{ColorScheme, Settings};
+use ashpd::desktop::{ResponseError, Session};
 use calloop::channel::Channel;
 use calloop::{EventSource, Poll, PostAction, Readiness, Token, TokenFactory};
+use futures::channel::oneshot;
 use smol::stream::StreamExt;
+use util::ResultExt as _;
 
-use crate::{BackgroundExecutor, WindowAppearance};
+use crate::{
+    BackgroundExecutor, ForegroundExecutor, GlobalHotkeyError, GlobalHotkeyId, Keystroke,
+    Notification, NotificationEvent, NotificationId, WindowAppearance,
+};
 
 pub enum Event {
     WindowAppearance(WindowAppearance),
@@ -155,6 +170,295 @@ impl EventSource for XDPEventSource {
     }
 }
 
+/// The action invoked when the body of a notification is clicked, rather than one of its buttons.
+const DEFAULT_NOTIFICATION_ACTION: &str = "default";
+
+type NotificationHandlers = RefCell<HashMap<String, Box<dyn FnMut(NotificationEvent)>>>;
+
+/// Shows notifications through the notification portal, and reports the actions invoked on them
+/// to the handler they were shown with.
+#[derive(Default)]
+pub struct Notifications {
+    handlers: Rc<NotificationHandlers>,
+    listener: Rc<Cell<ActionListener>>,
+}
+
+/// Whether the portal's `ActionInvoked` signal is subscribed to. It goes back to `Stopped` when
+/// subscribing fails, so that the next notification retries it.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum ActionListener {
+    #[default]
+    Stopped,
+    Subscribing,
+    Listening,
+}
+
+impl Notifications {
+    pub fn show(
+        &mut self,
+        executor: &ForegroundExecutor,
+        id: NotificationId,
+        notification: Notification,
+        on_event: Box<dyn FnMut(NotificationEvent)>,
+    ) -> oneshot::Receiver<anyhow::Result<()>> {
+        let portal_id = portal_notification_id(id);
+        self.handlers
+            .borrow_mut()
+            .insert(portal_id.clone(), on_event);
+
+        if self.listener.get() == ActionListener::Stopped {
+            self.listener.set(ActionListener::Subscribing);
+            let handlers = Rc::downgrade(&self.handlers);
+            let listener = self.listener.clone();
+            executor
+                .spawn(async move {
+                    listen_for_notification_actions(handlers, &listener)
+                        .await
+                        .context("failed to listen for notification actions")
+                        .log_err();
+                    listener.set(ActionListener::Stopped);
+                })
+                .detach();
+        }
+
+        let (done_tx, done_rx) = oneshot::channel();
+        let handlers = Rc::downgrade(&self.handlers);
+        executor
+            .spawn(async move {
+                let result = add_notification(&portal_id, notification)
+                    .await
+                    .context("failed to show notification");
+                if result.is_err()
+                    && let Some(handlers) = handlers.upgrade()
+                {
+                    handlers.borrow_mut().remove(&portal_id);
+                }
+                done_tx.send(result).ok();
+            })
+            .detach();
+        done_rx
+    }
+
+    pub fn remove(&mut self, executor: &ForegroundExecutor, id: NotificationId) {
+        let portal_id = portal_notification_id(id);
+        if self.handlers.borrow_mut().remove(&portal_id).is_none() {
+            return;
+        }
+        executor
+            .spawn(async move {
+                remove_notification(&portal_id)
+                    .await
+                    .context("failed to remove notification")
+                    .log_err();
+            })
+            .detach();
+    }
+}
+
+async fn add_notification(portal_id: &str, notification: Notification) -> anyhow::Result<()> {
+    let proxy = NotificationProxy::new().await?;
+    let mut portal_notification =
+        ashpd::desktop::notification::Notification::new(&notification.title)
+            .body(Some(notification.body.as_ref()))
+            .default_action(DEFAULT_NOTIFICATION_ACTION);
+    for action in &notification.actions {
+        portal_notification = portal_notification.button(Button::new(&action.label, &action.id));
+    }
+    proxy
+        .add_notification(portal_id, portal_notification)
+        .await?;
+    Ok(())
+}
+
+async fn remove_notification(portal_id: &str) -> anyhow::Result<()> {
+    let proxy = NotificationProxy::new().await?;
+    proxy.remove_notification(portal_id).await?;
+    Ok(())
+}
+
+fn portal_notification_id(id: NotificationId) -> String {
+    format!("gpui-notification-{}", id.0.as_u64())
+}
+
+async fn listen_for_notification_actions(
+    handlers: Weak<NotificationHandlers>,
+    listener: &Cell<ActionListener>,
+) -> anyhow::Result<()> {
+    let proxy = NotificationProxy::new().await?;
+    let mut actions = proxy.receive_action_invoked().await?;
+    listener.set(ActionListener::Listening);
+    while let Some(action) = actions.next().await {
+        let Some(handlers) = handlers.upgrade() else {
+            break;
+        };
+        let event = if action.name() == DEFAULT_NOTIFICATION_ACTION {
+            NotificationEvent::Activated
+        } else {
+            NotificationEvent::ActionInvoked(action.name().to_string().into())
+        };
+        // The portal withdraws notifications once they're interacted with, so there's
+        // at most one event for each of them.
+        let handler = handlers.borrow_mut().remove(action.id());
+        if let Some(mut handler) = handler {
+            handler(event);
+        }
+    }
+    Ok(())
+}
+
+/// Binds global shortcuts through the global shortcuts portal. Each shortcut gets a session
+/// of its own, which is closed once the sender stored for it here is dropped.
+#[cfg_attr(not(feature = "wayland"), allow(dead_code))]
+#[derive(Default)]
+pub struct GlobalShortcuts {
+    sessions: HashMap<GlobalHotkeyId, oneshot::Sender<()>>,
+}
+
+#[cfg_attr(not(feature = "wayland"), allow(dead_code))]
+impl GlobalShortcuts {
+    pub fn register(
+        &mut self,
+        executor: &ForegroundExecutor,
+        id: GlobalHotkeyId,
+        keystroke: &Keystroke,
+        window_identifier: impl Future<Output = Option<WindowIdentifier>> + 'static,
+        on_trigger: Box<dyn FnMut()>,
+    ) -> oneshot::Receiver<Result<(), GlobalHotkeyError>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        let (unregister_tx, unregister_rx) = oneshot::channel();
+        self.sessions.insert(id, unregister_tx);
+
+        let shortcut_id = portal_shortcut_id(id);
+        let keystroke = keystroke.clone();
+        executor
+            .spawn(async move {
+                let window_identifier = window_identifier.await;
+                match bind_global_shortcut(&shortcut_id, &keystroke, window_identifier).await {
+                    Ok((portal, session)) => {
+                        done_tx.send(Ok(())).ok();
+                        listen_for_global_shortcut(
+                            &portal,
+                            &shortcut_id,
+                            unregister_rx,
+                            on_trigger,
+                        )
+                        .await
+                        .context("failed to listen for global shortcut")
+                        .log_err();
+                        session
+                            .close()
+                            .await
+                            .context("failed to close global shortcuts session")
+                            .log_err();
+                    }
+                    Err(error) => {
+                        done_tx.send(Err(error)).ok();
+                    }
+                }
+            })
+            .detach();
+        done_rx
+    }
+
+    pub fn unregister(&mut self, id: GlobalHotkeyId) {
+        self.sessions.remove(&id);
+    }
+}
+
+async fn bind_global_shortcut(
+    shortcut_id: &str,
+    keystroke: &Keystroke,
+    window_identifier: Option<WindowIdentifier>,
+) -> Result<
+    (
+        GlobalShortcutsProxy<'static>,
+        Session<'static, GlobalShortcutsProxy<'static>>,
+    ),
+    GlobalHotkeyError,
+> {
+    let portal = GlobalShortcutsProxy::new().await.map_err(|error| {
+        log::warn!("global shortcuts portal is unavailable: {error}");
+        GlobalHotkeyError::Unsupported
+    })?;
+    let session = portal
+        .create_session()
+        .await
+        .context("failed to create global shortcuts session")?;
+
+    let trigger = portal_trigger(keystroke);
+    let shortcut = NewShortcut::new(shortcut_id, keystroke.to_string())
+        .preferred_trigger(Some(trigger.as_str()));
+    let response = portal
+        .bind_shortcuts(&session, &[shortcut], window_identifier.as_ref())
+        .await
+        .and_then(|request| request.response());
+
+    // The portal leaves out shortcuts whose trigger is taken, and lets the user decline to bind
+    // them at all, both of which mean that the hotkey won't fire.
+    let bound = match response {
+        Ok(response) => response
+            .shortcuts()
+            .iter()
+            .any(|shortcut| shortcut.id() == shortcut_id),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => false,
+        Err(error) => {
+            session.close().await.log_err();
+            return Err(anyhow::Error::new(error)
+                .context("failed to bind global shortcut")
+                .into());
+        }
+    };
+    if !bound {
+        session.close().await.log_err();
+        return Err(GlobalHotkeyError::Conflict(keystroke.clone()));
+    }
+    Ok((portal, session))
+}
+
+async fn listen_for_global_shortcut(
+    portal: &GlobalShortcutsProxy<'_>,
+    shortcut_id: &str,
+    unregistered: oneshot::Receiver<()>,
+    mut on_trigger: Box<dyn FnMut()>,
+) -> anyhow::Result<()> {
+    let activations = portal.receive_activated().await?.map(Some);
+    let unregistered = smol::stream::once_future(unregistered).map(|_| None);
+    let mut events = pin!(activations.or(unregistered));
+    while let Some(Some(activation)) = events.next().await {
+        if activation.shortcut_id() == shortcut_id {
+            on_trigger();
+        }
+    }
+    Ok(())
+}
+
+fn portal_shortcut_id(id: GlobalHotkeyId) -> String {
+    format!("gpui-hotkey-{}", id.0)
+}
+
+/// Formats a keystroke as a trigger in the shortcuts format of the XDG specification, such as
+/// `CTRL+SHIFT+a`.
+fn portal_trigger(keystroke: &Keystroke) -> String {
+    let mut trigger = String::new();
+    let modifiers = &keystroke.modifiers;
+    for (pressed, name) in [
+        (modifiers.control, "CTRL"),
+        (modifiers.alt, "ALT"),
+        (modifiers.shift, "SHIFT"),
+        (modifiers.platform, "LOGO"),
+    ] {
+        if pressed {
+            trigger.push_str(name);
+            trigger.push('+');
+        }
+    }
+    match keystroke.xkb_keysym() {
+        Some(keysym) => trigger.push_str(&xkbcommon::xkb::keysym_get_name(keysym)),
+        None => trigger.push_str(&keystroke.key),
+    }
+    trigger
+}
+
 impl WindowAppearance {
     fn from_native(cs: ColorScheme) -> WindowAppearance {
         match cs {
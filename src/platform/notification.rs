@@ -0,0 +1,68 @@
+use crate::{App, EntityId, EventEmitter, SharedString};
+
+/// A notification shown by the desktop, outside of the application's windows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    /// The title of the notification
+    pub title: SharedString,
+
+    /// The text shown below the title
+    pub body: SharedString,
+
+    /// The buttons shown on the notification
+    pub actions: Vec<NotificationAction>,
+}
+
+/// A button shown on a [`Notification`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotificationAction {
+    /// Identifies this action in [`NotificationEvent::ActionInvoked`]
+    pub id: SharedString,
+
+    /// The label of the button
+    pub label: SharedString,
+}
+
+impl NotificationAction {
+    /// Creates a new notification action with the given id and label
+    pub fn new(id: impl Into<SharedString>, label: impl Into<SharedString>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+        }
+    }
+}
+
+/// Emitted by a [`NotificationHandle`] when the user interacts with its notification
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotificationEvent {
+    /// The notification itself was clicked
+    Activated,
+
+    /// One of the notification's actions was clicked, identified by its id
+    ActionInvoked(SharedString),
+}
+
+/// Identifies a notification shown with [`App::show_notification`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NotificationId(pub(crate) EntityId);
+
+/// A notification shown with [`App::show_notification`], which emits a [`NotificationEvent`]
+/// whenever the user interacts with it
+pub struct NotificationHandle {
+    pub(crate) id: NotificationId,
+}
+
+impl NotificationHandle {
+    /// The id of this notification
+    pub fn id(&self) -> NotificationId {
+        self.id
+    }
+
+    /// Withdraws the notification, if it is still shown
+    pub fn dismiss(&self, cx: &mut App) {
+        cx.platform.remove_notification(self.id);
+    }
+}
+
+impl EventEmitter<NotificationEvent> for NotificationHandle {}
@@ -0,0 +1,18 @@
+use crate::{Image, Menu, SharedString};
+
+/// An icon in the system tray, also known as the status area or notification area,
+/// which shows a menu of actions when clicked
+pub struct StatusItem {
+    /// The icon to show in the tray
+    pub icon: Image,
+
+    /// The text shown when hovering over the icon
+    pub tooltip: SharedString,
+
+    /// The menu shown when the icon is clicked
+    pub menu: Menu,
+}
+
+/// Identifies a [`StatusItem`] added with [`crate::App::add_status_item`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StatusItemId(pub(crate) usize);
@@ -1,6 +1,7 @@
 use crate::{
     AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, DevicePixels,
-    DummyKeyboardMapper, ForegroundExecutor, Keymap, NoopTextSystem, Platform, PlatformDisplay,
+    DummyKeyboardMapper, ForegroundExecutor, GlobalHotkeyError, GlobalHotkeyId, Keymap, Keystroke,
+    NoopTextSystem, Notification, NotificationEvent, NotificationId, Platform, PlatformDisplay,
     PlatformKeyboardLayout, PlatformKeyboardMapper, PlatformTextSystem, PromptButton,
     ScreenCaptureFrame, ScreenCaptureSource, ScreenCaptureStream, SourceMetadata, Task,
     TestDisplay, TestWindow, WindowAppearance, WindowParams, size,
@@ -33,6 +34,8 @@ pub(crate) struct TestPlatform {
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     current_primary_item: Mutex<Option<ClipboardItem>>,
     pub(crate) prompts: RefCell<TestPrompts>,
+    notifications: RefCell<Vec<TestNotification>>,
+    global_hotkeys: RefCell<Vec<TestGlobalHotkey>>,
     screen_capture_sources: RefCell<Vec<TestScreenCaptureSource>>,
     pub opened_url: RefCell<Option<String>>,
     pub text_system: Arc<dyn PlatformTextSystem>,
@@ -85,6 +88,18 @@ struct TestPrompt {
     tx: oneshot::Sender<usize>,
 }
 
+struct TestNotification {
+    id: NotificationId,
+    notification: Notification,
+    on_event: Option<Box<dyn FnMut(NotificationEvent)>>,
+}
+
+struct TestGlobalHotkey {
+    id: GlobalHotkeyId,
+    keystroke: Keystroke,
+    on_trigger: Option<Box<dyn FnMut()>>,
+}
+
 #[derive(Default)]
 pub(crate) struct TestPrompts {
     multiple_choice: VecDeque<TestPrompt>,
@@ -109,6 +124,8 @@ impl TestPlatform {
             background_executor: executor,
             foreground_executor,
             prompts: Default::default(),
+            notifications: Default::default(),
+            global_hotkeys: Default::default(),
             screen_capture_sources: Default::default(),
             active_cursor: Default::default(),
             active_display: Rc::new(TestDisplay::new()),
@@ -221,6 +238,54 @@ impl TestPlatform {
     pub(crate) fn did_prompt_for_new_path(&self) -> bool {
         !self.prompts.borrow().new_path.is_empty()
     }
+
+    pub(crate) fn notifications(&self) -> Vec<(NotificationId, Notification)> {
+        self.notifications
+            .borrow()
+            .iter()
+            .map(|shown| (shown.id, shown.notification.clone()))
+            .collect()
+    }
+
+    #[track_caller]
+    pub(crate) fn simulate_notification_event(&self, id: NotificationId, event: NotificationEvent) {
+        let mut on_event = self
+            .notifications
+            .borrow_mut()
+            .iter_mut()
+            .find(|shown| shown.id == id)
+            .and_then(|shown| shown.on_event.take())
+            .expect("no such notification is shown");
+        on_event(event);
+        if let Some(shown) = self
+            .notifications
+            .borrow_mut()
+            .iter_mut()
+            .find(|shown| shown.id == id)
+        {
+            shown.on_event = Some(on_event);
+        }
+    }
+
+    #[track_caller]
+    pub(crate) fn simulate_global_hotkey(&self, keystroke: &Keystroke) {
+        let (id, mut on_trigger) = self
+            .global_hotkeys
+            .borrow_mut()
+            .iter_mut()
+            .find(|hotkey| &hotkey.keystroke == keystroke)
+            .and_then(|hotkey| Some((hotkey.id, hotkey.on_trigger.take()?)))
+            .expect("no such global hotkey is registered");
+        on_trigger();
+        if let Some(hotkey) = self
+            .global_hotkeys
+            .borrow_mut()
+            .iter_mut()
+            .find(|hotkey| hotkey.id == id)
+        {
+            hotkey.on_trigger = Some(on_trigger);
+        }
+    }
 }
 
 impl Platform for TestPlatform {
@@ -376,6 +441,59 @@ impl Platform for TestPlatform {
 
     fn add_recent_document(&self, _paths: &Path) {}
 
+    fn show_notification(
+        &self,
+        id: NotificationId,
+        notification: Notification,
+        on_event: Box<dyn FnMut(NotificationEvent)>,
+    ) -> oneshot::Receiver<Result<()>> {
+        self.notifications.borrow_mut().push(TestNotification {
+            id,
+            notification,
+            on_event: Some(on_event),
+        });
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx.send(Ok(())).ok();
+        done_rx
+    }
+
+    fn remove_notification(&self, id: NotificationId) {
+        self.notifications
+            .borrow_mut()
+            .retain(|shown| shown.id != id);
+    }
+
+    fn register_global_hotkey(
+        &self,
+        id: GlobalHotkeyId,
+        keystroke: &Keystroke,
+        on_trigger: Box<dyn FnMut()>,
+    ) -> oneshot::Receiver<Result<(), GlobalHotkeyError>> {
+        let mut global_hotkeys = self.global_hotkeys.borrow_mut();
+        let result = if global_hotkeys
+            .iter()
+            .any(|hotkey| &hotkey.keystroke == keystroke)
+        {
+            Err(GlobalHotkeyError::Conflict(keystroke.clone()))
+        } else {
+            global_hotkeys.push(TestGlobalHotkey {
+                id,
+                keystroke: keystroke.clone(),
+                on_trigger: Some(on_trigger),
+            });
+            Ok(())
+        };
+        let (done_tx, done_rx) = oneshot::channel();
+        done_tx.send(result).ok();
+        done_rx
+    }
+
+    fn unregister_global_hotkey(&self, id: GlobalHotkeyId) {
+        self.global_hotkeys
+            .borrow_mut()
+            .retain(|hotkey| hotkey.id != id);
+    }
+
     fn on_app_menu_action(&self, _callback: Box<dyn FnMut(&dyn crate::Action)>) {}
 
     fn on_will_open_app_menu(&self, _callback: Box<dyn FnMut()>) {}
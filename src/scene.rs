@@ -1,6 +1,7 @@
 // todo("windows"): remove
 #![cfg_attr(windows, allow(dead_code))]
 
+use collections::FxHashMap;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -723,6 +724,58 @@ impl Path<Pixels> {
         }
     }
 
+    /// Transform this path and scale it by the given factor, for painting a copy of a cached
+    /// tessellation.
+    pub(crate) fn transform_and_scale(
+        &self,
+        transform: &lyon::math::Transform,
+        factor: f32,
+    ) -> Path<ScaledPixels> {
+        if *transform == lyon::math::Transform::identity() {
+            return self.scale(factor);
+        }
+
+        let transform_point = |point: Point<Pixels>| {
+            let point = transform.transform_point(lyon::math::point(point.x.0, point.y.0));
+            Point::new(Pixels(point.x), Pixels(point.y))
+        };
+        let vertices = self
+            .vertices
+            .iter()
+            .map(|vertex| {
+                PathVertex {
+                    xy_position: transform_point(vertex.xy_position),
+                    ..vertex.clone()
+                }
+                .scale(factor)
+            })
+            .collect::<Vec<_>>();
+        let start = transform_point(self.start);
+        let bounds = vertices.iter().fold(
+            Bounds {
+                origin: start.scale(factor),
+                size: Default::default(),
+            },
+            |bounds, vertex| {
+                bounds.union(&Bounds {
+                    origin: vertex.xy_position,
+                    size: Default::default(),
+                })
+            },
+        );
+        Path {
+            id: self.id,
+            order: self.order,
+            bounds,
+            content_mask: self.content_mask.scale(factor),
+            vertices,
+            start: start.scale(factor),
+            current: transform_point(self.current).scale(factor),
+            contour_count: self.contour_count,
+            color: self.color,
+        }
+    }
+
     /// Move the start, current point to the given point.
     pub fn move_to(&mut self, to: Point<Pixels>) {
         self.contour_count += 1;
@@ -764,6 +817,22 @@ impl Path<Pixels> {
         &mut self,
         xy: (Point<Pixels>, Point<Pixels>, Point<Pixels>),
         st: (Point<f32>, Point<f32>, Point<f32>),
+    ) {
+        self.push_outlined_triangle(xy, st, [false; 3]);
+    }
+
+    /// Push a triangle to the Path, along with which of its edges are on the outline of the path.
+    /// Each edge is given by the vertex opposite it, so `outline[0]` is the edge from `xy.1` to
+    /// `xy.2`.
+    ///
+    /// When the renderer doesn't multisample paths, outline edges are anti-aliased by fading
+    /// them out over a pixel as the path is drawn. Edges shared with other triangles of the path
+    /// must not be on the outline, or the seams will show.
+    pub fn push_outlined_triangle(
+        &mut self,
+        xy: (Point<Pixels>, Point<Pixels>, Point<Pixels>),
+        st: (Point<f32>, Point<f32>, Point<f32>),
+        outline: [bool; 3],
     ) {
         self.bounds = self
             .bounds
@@ -783,16 +852,19 @@ impl Path<Pixels> {
         self.vertices.push(PathVertex {
             xy_position: xy.0,
             st_position: st.0,
+            edge_distances: edge_distances(&outline, 0),
             content_mask: Default::default(),
         });
         self.vertices.push(PathVertex {
             xy_position: xy.1,
             st_position: st.1,
+            edge_distances: edge_distances(&outline, 1),
             content_mask: Default::default(),
         });
         self.vertices.push(PathVertex {
             xy_position: xy.2,
             st_position: st.2,
+            edge_distances: edge_distances(&outline, 2),
             content_mask: Default::default(),
         });
     }
@@ -808,17 +880,121 @@ where
     }
 }
 
+impl Path<ScaledPixels> {
+    /// Returns this path with the outline edges of its triangles pushed out by the given
+    /// distance, for renderers that anti-alias paths by fading out their outline edges rather
+    /// than by multisampling. Pushed out by half a pixel, an edge faded out over the pixel
+    /// inside it is faded around its true position, and the pixels it only partly covers from
+    /// outside are drawn too.
+    ///
+    /// Each vertex on the outline is moved along the miter of the outline edges that meet at its
+    /// position, so that the triangles sharing it stay joined.
+    #[cfg_attr(
+        not(any(
+            all(
+                any(target_os = "linux", target_os = "freebsd"),
+                any(feature = "x11", feature = "wayland")
+            ),
+            all(target_os = "macos", feature = "macos-blade")
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn outset_outline(&self, distance: ScaledPixels) -> Self {
+        // Sharp corners are pushed out by at most this many times the distance.
+        const MITER_LIMIT: f32 = 4.;
+
+        let key = |position: Point<ScaledPixels>| (position.x.0.to_bits(), position.y.0.to_bits());
+        let mut normals = FxHashMap::<_, Vec<Point<f32>>>::default();
+        for triangle in self.vertices.chunks_exact(3) {
+            for edge in 0..3 {
+                let a = triangle[(edge + 1) % 3].xy_position;
+                let b = triangle[(edge + 2) % 3].xy_position;
+                let opposite = triangle[edge].xy_position;
+                if triangle[(edge + 1) % 3].edge_distances[edge] != 0. {
+                    continue;
+                }
+                let length = (b.x.0 - a.x.0).hypot(b.y.0 - a.y.0);
+                if length == 0. {
+                    continue;
+                }
+                let mut normal = point((b.y.0 - a.y.0) / length, (a.x.0 - b.x.0) / length);
+                if normal.x * (opposite.x.0 - a.x.0) + normal.y * (opposite.y.0 - a.y.0) > 0. {
+                    normal = point(-normal.x, -normal.y);
+                }
+                for position in [a, b] {
+                    let normals = normals.entry(key(position)).or_default();
+                    if !normals.contains(&normal) {
+                        normals.push(normal);
+                    }
+                }
+            }
+        }
+
+        // The miter points the same way as the normals on average, and is long enough that the
+        // vertex moves by at least the distance across each of its edges.
+        let offsets = normals
+            .into_iter()
+            .filter_map(|(key, normals)| {
+                let sum = normals
+                    .iter()
+                    .fold(point(0., 0.), |sum, normal| sum + *normal);
+                let length = sum.x.hypot(sum.y);
+                if length < 1e-6 {
+                    return None;
+                }
+                let direction = point(sum.x / length, sum.y / length);
+                let projection = normals
+                    .iter()
+                    .map(|normal| normal.x * direction.x + normal.y * direction.y)
+                    .fold(f32::INFINITY, f32::min)
+                    .max(1. / MITER_LIMIT);
+                let scale = distance.0 / projection;
+                Some((key, point(direction.x * scale, direction.y * scale)))
+            })
+            .collect::<FxHashMap<_, _>>();
+
+        let mut path = self.clone();
+        for vertex in &mut path.vertices {
+            if let Some(offset) = offsets.get(&key(vertex.xy_position)) {
+                vertex.xy_position.x.0 += offset.x;
+                vertex.xy_position.y.0 += offset.y;
+                path.bounds = path.bounds.union(&Bounds {
+                    origin: vertex.xy_position,
+                    size: Default::default(),
+                });
+            }
+        }
+        path
+    }
+}
+
 impl From<Path<ScaledPixels>> for Primitive {
     fn from(path: Path<ScaledPixels>) -> Self {
         Primitive::Path(path)
     }
 }
 
+/// Returns the barycentric distances of the given vertex of a triangle from the edges opposite
+/// each vertex. Edges that aren't on the outline are 1 at every vertex, so they're never faded.
+fn edge_distances(outline: &[bool; 3], vertex: usize) -> [f32; 3] {
+    let mut distances = [1.; 3];
+    for (edge, distance) in distances.iter_mut().enumerate() {
+        if outline[edge] && edge != vertex {
+            *distance = 0.;
+        }
+    }
+    distances
+}
+
 #[derive(Clone, Debug)]
 #[repr(C)]
 pub(crate) struct PathVertex<P: Clone + Debug + Default + PartialEq> {
     pub(crate) xy_position: Point<P>,
     pub(crate) st_position: Point<f32>,
+    /// How far the vertex is from each outline edge of its triangle, as a fraction of the
+    /// triangle's height. Renderers divide the interpolated distances by their screen-space
+    /// derivatives to get the distance in device pixels, and use it as the coverage of the edge.
+    pub(crate) edge_distances: [f32; 3],
     pub(crate) content_mask: ContentMask<P>,
 }
 
@@ -827,6 +1003,7 @@ impl PathVertex<Pixels> {
         PathVertex {
             xy_position: self.xy_position.scale(factor),
             st_position: self.st_position,
+            edge_distances: self.edge_distances,
             content_mask: self.content_mask.scale(factor),
         }
     }
@@ -1,10 +1,11 @@
 use crate::{
-    AssetSource, DevicePixels, IsZero, RenderImage, Result, SharedString, Size,
+    AssetSource, DevicePixels, Hsla, IsZero, RenderImage, Result, Rgba, SharedString, Size,
     swap_rgba_pa_to_bgra,
 };
 use image::Frame;
 use resvg::tiny_skia::Pixmap;
 use std::{
+    borrow::Cow,
     hash::Hash,
     sync::{Arc, LazyLock},
 };
@@ -16,6 +17,51 @@ pub const SMOOTH_SVG_SCALE_FACTOR: f32 = 2.;
 pub(crate) struct RenderSvgParams {
     pub(crate) path: SharedString,
     pub(crate) size: Size<DevicePixels>,
+    /// How many times the asset has been reloaded, so that reloaded SVGs are rendered again.
+    pub(crate) version: usize,
+    /// Whether the SVG is rendered with its own colors, rather than as an alpha mask.
+    pub(crate) full_color: bool,
+    /// The style sheet to apply to the SVG, including the rule for `currentColor` when it's
+    /// rendered in full color.
+    pub(crate) style_sheet: Option<SharedString>,
+    pub(crate) variables: Vec<(SharedString, SharedString)>,
+}
+
+/// How to render an SVG, see [`crate::Window::paint_styled_svg`].
+#[derive(Clone, Debug, Default)]
+pub struct SvgStyle {
+    /// The color that monochrome SVGs are tinted with, which is used for `currentColor` in
+    /// full-color SVGs
+    pub color: Hsla,
+    /// Whether to render the SVG with its own colors, rather than as a mask tinted with `color`
+    pub full_color: bool,
+    /// A CSS style sheet to apply to the SVG, after its own styles
+    pub style_sheet: Option<SharedString>,
+    /// Values for the CSS custom properties, such as `--accent`, that the SVG and its style sheet
+    /// use with `var()`
+    pub variables: Vec<(SharedString, SharedString)>,
+}
+
+impl SvgStyle {
+    /// The style sheet to render the SVG with, which sets `currentColor` for full-color SVGs.
+    pub(crate) fn render_style_sheet(&self) -> Option<SharedString> {
+        if !self.full_color {
+            return self.style_sheet.clone();
+        }
+        let color = Rgba::from(self.color);
+        let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+        let color_rule = format!(
+            "svg {{ color: rgba({}, {}, {}, {}) }}",
+            channel(color.r),
+            channel(color.g),
+            channel(color.b),
+            color.a.clamp(0., 1.)
+        );
+        Some(match &self.style_sheet {
+            Some(style_sheet) => format!("{color_rule}\n{style_sheet}").into(),
+            None => color_rule.into(),
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -36,30 +82,9 @@ pub enum SvgSize {
 impl SvgRenderer {
     /// Creates a new SVG renderer with the provided asset source.
     pub fn new(asset_source: Arc<dyn AssetSource>) -> Self {
-        static FONT_DB: LazyLock<Arc<usvg::fontdb::Database>> = LazyLock::new(|| {
-            let mut db = usvg::fontdb::Database::new();
-            db.load_system_fonts();
-            Arc::new(db)
-        });
-        let default_font_resolver = usvg::FontResolver::default_font_selector();
-        let font_resolver = Box::new(
-            move |font: &usvg::Font, db: &mut Arc<usvg::fontdb::Database>| {
-                if db.is_empty() {
-                    *db = FONT_DB.clone();
-                }
-                default_font_resolver(font, db)
-            },
-        );
-        let options = usvg::Options {
-            font_resolver: usvg::FontResolver {
-                select_font: font_resolver,
-                select_fallback: usvg::FontResolver::default_fallback_selector(),
-            },
-            ..Default::default()
-        };
         Self {
             asset_source,
-            usvg_options: Arc::new(options),
+            usvg_options: Arc::new(usvg_options()),
         }
     }
 
@@ -91,7 +116,8 @@ impl SvgRenderer {
         })
     }
 
-    pub(crate) fn render_alpha_mask(
+    /// Renders an SVG for the sprite atlas, as an alpha mask or, for full-color SVGs, in BGRA.
+    pub(crate) fn render_sprite(
         &self,
         params: &RenderSvgParams,
         bytes: Option<&[u8]>,
@@ -99,13 +125,22 @@ impl SvgRenderer {
         anyhow::ensure!(!params.size.is_zero(), "can't render at a zero size");
 
         let render_pixmap = |bytes| {
-            let pixmap = self.render_pixmap(bytes, SvgSize::Size(params.size))?;
-
-            // Convert the pixmap's pixels into an alpha mask.
+            let tree = self.parse(bytes, params.style_sheet.as_deref(), &params.variables)?;
+            let pixmap = render_tree(&tree, SvgSize::Size(params.size))?;
             let size = Size::new(
                 DevicePixels(pixmap.width() as i32),
                 DevicePixels(pixmap.height() as i32),
             );
+
+            if params.full_color {
+                let mut pixels = pixmap.take();
+                for pixel in pixels.chunks_exact_mut(4) {
+                    swap_rgba_pa_to_bgra(pixel);
+                }
+                return Ok(Some((size, pixels)));
+            }
+
+            // Convert the pixmap's pixels into an alpha mask.
             let alpha_mask = pixmap
                 .pixels()
                 .iter()
@@ -124,25 +159,193 @@ impl SvgRenderer {
         }
     }
 
+    fn parse(
+        &self,
+        bytes: &[u8],
+        style_sheet: Option<&str>,
+        variables: &[(SharedString, SharedString)],
+    ) -> Result<usvg::Tree, usvg::Error> {
+        let bytes = match std::str::from_utf8(bytes) {
+            Ok(source) => match resolve_css_variables(source, variables) {
+                Cow::Borrowed(_) => Cow::Borrowed(bytes),
+                Cow::Owned(source) => Cow::Owned(source.into_bytes()),
+            },
+            // Compressed SVGs aren't text, and are parsed as they are.
+            Err(_) => Cow::Borrowed(bytes),
+        };
+        if let Some(style_sheet) = style_sheet {
+            let options = usvg::Options {
+                style_sheet: Some(resolve_css_variables(style_sheet, variables).into_owned()),
+                ..usvg_options()
+            };
+            usvg::Tree::from_data(&bytes, &options)
+        } else {
+            usvg::Tree::from_data(&bytes, &self.usvg_options)
+        }
+    }
+
     fn render_pixmap(&self, bytes: &[u8], size: SvgSize) -> Result<Pixmap, usvg::Error> {
         let tree = usvg::Tree::from_data(bytes, &self.usvg_options)?;
-        let svg_size = tree.size();
-        let scale = match size {
-            SvgSize::Size(size) => size.width.0 as f32 / svg_size.width(),
-            SvgSize::ScaleFactor(scale) => scale,
+        render_tree(&tree, size)
+    }
+}
+
+fn usvg_options() -> usvg::Options<'static> {
+    static FONT_DB: LazyLock<Arc<usvg::fontdb::Database>> = LazyLock::new(|| {
+        let mut db = usvg::fontdb::Database::new();
+        db.load_system_fonts();
+        Arc::new(db)
+    });
+    let default_font_resolver = usvg::FontResolver::default_font_selector();
+    let font_resolver = Box::new(
+        move |font: &usvg::Font, db: &mut Arc<usvg::fontdb::Database>| {
+            if db.is_empty() {
+                *db = FONT_DB.clone();
+            }
+            default_font_resolver(font, db)
+        },
+    );
+    usvg::Options {
+        font_resolver: usvg::FontResolver {
+            select_font: font_resolver,
+            select_fallback: usvg::FontResolver::default_fallback_selector(),
+        },
+        ..Default::default()
+    }
+}
+
+fn render_tree(tree: &usvg::Tree, size: SvgSize) -> Result<Pixmap, usvg::Error> {
+    let svg_size = tree.size();
+    let scale = match size {
+        SvgSize::Size(size) => size.width.0 as f32 / svg_size.width(),
+        SvgSize::ScaleFactor(scale) => scale,
+    };
+
+    // Render the SVG to a pixmap with the specified width and height.
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(
+        (svg_size.width() * scale) as u32,
+        (svg_size.height() * scale) as u32,
+    )
+    .ok_or(usvg::Error::InvalidSize)?;
+
+    let transform = resvg::tiny_skia::Transform::from_scale(scale, scale);
+
+    resvg::render(tree, transform, &mut pixmap.as_mut());
+
+    Ok(pixmap)
+}
+
+/// Replaces each `var(--name)` in the source with the value of the variable, since usvg doesn't
+/// support custom properties, or with its fallback, as in `var(--name, black)`, if it has no
+/// value. Uses of variables without a value or a fallback are left as they are.
+fn resolve_css_variables<'a>(
+    source: &'a str,
+    variables: &[(SharedString, SharedString)],
+) -> Cow<'a, str> {
+    if !source.contains("var(") {
+        return Cow::Borrowed(source);
+    }
+
+    let mut resolved = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("var(") {
+        resolved.push_str(&rest[..start]);
+        let arguments_start = start + "var(".len();
+        let mut depth = 1;
+        let end = rest[arguments_start..].find(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        });
+        let Some(end) = end.map(|end| arguments_start + end) else {
+            break;
         };
 
-        // Render the SVG to a pixmap with the specified width and height.
-        let mut pixmap = resvg::tiny_skia::Pixmap::new(
-            (svg_size.width() * scale) as u32,
-            (svg_size.height() * scale) as u32,
-        )
-        .ok_or(usvg::Error::InvalidSize)?;
+        let arguments = &rest[arguments_start..end];
+        let (name, fallback) = match arguments.split_once(',') {
+            Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+            None => (arguments.trim(), None),
+        };
+        if let Some((_, value)) = variables.iter().find(|(variable, _)| variable == name) {
+            resolved.push_str(value);
+        } else if let Some(fallback) = fallback {
+            resolved.push_str(&resolve_css_variables(fallback, variables));
+        } else {
+            resolved.push_str(&rest[start..=end]);
+        }
+        rest = &rest[end + 1..];
+    }
+    resolved.push_str(rest);
+    Cow::Owned(resolved)
+}
 
-        let transform = resvg::tiny_skia::Transform::from_scale(scale, scale);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        resvg::render(&tree, transform, &mut pixmap.as_mut());
+    #[test]
+    fn test_resolve_css_variables() {
+        let variables = [("--accent".into(), "#ff0000".into())];
+        assert!(matches!(
+            resolve_css_variables("fill: red", &variables),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            resolve_css_variables(
+                "fill: var(--accent); stroke: var(--outline, rgb(0, 0, 0)); color: var(--other)",
+                &variables
+            ),
+            "fill: #ff0000; stroke: rgb(0, 0, 0); color: var(--other)"
+        );
+        assert_eq!(
+            resolve_css_variables("fill: var(--missing, var(--accent))", &variables),
+            "fill: #ff0000"
+        );
+        assert_eq!(
+            resolve_css_variables("fill: var(--accent", &variables),
+            "fill: var(--accent"
+        );
+    }
 
-        Ok(pixmap)
+    #[test]
+    fn test_render_full_color_sprite() {
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="2" height="1">
+            <rect class="primary" x="0" width="1" height="1" fill="currentColor"/>
+            <rect class="secondary" x="1" width="1" height="1" fill="var(--secondary, blue)"/>
+        </svg>"#;
+        let renderer = SvgRenderer::new(Arc::new(()));
+        let style = SvgStyle {
+            color: crate::red(),
+            full_color: true,
+            style_sheet: Some(".secondary { opacity: 0.5 }".into()),
+            variables: vec![("--secondary".into(), "#00ff00".into())],
+        };
+        let params = RenderSvgParams {
+            path: "icon.svg".into(),
+            size: Size::new(DevicePixels(2), DevicePixels(1)),
+            version: 0,
+            full_color: true,
+            style_sheet: style.render_style_sheet(),
+            variables: style.variables.clone(),
+        };
+        let (size, pixels) = renderer.render_sprite(&params, Some(svg)).unwrap().unwrap();
+        assert_eq!(size, Size::new(DevicePixels(2), DevicePixels(1)));
+        // Pixels are in BGRA, with the first taking `currentColor` from the style and the second
+        // taking its color from the variable and its opacity from the style sheet.
+        assert_eq!(pixels[..4], [0, 0, 255, 255]);
+        assert_eq!(pixels[4], 0);
+        assert!(pixels[5] >= 254, "{pixels:?}");
+        assert!(pixels[7].abs_diff(128) <= 1, "{pixels:?}");
+
+        let params = RenderSvgParams {
+            full_color: false,
+            ..params
+        };
+        let (_, alpha_mask) = renderer.render_sprite(&params, Some(svg)).unwrap().unwrap();
+        assert_eq!(alpha_mask[0], 255);
+        assert!(alpha_mask[1].abs_diff(128) <= 1);
     }
 }
@@ -1,23 +1,23 @@
 #[cfg(any(feature = "inspector", debug_assertions))]
 use crate::Inspector;
 use crate::{
-    Action, AnyDrag, AnyElement, AnyImageCache, AnyTooltip, AnyView, App, AppContext, Arena, Asset,
-    AsyncWindowContext, AvailableSpace, Background, BorderStyle, Bounds, BoxShadow, Capslock,
-    Context, Corners, CursorStyle, Decorations, DevicePixels, DispatchActionListener,
-    DispatchNodeId, DispatchTree, DisplayId, Edges, Effect, Entity, EntityId, EventEmitter,
-    FileDropEvent, FontId, Global, GlobalElementId, GlyphId, GpuSpecs, Hsla, InputHandler, IsZero,
-    KeyBinding, KeyContext, KeyDownEvent, KeyEvent, Keystroke, KeystrokeEvent, LayoutId,
-    LineLayoutIndex, Modifiers, ModifiersChangedEvent, MonochromeSprite, MouseButton, MouseEvent,
-    MouseMoveEvent, MouseUpEvent, Path, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
-    PlatformInputHandler, PlatformWindow, Point, PolychromeSprite, Priority, PromptButton,
-    PromptLevel, Quad, Render, RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams,
-    Replay, ResizeEdge, SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS_X, SUBPIXEL_VARIANTS_Y,
-    ScaledPixels, Scene, Shadow, SharedString, Size, StrikethroughStyle, Style, SubscriberSet,
-    Subscription, SystemWindowTab, SystemWindowTabController, TabStopMap, TaffyLayoutEngine, Task,
-    TextStyle, TextStyleRefinement, TransformationMatrix, Underline, UnderlineStyle,
-    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowControls, WindowDecorations,
-    WindowOptions, WindowParams, WindowTextSystem, point, prelude::*, px, rems, size,
-    transparent_black,
+    Action, ActionBuildError, AnyDrag, AnyElement, AnyImageCache, AnyTooltip, AnyView, App,
+    AppContext, Arena, Asset, AsyncWindowContext, AvailableSpace, Background, BorderStyle, Bounds,
+    BoxShadow, CachedPath, Capslock, Context, Corners, CursorStyle, Decorations, DevicePixels,
+    DispatchActionListener, DispatchNodeId, DispatchTree, DisplayId, DropAction, Edges, Effect,
+    Entity, EntityId, EventEmitter, ExternalDrag, FileDropEvent, FontId, Global, GlobalElementId,
+    GlyphId, GpuSpecs, Hsla, InputHandler, IsZero, KeyBinding, KeyContext, KeyDownEvent, KeyEvent,
+    Keystroke, KeystrokeEvent, LayoutId, LineLayoutIndex, Modifiers, ModifiersChangedEvent,
+    MonochromeSprite, MouseButton, MouseEvent, MouseMoveEvent, MouseUpEvent, Path, PathCache,
+    Pixels, PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow,
+    Point, PolychromeSprite, Priority, PromptButton, PromptLevel, Quad, Render, RenderGlyphParams,
+    RenderImage, RenderImageParams, RenderSvgParams, Replay, ResizeEdge, SMOOTH_SVG_SCALE_FACTOR,
+    SUBPIXEL_VARIANTS_X, SUBPIXEL_VARIANTS_Y, ScaledPixels, Scene, Shadow, SharedString, Size,
+    StrikethroughStyle, Style, SubscriberSet, Subscription, SvgStyle, SystemWindowTab,
+    SystemWindowTabController, TabStopMap, TaffyLayoutEngine, Task, TextStyle, TextStyleRefinement,
+    Transform, TransformationMatrix, Underline, UnderlineStyle, WindowAppearance,
+    WindowBackgroundAppearance, WindowBounds, WindowControls, WindowDecorations, WindowOptions,
+    WindowParams, WindowTextSystem, point, prelude::*, px, rems, size, transparent_black,
 };
 use anyhow::{Context as _, Result, anyhow};
 use collections::{FxHashMap, FxHashSet};
@@ -60,9 +60,15 @@ use crate::util::post_inc;
 use crate::util::{ResultExt, measure};
 use uuid::Uuid;
 
+mod action_macro;
+mod commands;
+mod key_modes;
 mod prompts;
 
 use crate::util::atomic_incr_if_not_zero;
+pub use action_macro::*;
+pub use commands::*;
+pub use key_modes::*;
 pub use prompts::*;
 
 pub(crate) const DEFAULT_WINDOW_SIZE: Size<Pixels> = size(px(1536.), px(864.));
@@ -193,6 +199,9 @@ impl WindowInvalidator {
 
 type AnyObserver = Box<dyn FnMut(&mut Window, &mut App) -> bool + 'static>;
 
+pub(crate) type AnyPendingInputListener =
+    Box<dyn FnMut(&PendingInputEvent, &mut Window, &mut App) -> bool + 'static>;
+
 pub(crate) type AnyWindowFocusListener =
     Box<dyn FnMut(&WindowFocusEvent, &mut Window, &mut App) -> bool + 'static>;
 
@@ -384,7 +393,7 @@ impl FocusHandle {
             .dispatch_tree
             .focusable_node_id(self.id)
         {
-            window.dispatch_action_on_node(node_id, action, cx)
+            window.dispatch_user_action(node_id, action, None, cx)
         }
     }
 }
@@ -860,6 +869,9 @@ pub struct Window {
     pub(crate) element_opacity: f32,
     pub(crate) content_mask_stack: Vec<ContentMask<Pixels>>,
     pub(crate) requested_autoscroll: Option<Bounds<Pixels>>,
+    /// The number of frames that have started drawing, including the one being drawn.
+    frame_number: u64,
+    path_cache: PathCache,
     pub(crate) image_cache_stack: Vec<AnyImageCache>,
     pub(crate) rendered_frame: Frame,
     pub(crate) next_frame: Frame,
@@ -895,6 +907,11 @@ pub struct Window {
     pending_input: Option<PendingInput>,
     pending_modifier: ModifierState,
     pub(crate) pending_input_observers: SubscriberSet<(), AnyObserver>,
+    pub(crate) pending_input_listeners: SubscriberSet<(), AnyPendingInputListener>,
+    pending_input_reported: bool,
+    macro_recorder: Option<MacroRecorder>,
+    action_dispatch_depth: usize,
+    key_modes: Rc<RefCell<KeyModeStack>>,
     prompt: Option<RenderablePromptHandle>,
     pub(crate) client_inset: Option<Pixels>,
     #[cfg(any(feature = "inspector", debug_assertions))]
@@ -921,6 +938,21 @@ struct PendingInput {
     focus: Option<FocusId>,
     timer: Option<Task<()>>,
     needs_timeout: bool,
+    timeout: Option<Duration>,
+}
+
+/// The keystrokes typed so far of a multi-stroke binding, such as `ctrl-k` of `ctrl-k ctrl-c`,
+/// along with the bindings they could complete. Emitted whenever they change, see
+/// [`Context::on_pending_input`].
+#[derive(Clone, Debug, Default)]
+pub struct PendingInputEvent {
+    /// The keystrokes typed so far, which is empty once they've been dispatched or cancelled
+    pub keystrokes: SmallVec<[Keystroke; 1]>,
+    /// The bindings that could fire when more keystrokes are typed, in precedence order
+    pub candidates: Vec<KeyBinding>,
+    /// How long after the last keystroke the keystrokes are dispatched on their own, if they are
+    /// at all. See [`App::set_multi_stroke_timeout`].
+    pub timeout: Option<Duration>,
 }
 
 pub(crate) struct ElementStateBox {
@@ -1270,6 +1302,7 @@ impl Window {
 
         platform_window.map_window().unwrap();
 
+        let key_modes = Rc::new(RefCell::new(KeyModeStack::default()));
         Ok(Window {
             handle,
             invalidator,
@@ -1290,8 +1323,18 @@ impl Window {
             content_mask_stack: Vec::new(),
             element_opacity: 1.0,
             requested_autoscroll: None,
-            rendered_frame: Frame::new(DispatchTree::new(cx.keymap.clone(), cx.actions.clone())),
-            next_frame: Frame::new(DispatchTree::new(cx.keymap.clone(), cx.actions.clone())),
+            frame_number: 0,
+            path_cache: PathCache::default(),
+            rendered_frame: Frame::new(DispatchTree::new(
+                cx.keymap.clone(),
+                key_modes.clone(),
+                cx.actions.clone(),
+            )),
+            next_frame: Frame::new(DispatchTree::new(
+                cx.keymap.clone(),
+                key_modes.clone(),
+                cx.actions.clone(),
+            )),
             next_frame_callbacks,
             next_hitbox_id: HitboxId(0),
             next_tooltip_id: TooltipId::default(),
@@ -1321,6 +1364,11 @@ impl Window {
             pending_input: None,
             pending_modifier: ModifierState::default(),
             pending_input_observers: SubscriberSet::new(),
+            pending_input_listeners: SubscriberSet::new(),
+            pending_input_reported: false,
+            macro_recorder: None,
+            action_dispatch_depth: 0,
+            key_modes,
             prompt: None,
             client_inset: None,
             image_cache_stack: Vec::new(),
@@ -1429,6 +1477,12 @@ impl Window {
         self.handle
     }
 
+    /// The number of frames that this window has started drawing, so that state can be tied to
+    /// the frame it was used in. During a draw, this is the number of the frame being drawn.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+
     /// Mark the window as dirty, scheduling it to be redrawn on the next frame.
     pub fn refresh(&mut self) {
         if self.invalidator.not_drawing() {
@@ -1456,6 +1510,7 @@ impl Window {
 
         self.focus = Some(handle.id);
         self.clear_pending_keystrokes();
+        self.clear_key_modes();
         self.refresh();
     }
 
@@ -1466,6 +1521,7 @@ impl Window {
         }
 
         self.focus = None;
+        self.clear_key_modes();
         self.refresh();
     }
 
@@ -1542,13 +1598,20 @@ impl Window {
     /// Dispatch the given action on the currently focused element.
     pub fn dispatch_action(&mut self, action: Box<dyn Action>, cx: &mut App) {
         let focus_id = self.focused(cx).map(|handle| handle.id);
+        // Actions dispatched while handling another action follow from it, so they're replayed
+        // by replaying it rather than recorded themselves.
+        let follows_action = self.action_dispatch_depth > 0;
 
         let window = self.handle;
         cx.defer(move |cx| {
             window
                 .update(cx, |_, window, cx| {
                     let node_id = window.focus_node_id_in_rendered_frame(focus_id);
-                    window.dispatch_action_on_node(node_id, action.as_ref(), cx);
+                    if follows_action {
+                        window.dispatch_action_on_node(node_id, action.as_ref(), cx);
+                    } else {
+                        window.dispatch_user_action(node_id, action.as_ref(), None, cx);
+                    }
                 })
                 .log_err();
         })
@@ -1843,6 +1906,29 @@ impl Window {
         self.platform_window.start_window_move()
     }
 
+    /// Hand the current mouse drag over to the platform so that the given data can be dropped
+    /// into other applications (Wayland and X11). Call this from an `on_drag` handler, while
+    /// the mouse button that started the drag is still held.
+    ///
+    /// The returned task resolves to the action chosen by the drop target, or
+    /// [`DropAction::None`] if the drag was cancelled or the platform doesn't support it.
+    /// Any in-app drag is stopped once the platform drag ends, since the mouse release is
+    /// delivered to the drop target rather than to this window.
+    pub fn start_external_drag(&mut self, drag: ExternalDrag, cx: &mut App) -> Task<DropAction> {
+        let receiver = self.platform_window.start_external_drag(drag);
+        self.spawn(cx, async move |cx| {
+            let action = match receiver {
+                Some(receiver) => receiver.await.unwrap_or_default(),
+                None => DropAction::None,
+            };
+            cx.update(|window, cx| {
+                cx.stop_active_drag(window);
+            })
+            .ok();
+            action
+        })
+    }
+
     /// When using client side decorations, set this to the width of the invisible decorations (Wayland and X11)
     pub fn set_client_inset(&mut self, inset: Pixels) {
         self.client_inset = Some(inset);
@@ -2016,6 +2102,8 @@ impl Window {
         debug_assert!(self.rendered_entity_stack.is_empty());
         self.invalidator.set_dirty(false);
         self.requested_autoscroll = None;
+        self.frame_number += 1;
+        self.path_cache.finish_frame();
 
         // Restore the previously-used input handler.
         if let Some(input_handler) = self.platform_window.take_input_handler() {
@@ -2071,6 +2159,12 @@ impl Window {
                 .retain(&(), |listener| listener(&event, self, cx));
         }
 
+        // Pending input that was cleared without an `App`, such as when focus moved, is
+        // reported as ended once the window draws.
+        if self.pending_input.is_none() && self.pending_input_reported {
+            self.pending_input_changed(cx);
+        }
+
         debug_assert!(self.rendered_entity_stack.is_empty());
         self.record_entities_accessed(cx);
         self.reset_cursor_style(cx);
@@ -2996,6 +3090,29 @@ impl Window {
             .insert_primitive(path.scale(scale_factor));
     }
 
+    /// Paint a [`CachedPath`] into the scene for the next frame at the current z-index, with the
+    /// given transform applied to it. The path is only tessellated if it wasn't painted in the
+    /// previous frame at the window's scale factor.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    pub fn paint_cached_path(
+        &mut self,
+        path: &CachedPath,
+        transform: Transform,
+        color: impl Into<Background>,
+    ) -> Result<()> {
+        self.invalidator.debug_assert_paint();
+
+        let scale_factor = self.scale_factor();
+        let tessellation = self.path_cache.get_or_tessellate(path, scale_factor)?;
+        let mut path = tessellation.transform_and_scale(&transform, scale_factor);
+        let color: Background = color.into();
+        path.content_mask = self.content_mask().scale(scale_factor);
+        path.color = color.opacity(self.element_opacity());
+        self.next_frame.scene.insert_primitive(path);
+        Ok(())
+    }
+
     /// Paint an underline into the scene for the next frame at the current z-index.
     ///
     /// This method should only be called as part of the paint phase of element drawing.
@@ -3191,29 +3308,58 @@ impl Window {
         &mut self,
         bounds: Bounds<Pixels>,
         path: SharedString,
-        mut data: Option<&[u8]>,
+        data: Option<&[u8]>,
         transformation: TransformationMatrix,
         color: Hsla,
         cx: &App,
+    ) -> Result<()> {
+        let style = SvgStyle {
+            color,
+            ..SvgStyle::default()
+        };
+        self.paint_styled_svg(bounds, path, data, transformation, &style, cx)
+    }
+
+    /// Paint an SVG into the scene for the next frame at the current stacking context, either as
+    /// a monochrome sprite tinted with the color of the style or, for full-color SVGs, with its
+    /// own colors. Full-color SVGs can't be transformed, and are painted without the
+    /// transformation.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    pub fn paint_styled_svg(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        path: SharedString,
+        mut data: Option<&[u8]>,
+        transformation: TransformationMatrix,
+        style: &SvgStyle,
+        cx: &App,
     ) -> Result<()> {
         self.invalidator.debug_assert_paint();
+        debug_assert!(
+            !style.full_color || transformation == TransformationMatrix::unit(),
+            "full-color SVGs can't be transformed"
+        );
 
         let element_opacity = self.element_opacity();
         let scale_factor = self.scale_factor();
 
         let bounds = bounds.scale(scale_factor);
         let params = RenderSvgParams {
+            version: cx.asset_versions.get(&path).copied().unwrap_or(0),
             path,
             size: bounds.size.map(|pixels| {
                 DevicePixels::from((pixels.0 * SMOOTH_SVG_SCALE_FACTOR).ceil() as i32)
             }),
+            full_color: style.full_color,
+            style_sheet: style.render_style_sheet(),
+            variables: style.variables.clone(),
         };
 
         let Some(tile) =
             self.sprite_atlas
                 .get_or_insert_with(&params.clone().into(), &mut || {
-                    let Some((size, bytes)) = cx.svg_renderer.render_alpha_mask(&params, data)?
-                    else {
+                    let Some((size, bytes)) = cx.svg_renderer.render_sprite(&params, data)? else {
                         return Ok(None);
                     };
                     Ok(Some((size, Cow::Owned(bytes))))
@@ -3233,18 +3379,32 @@ impl Window {
                 .size
                 .map(|value| ScaledPixels(value.0 as f32 / SMOOTH_SVG_SCALE_FACTOR)),
         };
+        let bounds = svg_bounds
+            .map_origin(|origin| origin.round())
+            .map_size(|size| size.ceil());
 
-        self.next_frame.scene.insert_primitive(MonochromeSprite {
-            order: 0,
-            pad: 0,
-            bounds: svg_bounds
-                .map_origin(|origin| origin.round())
-                .map_size(|size| size.ceil()),
-            content_mask,
-            color: color.opacity(element_opacity),
-            tile,
-            transformation,
-        });
+        if style.full_color {
+            self.next_frame.scene.insert_primitive(PolychromeSprite {
+                order: 0,
+                pad: 0,
+                grayscale: false,
+                bounds,
+                content_mask,
+                corner_radii: Corners::default(),
+                tile,
+                opacity: element_opacity,
+            });
+        } else {
+            self.next_frame.scene.insert_primitive(MonochromeSprite {
+                order: 0,
+                pad: 0,
+                bounds,
+                content_mask,
+                color: style.color.opacity(element_opacity),
+                tile,
+                transformation,
+            });
+        }
 
         Ok(())
     }
@@ -3917,6 +4077,7 @@ impl Window {
             return;
         }
 
+        let is_escape = keystroke.key == "escape" && !keystroke.modifiers.modified();
         let mut currently_pending = self.pending_input.take().unwrap_or_default();
         if currently_pending.focus.is_some() && currently_pending.focus != self.focus {
             currently_pending = PendingInput::default();
@@ -3952,8 +4113,10 @@ impl Window {
                 match_result.pending_has_binding || text_input_requires_timeout;
 
             if currently_pending.needs_timeout {
+                let timeout = cx.multi_stroke_timeout;
+                currently_pending.timeout = Some(timeout);
                 currently_pending.timer = Some(self.spawn(cx, async move |cx| {
-                    cx.background_executor.timer(Duration::from_secs(1)).await;
+                    cx.background_executor.timer(timeout).await;
                     cx.update(move |window, cx| {
                         let Some(currently_pending) = window
                             .pending_input
@@ -3979,6 +4142,7 @@ impl Window {
                 }));
             } else {
                 currently_pending.timer = None;
+                currently_pending.timeout = None;
             }
             self.pending_input = Some(currently_pending);
             self.pending_input_changed(cx);
@@ -4004,7 +4168,12 @@ impl Window {
 
         if !skip_bindings {
             for binding in match_result.bindings {
-                self.dispatch_action_on_node(node_id, binding.action.as_ref(), cx);
+                self.dispatch_user_action(
+                    node_id,
+                    binding.action.as_ref(),
+                    binding.action_input.as_ref(),
+                    cx,
+                );
                 if !cx.propagate_event {
                     self.dispatch_keystroke_observers(
                         event,
@@ -4018,6 +4187,19 @@ impl Window {
             }
         }
 
+        // Escape pops the topmost key mode when neither a binding nor a key listener handles it.
+        if is_escape {
+            self.dispatch_key_down_up_event(event, &dispatch_path, cx);
+            if cx.propagate_event {
+                if self.pop_key_mode_on_escape() {
+                    cx.propagate_event = false;
+                }
+                self.dispatch_keystroke_observers(event, None, match_result.context_stack, cx);
+            }
+            self.pending_input_changed(cx);
+            return;
+        }
+
         self.finish_dispatch_key_event(event, dispatch_path, match_result.context_stack, cx);
         self.pending_input_changed(cx);
     }
@@ -4046,6 +4228,32 @@ impl Window {
         self.pending_input_observers
             .clone()
             .retain(&(), |callback| callback(self, cx));
+
+        // Listeners are only told that there's no pending input once, when it ends.
+        let event = self.pending_input_event();
+        if event.keystrokes.is_empty() && !self.pending_input_reported {
+            return;
+        }
+        self.pending_input_reported = !event.keystrokes.is_empty();
+        self.pending_input_listeners
+            .clone()
+            .retain(&(), |listener| listener(&event, self, cx));
+    }
+
+    fn pending_input_event(&self) -> PendingInputEvent {
+        let Some(pending_input) = &self.pending_input else {
+            return PendingInputEvent::default();
+        };
+        let node_id = self.focus_node_id_in_rendered_frame(pending_input.focus);
+        let dispatch_path = self.rendered_frame.dispatch_tree.dispatch_path(node_id);
+        PendingInputEvent {
+            keystrokes: pending_input.keystrokes.clone(),
+            candidates: self
+                .rendered_frame
+                .dispatch_tree
+                .pending_bindings_for_input(&pending_input.keystrokes, &dispatch_path),
+            timeout: pending_input.timeout,
+        }
     }
 
     fn dispatch_key_down_up_event(
@@ -4104,8 +4312,95 @@ impl Window {
         self.pending_input.is_some()
     }
 
+    /// Discards the pending keystrokes where there's no `App` to tell listeners with, such as
+    /// when focus moves. They're told that the pending input ended when the window next draws,
+    /// so prefer [`Self::cancel_pending_input`] where there is one.
     pub(crate) fn clear_pending_keystrokes(&mut self) {
-        self.pending_input.take();
+        if self.pending_input.take().is_some() {
+            self.refresh();
+        }
+    }
+
+    /// Discard the keystrokes typed so far of a multi-stroke key binding, without dispatching
+    /// them, so that the next keystroke starts a new binding.
+    pub fn cancel_pending_input(&mut self, cx: &mut App) {
+        if self.pending_input.take().is_some() {
+            self.pending_input_changed(cx);
+        }
+    }
+
+    /// Start recording the actions handled in this window and the text typed into it, until
+    /// [`Self::stop_recording_macro`] is called. Only actions that the user asked for are
+    /// recorded, and not those dispatched while handling them, since replaying the first
+    /// dispatches the others again. Recording again discards the steps recorded so far.
+    pub fn start_recording_macro(&mut self) {
+        self.macro_recorder = Some(MacroRecorder::default());
+    }
+
+    /// Whether a macro is being recorded in this window, see [`Self::start_recording_macro`].
+    pub fn is_recording_macro(&self) -> bool {
+        self.macro_recorder.is_some()
+    }
+
+    /// Stop recording a macro, returning what was recorded if a macro was being recorded.
+    pub fn stop_recording_macro(&mut self) -> Option<ActionMacro> {
+        self.macro_recorder.take().map(MacroRecorder::finish)
+    }
+
+    /// Replay a macro on the focused element, `repeat` times. Each step is replayed once the
+    /// window reflects the previous one, as typed input would be, letting other tasks run in
+    /// between. If any of the macro's actions can't be built, nothing is replayed.
+    ///
+    /// The macro is replayed until the returned task is dropped.
+    pub fn replay_macro(
+        &mut self,
+        action_macro: &ActionMacro,
+        repeat: usize,
+        cx: &mut App,
+    ) -> std::result::Result<Task<()>, ActionBuildError> {
+        let actions = action_macro.build_actions(cx)?;
+        let steps = action_macro.steps.clone();
+        Ok(self.spawn(cx, async move |cx| {
+            for _ in 0..repeat {
+                for (step, action) in steps.iter().zip(&actions) {
+                    let replayed = cx
+                        .update(|window, cx| window.replay_macro_step(step, action.as_deref(), cx));
+                    if replayed.is_err() {
+                        return;
+                    }
+                    crate::executor::yield_now().await;
+                }
+            }
+        }))
+    }
+
+    fn replay_macro_step(&mut self, step: &MacroStep, action: Option<&dyn Action>, cx: &mut App) {
+        if self.invalidator.is_dirty() {
+            self.draw(cx).clear();
+        }
+
+        if let Some(action) = action {
+            let node_id = self.focus_node_id_in_rendered_frame(self.focus);
+            self.dispatch_action_on_node(node_id, action, cx);
+            // Steps replayed while recording are part of the new macro.
+            if !cx.propagate_event
+                && let Some(recorder) = &mut self.macro_recorder
+            {
+                recorder.push_step(step.clone());
+            }
+        } else if let MacroStep::Text(text) = step
+            && let Some(mut input_handler) = self.platform_window.take_input_handler()
+        {
+            input_handler.dispatch_input(text, self, cx);
+            self.platform_window.set_input_handler(input_handler);
+        }
+    }
+
+    /// Records text typed into the focused element, if a macro is being recorded.
+    pub(crate) fn record_text_input(&mut self, text: &str) {
+        if let Some(recorder) = &mut self.macro_recorder {
+            recorder.push_step(MacroStep::Text(text.to_string()));
+        }
     }
 
     /// Returns the currently pending input keystrokes that might result in a multi-stroke key binding.
@@ -4128,7 +4423,12 @@ impl Window {
 
             cx.propagate_event = true;
             for binding in replay.bindings {
-                self.dispatch_action_on_node(node_id, binding.action.as_ref(), cx);
+                self.dispatch_user_action(
+                    node_id,
+                    binding.action.as_ref(),
+                    binding.action_input.as_ref(),
+                    cx,
+                );
                 if !cx.propagate_event {
                     self.dispatch_keystroke_observers(
                         &event,
@@ -4163,12 +4463,43 @@ impl Window {
             .unwrap_or_else(|| self.rendered_frame.dispatch_tree.root_node_id())
     }
 
+    /// Dispatches an action that the user asked for, recording it into the macro being recorded
+    /// if it's handled.
+    fn dispatch_user_action(
+        &mut self,
+        node_id: DispatchNodeId,
+        action: &dyn Action,
+        action_input: Option<&SharedString>,
+        cx: &mut App,
+    ) {
+        // The action that starts recording isn't part of the macro.
+        let recording = self.macro_recorder.is_some() && self.action_dispatch_depth == 0;
+        // Nor does the action that pushes a mode complete it.
+        let next_key_mode_id = self.next_key_mode_id();
+        self.dispatch_action_on_node(node_id, action, cx);
+        if cx.propagate_event {
+            return;
+        }
+        if recording && let Some(recorder) = &mut self.macro_recorder {
+            recorder.push_action(action, action_input, cx);
+        }
+        if self.action_dispatch_depth == 0 {
+            self.pop_key_modes_after_action(next_key_mode_id);
+        }
+    }
+
     fn dispatch_action_on_node(
         &mut self,
         node_id: DispatchNodeId,
         action: &dyn Action,
         cx: &mut App,
     ) {
+        self.action_dispatch_depth += 1;
+        self.run_action_listeners(node_id, action, cx);
+        self.action_dispatch_depth -= 1;
+    }
+
+    fn run_action_listeners(&mut self, node_id: DispatchNodeId, action: &dyn Action, cx: &mut App) {
         let dispatch_path = self.rendered_frame.dispatch_tree.dispatch_path(node_id);
 
         // Capture phase for global actions.
@@ -4366,15 +4697,12 @@ impl Window {
         receiver
     }
 
-    /// Returns the current context stack.
+    /// Returns the current context stack, including the contexts of the pushed key modes above
+    /// those of the focused element.
     pub fn context_stack(&self) -> Vec<KeyContext> {
         let node_id = self.focus_node_id_in_rendered_frame(self.focus);
         let dispatch_tree = &self.rendered_frame.dispatch_tree;
-        dispatch_tree
-            .dispatch_path(node_id)
-            .iter()
-            .filter_map(move |&node_id| dispatch_tree.node(node_id).context.clone())
-            .collect()
+        dispatch_tree.context_stack_for_path(&dispatch_tree.dispatch_path(node_id))
     }
 
     /// Returns all available actions for the focused element.
@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Action, ActionBuildError, App, SharedString};
+
+/// A sequence of actions and text input recorded in a window, which can be replayed later. See
+/// [`crate::Window::start_recording_macro`].
+///
+/// Macros serialize to JSON, with actions given by their name and arguments as they would be in
+/// a keymap, so they can be saved or attached to bug reports.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionMacro {
+    /// The steps of the macro, in the order they were recorded
+    pub steps: Vec<MacroStep>,
+}
+
+/// A step of an [`ActionMacro`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroStep {
+    /// An action that was dispatched and handled
+    Action {
+        /// The name of the action
+        name: String,
+        /// The arguments that the action is built with, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arguments: Option<serde_json::Value>,
+    },
+    /// Text that was typed into the focused element
+    Text(String),
+}
+
+impl ActionMacro {
+    /// Builds the actions of this macro, in the order of its steps, failing if any of them isn't
+    /// registered or can't be built from its arguments.
+    pub(crate) fn build_actions(
+        &self,
+        cx: &App,
+    ) -> Result<Vec<Option<Box<dyn Action>>>, ActionBuildError> {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                MacroStep::Action { name, arguments } => {
+                    cx.build_action(name, arguments.clone()).map(Some)
+                }
+                MacroStep::Text(_) => Ok(None),
+            })
+            .collect()
+    }
+}
+
+/// The steps recorded so far by [`crate::Window::start_recording_macro`].
+#[derive(Default)]
+pub(crate) struct MacroRecorder {
+    steps: Vec<MacroStep>,
+}
+
+impl MacroRecorder {
+    /// Records an action that was handled, along with the JSON it was built from when it was
+    /// dispatched by a key binding. Actions that can't be rebuilt from their name and arguments
+    /// are skipped, since they couldn't be replayed.
+    pub fn push_action(
+        &mut self,
+        action: &dyn Action,
+        action_input: Option<&SharedString>,
+        cx: &App,
+    ) {
+        let arguments = action_input.and_then(|input| serde_json::from_str(input).ok());
+        let replayable = cx
+            .build_action(action.name(), arguments.clone())
+            .is_ok_and(|rebuilt| rebuilt.partial_eq(action));
+        if !replayable {
+            log::warn!(
+                "not recording {} in macro, because it can't be rebuilt from its name and arguments",
+                action.name()
+            );
+            return;
+        }
+        self.push_step(MacroStep::Action {
+            name: action.name().to_string(),
+            arguments,
+        });
+    }
+
+    /// Records a step, appending text to the previous step when both are text.
+    pub fn push_step(&mut self, step: MacroStep) {
+        if let MacroStep::Text(text) = &step
+            && let Some(MacroStep::Text(last_text)) = self.steps.last_mut()
+        {
+            last_text.push_str(text);
+            return;
+        }
+        self.steps.push(step);
+    }
+
+    pub fn finish(self) -> ActionMacro {
+        ActionMacro { steps: self.steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        self as gpui, AppContext as _, Context, FocusHandle, InteractiveElement, IntoElement,
+        Keymap, Render, TestAppContext, Window, actions, div,
+    };
+    use schemars::JsonSchema;
+
+    actions!(test_only, [IncrementCount]);
+
+    #[derive(Clone, Debug, PartialEq, Deserialize, JsonSchema, Action)]
+    #[action(namespace = test_only)]
+    struct AddToCount {
+        amount: u32,
+    }
+
+    struct Counter {
+        count: u32,
+        focus_handle: FocusHandle,
+    }
+
+    impl Render for Counter {
+        fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            div()
+                .key_context("Counter")
+                .track_focus(&self.focus_handle)
+                .on_action(cx.listener(|this, _: &IncrementCount, _, _| this.count += 1))
+                .on_action(
+                    cx.listener(|this, action: &AddToCount, _, _| this.count += action.amount),
+                )
+        }
+    }
+
+    #[gpui::test]
+    fn test_record_and_replay(cx: &mut TestAppContext) {
+        let window = cx.update(|cx| {
+            let keymap = Keymap::load_from_json(
+                r#"[{
+                    "context": "Counter",
+                    "bindings": {
+                        "ctrl-i": "test_only::IncrementCount",
+                        "ctrl-a": ["test_only::AddToCount", { "amount": 5 }]
+                    }
+                }]"#,
+                cx,
+            );
+            assert!(keymap.errors.is_empty());
+            cx.bind_keys(keymap.bindings);
+            cx.open_window(Default::default(), |_, cx| {
+                cx.new(|cx| Counter {
+                    count: 0,
+                    focus_handle: cx.focus_handle(),
+                })
+            })
+            .unwrap()
+        });
+        window
+            .update(cx, |counter, window, _| {
+                window.focus(&counter.focus_handle);
+                window.start_recording_macro();
+            })
+            .unwrap();
+
+        cx.simulate_keystrokes(*window, "ctrl-i ctrl-a");
+        let action_macro = window
+            .update(cx, |counter, window, _| {
+                assert_eq!(counter.count, 6);
+                window.stop_recording_macro().unwrap()
+            })
+            .unwrap();
+        assert_eq!(
+            action_macro.steps,
+            [
+                MacroStep::Action {
+                    name: "test_only::IncrementCount".to_string(),
+                    arguments: None,
+                },
+                MacroStep::Action {
+                    name: "test_only::AddToCount".to_string(),
+                    arguments: Some(serde_json::json!({ "amount": 5 })),
+                },
+            ]
+        );
+
+        window
+            .update(cx, |_, window, cx| {
+                window.replay_macro(&action_macro, 2, cx).unwrap().detach()
+            })
+            .unwrap();
+        cx.run_until_parked();
+        window
+            .update(cx, |counter, _, _| assert_eq!(counter.count, 18))
+            .unwrap();
+
+        // Replaying yields between steps, so it stops partway through once its task is dropped.
+        let task = window
+            .update(cx, |_, window, cx| {
+                window.replay_macro(&action_macro, 100, cx).unwrap()
+            })
+            .unwrap();
+        let count = |cx: &mut TestAppContext| window.update(cx, |counter, _, _| counter.count);
+        while count(cx).unwrap() == 18 {
+            assert!(cx.executor().tick());
+        }
+        drop(task);
+        cx.run_until_parked();
+        assert!(count(cx).unwrap() < 18 + 100 * 6);
+    }
+
+    #[test]
+    fn test_macro_serialization() {
+        let mut recorder = MacroRecorder::default();
+        recorder.push_step(MacroStep::Action {
+            name: "editor::MoveDown".to_string(),
+            arguments: None,
+        });
+        recorder.push_step(MacroStep::Text("he".to_string()));
+        recorder.push_step(MacroStep::Text("llo".to_string()));
+        recorder.push_step(MacroStep::Action {
+            name: "editor::Scroll".to_string(),
+            arguments: Some(serde_json::json!({ "lines": 2 })),
+        });
+        let action_macro = recorder.finish();
+
+        let json = serde_json::to_string(&action_macro).unwrap();
+        assert_eq!(
+            json,
+            r#"{"steps":[{"action":{"name":"editor::MoveDown"}},{"text":"hello"},{"action":{"name":"editor::Scroll","arguments":{"lines":2}}}]}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ActionMacro>(&json).unwrap(),
+            action_macro
+        );
+    }
+}
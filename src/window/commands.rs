@@ -0,0 +1,371 @@
+use std::cmp::Reverse;
+
+use crate::{Action, App, KeyBinding, SharedString, Window};
+
+/// An action that can be dispatched to the focused element, along with what a command palette
+/// would show for it. See [`Window::available_commands`].
+#[derive(Debug)]
+pub struct AvailableCommand {
+    /// The action, built with its default arguments
+    pub action: Box<dyn Action>,
+    /// A name for the action that's suitable for display, see [`humanize_action_name`]
+    pub name: SharedString,
+    /// The documentation of the action, from its doc comment
+    pub documentation: Option<&'static str>,
+    /// Why the action is deprecated, if it is
+    pub deprecation_message: Option<&'static str>,
+    /// The deprecated names of the action, which can still be used to refer to it
+    pub deprecated_aliases: Vec<&'static str>,
+    /// The key bindings for the action on the focused element, highest precedence first
+    pub bindings: Vec<KeyBinding>,
+}
+
+impl Window {
+    /// Returns the actions that can be dispatched to the focused element, as a command palette
+    /// would list them, sorted by name. Use [`match_commands`] to filter them with a query.
+    pub fn available_commands(&self, cx: &App) -> Vec<AvailableCommand> {
+        let context_stack = self.context_stack();
+        let dispatch_tree = &self.rendered_frame.dispatch_tree;
+        let mut commands = self
+            .available_actions(cx)
+            .into_iter()
+            .map(|action| {
+                let action_name = action.name();
+                let mut bindings =
+                    dispatch_tree.bindings_for_action(action.as_ref(), &context_stack);
+                bindings.reverse();
+                let mut deprecated_aliases = cx
+                    .deprecated_actions_to_preferred_actions()
+                    .iter()
+                    .filter(|(_, preferred)| **preferred == action_name)
+                    .map(|(alias, _)| *alias)
+                    .collect::<Vec<_>>();
+                deprecated_aliases.sort_unstable();
+                AvailableCommand {
+                    name: humanize_action_name(action_name).into(),
+                    documentation: cx.action_documentation().get(action_name).copied(),
+                    deprecation_message: cx.action_deprecation_messages().get(action_name).copied(),
+                    deprecated_aliases,
+                    bindings,
+                    action,
+                }
+            })
+            .collect::<Vec<_>>();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        commands
+    }
+}
+
+/// Turns the name of an action into one suitable for display, such as `editor: toggle comments`
+/// for `editor::ToggleComments`.
+pub fn humanize_action_name(name: &str) -> String {
+    let (namespace, action) = name.rsplit_once("::").unwrap_or(("", name));
+
+    let mut humanized = String::with_capacity(name.len() + 4);
+    if !namespace.is_empty() {
+        humanized.push_str(&namespace.replace("::", ": ").replace('_', " "));
+        humanized.push_str(": ");
+    }
+
+    let chars = action.chars().collect::<Vec<_>>();
+    for (ix, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            humanized.push(' ');
+            continue;
+        }
+        if ix > 0 && c.is_uppercase() {
+            let previous = chars[ix - 1];
+            let next_is_lowercase = chars.get(ix + 1).is_some_and(|next| next.is_lowercase());
+            // Acronyms stay together, as in `OpenURL` and `URLEncode`.
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lowercase)
+            {
+                humanized.push(' ');
+            }
+        }
+        humanized.extend(c.to_lowercase());
+    }
+    humanized
+}
+
+/// A command that matched a query, see [`match_commands`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandMatch {
+    /// The index of the command in those that were matched
+    pub index: usize,
+    /// How well the command matched, see [`fuzzy_match`]
+    pub score: i32,
+    /// The byte offsets of the characters of the command's name that matched the query
+    pub positions: Vec<usize>,
+}
+
+/// Matches commands by their name against a query with [`fuzzy_match`], returning the matches
+/// from best to worst. All commands match an empty query, in their original order.
+pub fn match_commands(commands: &[AvailableCommand], query: &str) -> Vec<CommandMatch> {
+    if query.chars().all(char::is_whitespace) {
+        return (0..commands.len())
+            .map(|index| CommandMatch {
+                index,
+                score: 0,
+                positions: Vec::new(),
+            })
+            .collect();
+    }
+
+    let mut matches = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(index, command)| {
+            let FuzzyMatch { score, positions } = fuzzy_match(query, &command.name)?;
+            Some(CommandMatch {
+                index,
+                score,
+                positions,
+            })
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|m| (Reverse(m.score), commands[m.index].name.len(), m.index));
+    matches
+}
+
+/// How a query matched a string, see [`fuzzy_match`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// How well the query matched, where higher is better
+    pub score: i32,
+    /// The byte offsets of the characters of the string that matched the query
+    pub positions: Vec<usize>,
+}
+
+const MATCH_SCORE: i32 = 16;
+const WORD_START_BONUS: i32 = 8;
+const CONSECUTIVE_BONUS: i32 = 8;
+const GAP_PENALTY: i32 = 1;
+
+/// Matches a query against a string, if the characters of the query appear in it in order,
+/// ignoring case and the whitespace in the query. Matches score higher when the characters are
+/// consecutive or start words, and lower when there's more of the string between them. Of the
+/// ways the query can match, the one with the best score is returned.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let chars = candidate.char_indices().collect::<Vec<_>>();
+    let (m, n) = (query.len(), chars.len());
+    if m > n {
+        return None;
+    }
+
+    let lowercase = chars
+        .iter()
+        .map(|(_, c)| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+    let bonuses = (0..n)
+        .map(|j| {
+            let c = chars[j].1;
+            let starts_word = match j.checked_sub(1).map(|j| chars[j].1) {
+                None => true,
+                Some(previous) => {
+                    !previous.is_alphanumeric() || (previous.is_lowercase() && c.is_uppercase())
+                }
+            };
+            if starts_word { WORD_START_BONUS } else { 0 }
+        })
+        .collect::<Vec<_>>();
+
+    // The best score of matching the first `i + 1` characters of the query with the last one at
+    // `j`, and where the previous one was matched, at `i * n + j`.
+    let mut scores = vec![None::<i32>; m * n];
+    let mut previous = vec![0; m * n];
+    for i in 0..m {
+        // The best score of the previous character at least two characters back, before
+        // subtracting the gap to `j`.
+        let mut best_before_gap = None::<(i32, usize)>;
+        for j in i..n {
+            if i > 0
+                && j >= 2
+                && let Some(score) = scores[(i - 1) * n + j - 2]
+            {
+                let score = score + GAP_PENALTY * (j as i32 - 1);
+                if best_before_gap.is_none_or(|(best, _)| score > best) {
+                    best_before_gap = Some((score, j - 2));
+                }
+            }
+            if lowercase[j] != query[i] {
+                continue;
+            }
+
+            let score = MATCH_SCORE + bonuses[j];
+            if i == 0 {
+                scores[j] = Some(score);
+                continue;
+            }
+            let consecutive = scores[(i - 1) * n + j - 1].map(|s| (s + CONSECUTIVE_BONUS, j - 1));
+            let gapped = best_before_gap.map(|(s, k)| (s - GAP_PENALTY * j as i32, k));
+            let best = match (consecutive, gapped) {
+                (Some(a), Some(b)) => Some(if b.0 > a.0 { b } else { a }),
+                (a, b) => a.or(b),
+            };
+            if let Some((best, k)) = best {
+                scores[i * n + j] = Some(score + best);
+                previous[i * n + j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = (0..n)
+        .filter_map(|j| Some((j, scores[(m - 1) * n + j]?)))
+        .max_by_key(|(j, score)| (*score, Reverse(*j)))?;
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = chars[j].0;
+        j = previous[i * n + j];
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        self as gpui, AppContext as _, Context, FocusHandle, InteractiveElement, IntoElement,
+        Render, TestAppContext, actions, div,
+    };
+
+    actions!(
+        test_only,
+        [
+            /// Opens a document.
+            OpenDocument,
+            CloseDocument,
+            ArchiveDocument
+        ]
+    );
+
+    struct Documents {
+        focus_handle: FocusHandle,
+    }
+
+    impl Render for Documents {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div()
+                .key_context("Documents")
+                .track_focus(&self.focus_handle)
+                .on_action(|_: &OpenDocument, _, _| {})
+                .on_action(|_: &CloseDocument, _, _| {})
+        }
+    }
+
+    #[gpui::test]
+    fn test_available_commands(cx: &mut TestAppContext) {
+        let window = cx.update(|cx| {
+            cx.bind_keys([
+                KeyBinding::new("ctrl-o", OpenDocument, Some("Documents")),
+                KeyBinding::new("ctrl-shift-o", OpenDocument, None),
+                KeyBinding::new("ctrl-a", ArchiveDocument, None),
+            ]);
+            cx.open_window(Default::default(), |_, cx| {
+                cx.new(|cx| Documents {
+                    focus_handle: cx.focus_handle(),
+                })
+            })
+            .unwrap()
+        });
+        window
+            .update(cx, |documents, window, _| {
+                window.focus(&documents.focus_handle)
+            })
+            .unwrap();
+        cx.run_until_parked();
+
+        window
+            .update(cx, |_, window, cx| {
+                let commands = window.available_commands(cx);
+                let names = commands
+                    .iter()
+                    .map(|command| command.name.as_ref())
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    names,
+                    ["test only: close document", "test only: open document"]
+                );
+
+                let open = &commands[1];
+                assert!(open.action.partial_eq(&OpenDocument));
+                assert_eq!(open.documentation, Some("Opens a document."));
+                let keystrokes = open
+                    .bindings
+                    .iter()
+                    .map(|binding| binding.keystrokes()[0].unparse())
+                    .collect::<Vec<_>>();
+                assert_eq!(keystrokes, ["ctrl-shift-o", "ctrl-o"]);
+
+                let matches = match_commands(&commands, "open");
+                assert_eq!(matches.len(), 1);
+                assert_eq!(matches[0].index, 1);
+
+                // Every command matches an empty query, in their original order rather than
+                // by the length of their names.
+                for query in ["", " "] {
+                    let indices = match_commands(&commands, query)
+                        .iter()
+                        .map(|m| m.index)
+                        .collect::<Vec<_>>();
+                    assert_eq!(indices, [0, 1]);
+                }
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_humanize_action_name() {
+        assert_eq!(
+            humanize_action_name("editor::ToggleComments"),
+            "editor: toggle comments"
+        );
+        assert_eq!(
+            humanize_action_name("git_panel::OpenURL"),
+            "git panel: open url"
+        );
+        assert_eq!(humanize_action_name("zed::URLEncode2"), "zed: url encode2");
+        assert_eq!(
+            humanize_action_name("vim::normal::MoveUp"),
+            "vim: normal: move up"
+        );
+        assert_eq!(humanize_action_name("Quit"), "quit");
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_match("xyz", "editor: toggle comments"), None);
+        assert_eq!(fuzzy_match("tc", "ab"), None);
+
+        // Word starts are preferred over earlier matches.
+        let toggle_comments = fuzzy_match("tc", "editor: toggle comments").unwrap();
+        assert_eq!(toggle_comments.positions, [8, 15]);
+
+        // Consecutive matches are preferred over word starts that are far apart.
+        let comments = fuzzy_match("com", "editor: toggle comments").unwrap();
+        assert_eq!(comments.positions, [15, 16, 17]);
+
+        // Whitespace in the query is ignored, and case doesn't matter.
+        let query_with_space = fuzzy_match("Tog Com", "editor: toggle comments").unwrap();
+        assert_eq!(query_with_space.positions, [8, 9, 10, 15, 16, 17]);
+
+        assert!(
+            fuzzy_match("open", "file: open").unwrap().score
+                > fuzzy_match("open", "editor: copy entire line")
+                    .unwrap()
+                    .score
+        );
+    }
+}
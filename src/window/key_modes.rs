@@ -0,0 +1,289 @@
+use crate::{KeyContext, SharedString, Window};
+
+/// A mode that changes which key bindings apply while it's pushed, such as the modes of a modal
+/// editor. See [`Window::push_key_mode`].
+///
+/// A mode's context is layered above the contexts of the focused element, so bindings can match
+/// on it with predicates like `mode == operator_pending`, or `Editor > mode == operator_pending`
+/// to only apply in some elements. Since modes are pushed onto a stack, each above the last, the
+/// bindings of the topmost mode take precedence over those of the modes below it, which still
+/// apply for keystrokes that the topmost mode doesn't bind.
+#[derive(Clone, Debug)]
+pub struct KeyMode {
+    name: SharedString,
+    context: KeyContext,
+    pop_after_action: bool,
+    pop_on_escape: bool,
+}
+
+impl KeyMode {
+    /// Create a mode with the given name, whose context has `mode` set to that name. The mode is
+    /// popped when escape is pressed, unless [`Self::keep_on_escape`] is called.
+    pub fn new(name: impl Into<SharedString>) -> Self {
+        let name = name.into();
+        let mut context = KeyContext::default();
+        context.set("mode", name.clone());
+        Self {
+            name,
+            context,
+            pop_after_action: false,
+            pop_on_escape: true,
+        }
+    }
+
+    /// Set a key value pair in the mode's context, such as the operator that's pending.
+    pub fn set(mut self, key: impl Into<SharedString>, value: impl Into<SharedString>) -> Self {
+        self.context.set(key, value);
+        self
+    }
+
+    /// Pop the mode once the next action that the user asks for is handled, as when an operator
+    /// completes. The action that pushes the mode doesn't pop it.
+    pub fn pop_after_action(mut self) -> Self {
+        self.pop_after_action = true;
+        self
+    }
+
+    /// Don't pop the mode when escape is pressed.
+    pub fn keep_on_escape(mut self) -> Self {
+        self.pop_on_escape = false;
+        self
+    }
+
+    /// The name of the mode.
+    pub fn name(&self) -> &SharedString {
+        &self.name
+    }
+
+    /// The context that the mode adds above the focused element.
+    pub fn context(&self) -> &KeyContext {
+        &self.context
+    }
+}
+
+/// The modes pushed in a window, shared with its dispatch trees so that they can match key
+/// bindings against them.
+#[derive(Default)]
+pub(crate) struct KeyModeStack {
+    modes: Vec<(usize, KeyMode)>,
+    next_id: usize,
+}
+
+impl KeyModeStack {
+    /// The contexts of the modes, from the bottom of the stack to the top.
+    pub fn contexts(&self) -> impl Iterator<Item = &KeyContext> {
+        self.modes.iter().map(|(_, mode)| &mode.context)
+    }
+}
+
+impl Window {
+    /// Push a mode onto this window's stack of modes, layering its context above the focused
+    /// element's so that bindings for the mode apply. Modes are popped when they're done, as
+    /// [`KeyMode`] describes, or with [`Self::pop_key_mode`], and all of them are popped when
+    /// focus moves to another element.
+    pub fn push_key_mode(&mut self, mode: KeyMode) {
+        let mut key_modes = self.key_modes.borrow_mut();
+        let id = key_modes.next_id;
+        key_modes.next_id += 1;
+        key_modes.modes.push((id, mode));
+        drop(key_modes);
+        self.key_modes_changed();
+    }
+
+    /// Pop the topmost mode off this window's stack of modes, returning it if there was one.
+    pub fn pop_key_mode(&mut self) -> Option<KeyMode> {
+        let (_, mode) = self.key_modes.borrow_mut().modes.pop()?;
+        self.key_modes_changed();
+        Some(mode)
+    }
+
+    /// Pop all of the modes off this window's stack of modes.
+    pub fn clear_key_modes(&mut self) {
+        let mut key_modes = self.key_modes.borrow_mut();
+        if key_modes.modes.is_empty() {
+            return;
+        }
+        key_modes.modes.clear();
+        drop(key_modes);
+        self.key_modes_changed();
+    }
+
+    /// The modes pushed in this window, from the bottom of the stack to the top.
+    pub fn key_modes(&self) -> Vec<KeyMode> {
+        let key_modes = self.key_modes.borrow();
+        key_modes
+            .modes
+            .iter()
+            .map(|(_, mode)| mode.clone())
+            .collect()
+    }
+
+    /// The topmost mode pushed in this window, if any.
+    pub fn active_key_mode(&self) -> Option<KeyMode> {
+        let key_modes = self.key_modes.borrow();
+        key_modes.modes.last().map(|(_, mode)| mode.clone())
+    }
+
+    /// The ID that the next pushed mode will have, so that the modes pushed before some point can
+    /// be told apart from those pushed after it.
+    pub(crate) fn next_key_mode_id(&self) -> usize {
+        self.key_modes.borrow().next_id
+    }
+
+    /// Pops the modes that pop after an action, and that were pushed before the mode with the
+    /// given ID would have been.
+    pub(crate) fn pop_key_modes_after_action(&mut self, pushed_before: usize) {
+        let mut key_modes = self.key_modes.borrow_mut();
+        let len = key_modes.modes.len();
+        key_modes
+            .modes
+            .retain(|(id, mode)| !(mode.pop_after_action && *id < pushed_before));
+        let changed = key_modes.modes.len() != len;
+        drop(key_modes);
+        if changed {
+            self.key_modes_changed();
+        }
+    }
+
+    /// Pops the topmost mode if escape pops it, returning whether it did.
+    pub(crate) fn pop_key_mode_on_escape(&mut self) -> bool {
+        let pops = self
+            .key_modes
+            .borrow()
+            .modes
+            .last()
+            .is_some_and(|(_, mode)| mode.pop_on_escape);
+        if pops {
+            self.pop_key_mode();
+        }
+        pops
+    }
+
+    fn key_modes_changed(&mut self) {
+        // Bindings that were pending may no longer apply.
+        self.clear_pending_keystrokes();
+        self.refresh();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        self as gpui, AppContext as _, Context, FocusHandle, InteractiveElement, IntoElement,
+        KeyBinding, KeyDownEvent, Render, TestAppContext, actions, div,
+    };
+
+    actions!(test_only, [StartDelete, DeleteWord, MoveWord, EnterInsert]);
+
+    struct ModalEditor {
+        log: Vec<&'static str>,
+        focus_handle: FocusHandle,
+        /// Whether the editor's key listener handles escape, as a popup would
+        handles_escape: bool,
+    }
+
+    impl Render for ModalEditor {
+        fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            div()
+                .key_context("ModalEditor")
+                .track_focus(&self.focus_handle)
+                .on_key_down(cx.listener(|this, event: &KeyDownEvent, _, cx| {
+                    if event.keystroke.key == "escape" {
+                        this.log.push("escape");
+                        if this.handles_escape {
+                            cx.stop_propagation();
+                        }
+                    }
+                }))
+                .on_action(cx.listener(|this, _: &StartDelete, window, _| {
+                    this.log.push("start delete");
+                    window.push_key_mode(
+                        KeyMode::new("operator_pending")
+                            .set("operator", "d")
+                            .pop_after_action(),
+                    );
+                }))
+                .on_action(cx.listener(|this, _: &DeleteWord, _, _| this.log.push("delete word")))
+                .on_action(cx.listener(|this, _: &MoveWord, _, _| this.log.push("move word")))
+                .on_action(cx.listener(|this, _: &EnterInsert, window, _| {
+                    this.log.push("insert");
+                    window.push_key_mode(KeyMode::new("insert"));
+                }))
+        }
+    }
+
+    #[gpui::test]
+    fn test_key_modes(cx: &mut TestAppContext) {
+        let window = cx.update(|cx| {
+            cx.bind_keys([
+                KeyBinding::new("d", StartDelete, Some("ModalEditor")),
+                KeyBinding::new("w", MoveWord, Some("ModalEditor")),
+                KeyBinding::new("i", EnterInsert, Some("ModalEditor")),
+                KeyBinding::new("w", DeleteWord, Some("ModalEditor > operator == d")),
+            ]);
+            cx.open_window(Default::default(), |_, cx| {
+                cx.new(|cx| ModalEditor {
+                    log: Vec::new(),
+                    focus_handle: cx.focus_handle(),
+                    handles_escape: false,
+                })
+            })
+            .unwrap()
+        });
+        window
+            .update(cx, |editor, window, _| window.focus(&editor.focus_handle))
+            .unwrap();
+        cx.run_until_parked();
+
+        // The mode pops once the operator completes.
+        cx.simulate_keystrokes(*window, "w d w w");
+        window
+            .update(cx, |editor, window, _| {
+                assert_eq!(
+                    editor.log,
+                    ["move word", "start delete", "delete word", "move word"]
+                );
+                assert!(window.active_key_mode().is_none());
+                editor.log.clear();
+            })
+            .unwrap();
+
+        // Escape pops the mode without completing the operator, after key listeners see it.
+        cx.simulate_keystrokes(*window, "d escape w");
+        window
+            .update(cx, |editor, window, _| {
+                assert_eq!(editor.log, ["start delete", "escape", "move word"]);
+                assert!(window.active_key_mode().is_none());
+                editor.log.clear();
+                editor.handles_escape = true;
+            })
+            .unwrap();
+
+        // Key listeners that handle escape keep the mode.
+        cx.simulate_keystrokes(*window, "d escape w");
+        window
+            .update(cx, |editor, window, _| {
+                assert_eq!(editor.log, ["start delete", "escape", "delete word"]);
+                assert!(window.active_key_mode().is_none());
+                editor.log.clear();
+            })
+            .unwrap();
+
+        // Modes that don't pop after an action stay until they're popped.
+        cx.simulate_keystrokes(*window, "i w");
+        window
+            .update(cx, |editor, window, _| {
+                assert_eq!(editor.log, ["insert", "move word"]);
+                let mode = window.active_key_mode().unwrap();
+                assert_eq!(mode.name().as_ref(), "insert");
+                assert_eq!(window.context_stack().last(), Some(mode.context()));
+            })
+            .unwrap();
+
+        window.update(cx, |_, window, _| window.blur()).unwrap();
+        window
+            .update(cx, |_, window, _| assert!(window.key_modes().is_empty()))
+            .unwrap();
+    }
+}